@@ -0,0 +1,239 @@
+//! run a saved board without a window and print a JSON summary of the run.
+//!
+//! usage: server_sim_batch <board.json|diagram.dot|spec.yaml> [scenario.json] [--ticks N] [--seed N]
+//!
+//! the board can also be a Graphviz `.dot` diagram, or a `.yaml` or `.toml`
+//! architecture spec, laid out on a flat board.
+//!
+//! exits with 1 if an SLA target was breached at any point, 2 on bad input.
+
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use serde::Serialize;
+use server_sim::game::blueprint::Blueprint;
+use server_sim::game::replay::{ActionLog, LoggedAction, Replay};
+use server_sim::game::resources::{Economy, RenderAssets};
+use server_sim::game::scenario::Scenario;
+use server_sim::game::spec::ArchitectureSpec;
+use server_sim::game::terrain::Terrain;
+use server_sim::game::{GameCorePlugin, GameState};
+use server_sim::sim::recorder::MetricsLog;
+use server_sim::sim::resources::{FastForward, SimClock, SimRng, SimStats};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: server_sim_batch <board.json|diagram.dot|spec.yaml> [scenario.json] [--ticks N] [--seed N]";
+
+#[derive(Serialize)]
+struct Summary {
+    seed: u64,
+    ticks: u64,
+    game_over: bool,
+    generated: u64,
+    completed: u64,
+    dropped: u64,
+    throttled: u64,
+    blocked_attacks: u64,
+    breaches: u64,
+    data_loss_events: u64,
+    avg_latency_ticks: f32,
+    drop_rate: f32,
+    budget: i64,
+    sla_breaches: usize,
+    sla_breached: bool,
+    diverged_at: Option<u64>,
+}
+
+struct Args {
+    board: PathBuf,
+    scenario: Option<PathBuf>,
+    ticks: Option<u64>,
+    seed: Option<u64>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut board = None;
+    let mut scenario = None;
+    let mut ticks = None;
+    let mut seed = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ticks" | "--seed" => {
+                let value = args
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| format!("{arg} needs a number"))?;
+                if arg == "--ticks" {
+                    ticks = Some(value);
+                } else {
+                    seed = Some(value);
+                }
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if board.is_none() => board = Some(PathBuf::from(arg)),
+            _ if scenario.is_none() => scenario = Some(PathBuf::from(arg)),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    Ok(Args {
+        board: board.ok_or(USAGE)?,
+        scenario,
+        ticks,
+        seed,
+    })
+}
+
+/// a saved board, or a diagram or spec whose nodes are bought and linked
+/// before the first tick.
+fn load_board(path: &Path) -> Result<ActionLog, String> {
+    let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    let actions = match ext {
+        "dot" | "gv" => {
+            let diagram = Blueprint::load(path).map_err(|err| err.to_string())?;
+            let at = diagram
+                .fit(&Terrain::flat(), Economy::default().budget, false)
+                .map_err(|err| format!("the diagram doesn't fit, it would go {err}"))?;
+            diagram.actions(at)
+        }
+        "yaml" | "yml" | "toml" => {
+            let spec = ArchitectureSpec::load(path).map_err(|errors| {
+                let lines: Vec<String> = errors.iter().map(|err| format!("\n  {err}")).collect();
+                lines.concat()
+            })?;
+            spec.actions(&Terrain::flat(), Economy::default().budget)
+                .map_err(|err| err.to_string())?
+        }
+        _ => return ActionLog::load(path).map_err(|err| err.to_string()),
+    };
+    let actions = actions
+        .into_iter()
+        .map(|action| LoggedAction { tick: 0, action })
+        .collect();
+    Ok(ActionLog {
+        terrain: Terrain::flat(),
+        actions,
+        ..default()
+    })
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::from(2);
+        }
+    };
+    let mut log = match load_board(&args.board) {
+        Ok(log) => log,
+        Err(err) => {
+            eprintln!("could not load board {}: {err}", args.board.display());
+            return ExitCode::from(2);
+        }
+    };
+    let mut scenario = match &args.scenario {
+        Some(path) => match Scenario::load(path) {
+            Ok(scenario) => scenario,
+            Err(err) => {
+                eprintln!("could not load scenario {}: {err}", path.display());
+                return ExitCode::from(2);
+            }
+        },
+        None => Scenario::default(),
+    };
+    scenario.ticks = args.ticks.unwrap_or(scenario.ticks);
+    scenario.seed = args.seed.or(scenario.seed);
+
+    // a different seed or terrain would never match the recorded checkpoints
+    if let Some(seed) = scenario.seed
+        && seed != log.seed
+    {
+        log.seed = seed;
+        log.checkpoints.clear();
+    }
+    if let Some(terrain) = scenario.terrain.take() {
+        log.terrain = terrain;
+        log.checkpoints.clear();
+    }
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin, GameCorePlugin))
+        .init_resource::<RenderAssets>()
+        .insert_resource(log.terrain.clone());
+    app.finish();
+    app.cleanup();
+
+    // leaving Setup builds the board, then the saved log is played back on it
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Paused);
+    app.update();
+
+    let world = app.world_mut();
+    world.insert_resource(SimRng::seeded(log.seed));
+    world.insert_resource(log);
+    world.insert_resource(Replay {
+        active: true,
+        ..default()
+    });
+    if let Some(budget) = scenario.budget {
+        world.resource_mut::<Economy>().budget = budget;
+    }
+
+    // each tick applies its logged actions, deletes and their links
+    // included, so nothing from the game's Update schedule is needed to
+    // match a live run
+    world.resource_mut::<FastForward>().0 = true;
+    let mut game_over = false;
+    while world.resource::<SimClock>().tick < scenario.ticks {
+        world.run_schedule(FixedUpdate);
+        if world.resource::<Economy>().budget < 0 {
+            game_over = true;
+            break;
+        }
+    }
+
+    let stats = world.resource::<SimStats>();
+    let metrics = world.resource::<MetricsLog>();
+    let finished = stats.completed + stats.dropped;
+    let sla_breaches = metrics.sla_events.iter().filter(|e| e.breached).count();
+    let summary = Summary {
+        seed: world.resource::<ActionLog>().seed,
+        ticks: world.resource::<SimClock>().tick,
+        game_over,
+        generated: stats.generated,
+        completed: stats.completed,
+        dropped: stats.dropped,
+        throttled: stats.throttled,
+        blocked_attacks: stats.blocked_attacks,
+        breaches: stats.breaches,
+        data_loss_events: stats.data_loss_events,
+        avg_latency_ticks: stats.avg_latency_ticks(),
+        drop_rate: if finished == 0 {
+            0.0
+        } else {
+            stats.dropped as f32 / finished as f32
+        },
+        budget: world.resource::<Economy>().budget,
+        sla_breaches,
+        sla_breached: sla_breaches > 0,
+        diverged_at: world.resource::<Replay>().diverged_at,
+    };
+
+    match serde_json::to_string_pretty(&summary) {
+        Ok(json) => println!("{json}"),
+        Err(err) => {
+            eprintln!("could not write summary: {err}");
+            return ExitCode::from(2);
+        }
+    }
+
+    if summary.sla_breached {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
@@ -0,0 +1,676 @@
+use super::components::{
+    BoardPos, Footprint, NodeLinks, NodeName, NodeTag, Paid, TileNodeLink, TileTag,
+};
+use super::constants::{
+    GAME_BOARD_SIZE_X, GAME_BOARD_SIZE_Z, MAX_TIER, REFUND_RATIO, TIER_SCALE_STEP,
+};
+use super::nodes::{connect_nodes, node_transform, spawn_node};
+use super::resources::{Economy, RenderAssets};
+use super::state::GameState;
+use super::terrain::Terrain;
+use super::types::NodeType;
+
+use crate::sim::components::SimNode;
+use crate::sim::config::{ConfigTarget, NodeConfig};
+use crate::sim::messages::{Failover, SetNodeFailed};
+use crate::sim::resources::SimClock;
+
+use bevy::ecs::schedule::ScheduleLabel;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// everything the player can do that changes the simulation. the UI writes
+/// these and they are applied, and logged, at the next tick boundary. nodes
+/// are addressed by board position so a replay can find them again.
+#[derive(Message, Clone, Debug, Serialize, Deserialize)]
+pub enum PlayerAction {
+    PlaceNode {
+        at: BoardPos,
+        node_type: NodeType,
+        #[serde(default)]
+        rotation: u8,
+    },
+    /// `at` may be any tile the node covers.
+    DeleteNode {
+        at: BoardPos,
+    },
+    Link {
+        from: BoardPos,
+        to: BoardPos,
+    },
+    Configure {
+        at: BoardPos,
+        config: NodeConfig,
+    },
+    SetFailed {
+        at: BoardPos,
+        failed: bool,
+    },
+    Failover {
+        at: BoardPos,
+    },
+    /// an empty name clears it.
+    Rename {
+        at: BoardPos,
+        name: String,
+    },
+    /// move the node up one tier, in place.
+    Upgrade {
+        at: BoardPos,
+    },
+    /// move nodes from one anchor tile to another, all at once so they can
+    /// trade places. nothing moves unless every node fits.
+    Arrange {
+        moves: Vec<(BoardPos, BoardPos)>,
+    },
+    /// recorded for the log only, replays run at their own pace.
+    SetState(GameState),
+}
+
+/// a node was deleted. it is already off the board, this is what's left to
+/// show for it.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct NodeRemoved {
+    pub node_type: NodeType,
+    pub transform: Transform,
+    pub refund: i64,
+}
+
+/// a node slid over to other tiles. its transform is already there, `from`
+/// is where it was.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct NodeMoved {
+    pub node: Entity,
+    pub from: Vec3,
+}
+
+/// right-click on the board: open the menu for whatever is at `at`, with its
+/// corner at `cursor`.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct OpenContextMenu {
+    pub at: BoardPos,
+    pub node: Option<Entity>,
+    pub cursor: Vec2,
+}
+
+/// why a node can't go where the player pointed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlacementError {
+    OutOfBounds,
+    Blocked,
+    Occupied,
+    InsufficientFunds,
+    PowerLimit,
+    /// the node is already as big as it gets.
+    MaxTier,
+}
+
+impl std::fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let why = match self {
+            Self::OutOfBounds => "off the board",
+            Self::Blocked => "on blocked terrain",
+            Self::Occupied => "on top of other nodes",
+            Self::InsufficientFunds => "over the budget",
+            Self::PowerLimit => "over a zone's power limit",
+            Self::MaxTier => "past the top tier",
+        };
+        write!(f, "{why}")
+    }
+}
+
+/// which tiles of the board have a node on them, the usual `occupied` for
+/// `check_placement`.
+pub struct Occupancy(HashMap<BoardPos, bool>);
+
+impl Occupancy {
+    pub fn of<'a>(tiles: impl IntoIterator<Item = (&'a BoardPos, &'a TileNodeLink)>) -> Self {
+        Self::ignoring(tiles, &[])
+    }
+
+    /// as `of`, with the tiles under `moving` counted as free.
+    pub fn ignoring<'a>(
+        tiles: impl IntoIterator<Item = (&'a BoardPos, &'a TileNodeLink)>,
+        moving: &[Entity],
+    ) -> Self {
+        Self(
+            tiles
+                .into_iter()
+                .map(|(pos, link)| (*pos, link.node.is_some_and(|e| !moving.contains(&e))))
+                .collect(),
+        )
+    }
+
+    /// the board `terrain` generates, before its tiles are spawned, with only
+    /// the legacy nodes on it.
+    pub fn of_terrain(terrain: &Terrain) -> Self {
+        let legacy: Vec<(Footprint, BoardPos)> = terrain
+            .legacy
+            .iter()
+            .map(|node| (node.node_type.footprint(node.rotation), node.at))
+            .collect();
+        Self(
+            (0..GAME_BOARD_SIZE_Z)
+                .flat_map(|z| (0..GAME_BOARD_SIZE_X).map(move |x| BoardPos { x, z }))
+                .filter(|pos| !terrain.is_missing(*pos))
+                .map(|pos| {
+                    let taken = legacy
+                        .iter()
+                        .any(|(footprint, at)| footprint.contains(*at, pos));
+                    (pos, taken)
+                })
+                .collect(),
+        )
+    }
+
+    /// whether the tile at `pos` is taken, `None` if there is no tile.
+    pub fn taken(&self, pos: BoardPos) -> Option<bool> {
+        self.0.get(&pos).copied()
+    }
+}
+
+/// checks a placement against the board, the terrain and the budget.
+/// `occupied` reports whether the tile at a position is taken, or `None` if
+/// there is no tile. `power_used` is what the anchor's zone already draws.
+pub fn check_placement(
+    node_type: NodeType,
+    rotation: u8,
+    at: BoardPos,
+    budget: i64,
+    terrain: &Terrain,
+    power_used: u32,
+    occupied: impl Fn(BoardPos) -> Option<bool>,
+) -> Result<(), PlacementError> {
+    for covered in node_type.footprint(rotation).tiles(at) {
+        match occupied(covered) {
+            None => return Err(PlacementError::OutOfBounds),
+            Some(_) if terrain.is_blocked(covered) => return Err(PlacementError::Blocked),
+            Some(true) => return Err(PlacementError::Occupied),
+            Some(false) => {}
+        }
+    }
+    if budget < terrain.cost(node_type, at) {
+        return Err(PlacementError::InsufficientFunds);
+    }
+    let power_limit = terrain
+        .zone(terrain.zone_at(at))
+        .and_then(|zone| zone.power_limit);
+    if power_limit.is_some_and(|limit| power_used + node_type.power() > limit) {
+        return Err(PlacementError::PowerLimit);
+    }
+    Ok(())
+}
+
+/// checks that the node anchored at `at` can move up from `tier`.
+/// `power_used` is what the anchor's zone already draws, the node included.
+pub fn check_upgrade(
+    node_type: NodeType,
+    tier: u8,
+    at: BoardPos,
+    budget: i64,
+    terrain: &Terrain,
+    power_used: u32,
+) -> Result<(), PlacementError> {
+    if tier >= MAX_TIER || !node_type.upgradable() {
+        return Err(PlacementError::MaxTier);
+    }
+    if budget < terrain.upgrade_cost(node_type, tier + 1, at) {
+        return Err(PlacementError::InsufficientFunds);
+    }
+    let extra = node_type.tier_power(tier + 1) - node_type.tier_power(tier);
+    let power_limit = terrain
+        .zone(terrain.zone_at(at))
+        .and_then(|zone| zone.power_limit);
+    if power_limit.is_some_and(|limit| power_used + extra > limit) {
+        return Err(PlacementError::PowerLimit);
+    }
+    Ok(())
+}
+
+/// runs once per action: the action itself, then whatever reacts to it.
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ApplyAction;
+
+/// the action the current `ApplyAction` run is working on.
+#[derive(Resource, Default)]
+pub struct PendingAction(pub Option<PlayerAction>);
+
+pub fn apply_action_system(
+    mut commands: Commands,
+    mut pending: ResMut<PendingAction>,
+    clock: Res<SimClock>,
+    mut economy: ResMut<Economy>,
+    render_assets: Res<RenderAssets>,
+    terrain: Res<Terrain>,
+    mut tiles: Query<(Entity, &BoardPos, &mut TileNodeLink), With<TileTag>>,
+    mut nodes: Query<(Entity, &BoardPos, ConfigTarget), With<NodeTag>>,
+    mut placed: Query<(
+        &mut NodeTag,
+        &BoardPos,
+        &mut Transform,
+        &mut Paid,
+        &mut SimNode,
+        &Footprint,
+    )>,
+    mut node_links: Query<&mut NodeLinks>,
+    mut set_failed: MessageWriter<SetNodeFailed>,
+    mut failover: MessageWriter<Failover>,
+    mut removed: MessageWriter<NodeRemoved>,
+    mut moved: MessageWriter<NodeMoved>,
+) {
+    let Some(action) = pending.0.take() else {
+        return;
+    };
+    let node_at = |nodes: &Query<(Entity, &BoardPos, ConfigTarget), With<NodeTag>>,
+                   at: BoardPos| {
+        nodes
+            .iter()
+            .find(|(_, pos, _)| **pos == at)
+            .map(|(node_e, ..)| node_e)
+    };
+
+    match action {
+        PlayerAction::PlaceNode {
+            at,
+            node_type,
+            rotation,
+        } => {
+            // legacy infrastructure that comes with the board costs nothing,
+            // once
+            let free = terrain.legacy.iter().any(|node| {
+                node.at == at && node.node_type == node_type && node.rotation == rotation
+            }) && !economy.legacy_claimed.contains(&at);
+            let power_used = terrain.power_used(
+                terrain.zone_at(at),
+                placed.iter().map(|(tag, pos, ..)| (tag, pos)),
+            );
+            let budget = if free { i64::MAX } else { economy.budget };
+            let occupancy = Occupancy::of(tiles.iter().map(|(_, pos, link)| (pos, link)));
+            let placement = check_placement(
+                node_type,
+                rotation,
+                at,
+                budget,
+                &terrain,
+                power_used,
+                |covered| occupancy.taken(covered),
+            );
+            let (Ok(()), Some((anchor, ..))) =
+                (placement, tiles.iter().find(|(_, pos, _)| **pos == at))
+            else {
+                return;
+            };
+            let price = if free { 0 } else { terrain.cost(node_type, at) };
+            economy.budget -= price;
+            if free {
+                economy.legacy_claimed.push(at);
+            }
+
+            let footprint = node_type.footprint(rotation);
+            let node_e = spawn_node(
+                &mut commands,
+                anchor,
+                &render_assets,
+                node_type,
+                at,
+                rotation,
+            );
+            commands.entity(node_e).insert(Paid(price));
+            for (_, pos, mut link) in &mut tiles {
+                if footprint.contains(at, *pos) {
+                    link.node = Some(node_e);
+                }
+            }
+        }
+        PlayerAction::DeleteNode { at } => {
+            let Some(node_e) = tiles
+                .iter()
+                .find(|(_, pos, _)| **pos == at)
+                .and_then(|(_, _, link)| link.node)
+            else {
+                return;
+            };
+            if let Ok((tag, _, tf, price, ..)) = placed.get(node_e) {
+                let refund = (price.0 as f64 * REFUND_RATIO).round() as i64;
+                economy.budget += refund;
+                removed.write(NodeRemoved {
+                    node_type: tag.node_type,
+                    transform: *tf,
+                    refund,
+                });
+            }
+            commands.entity(node_e).despawn();
+            for (_, _, mut link) in &mut tiles {
+                if link.node == Some(node_e) {
+                    link.node = None;
+                }
+            }
+            // in here rather than a frame later, so seeks and the headless
+            // runner route around it on the same tick as the live game
+            for mut links in &mut node_links {
+                links.out.retain(|e| *e != node_e);
+            }
+        }
+        PlayerAction::Link { from, to } => {
+            if let (Some(source), Some(target)) = (node_at(&nodes, from), node_at(&nodes, to)) {
+                connect_nodes(&mut node_links, source, target);
+            }
+        }
+        PlayerAction::Configure { at, config } => {
+            if let Some((_, _, mut target)) = nodes.iter_mut().find(|(_, pos, _)| **pos == at) {
+                target.apply(config, clock.tick);
+            }
+        }
+        PlayerAction::SetFailed { at, failed } => {
+            if let Some(node) = node_at(&nodes, at) {
+                set_failed.write(SetNodeFailed { node, failed });
+            }
+        }
+        PlayerAction::Failover { at } => {
+            if let Some(primary) = node_at(&nodes, at) {
+                failover.write(Failover { primary });
+            }
+        }
+        PlayerAction::Rename { at, name } => {
+            if let Some(node_e) = node_at(&nodes, at) {
+                let name = name.trim();
+                if name.is_empty() {
+                    commands.entity(node_e).remove::<NodeName>();
+                } else {
+                    commands.entity(node_e).insert(NodeName(name.to_string()));
+                }
+            }
+        }
+        PlayerAction::Upgrade { at } => {
+            let Some(node_e) = node_at(&nodes, at) else {
+                return;
+            };
+            let zone = terrain.zone_at(at);
+            let power_used =
+                terrain.power_used(zone, placed.iter().map(|(tag, pos, ..)| (tag, pos)));
+            let Ok((mut tag, _, mut tf, mut paid, mut sim, _)) = placed.get_mut(node_e) else {
+                return;
+            };
+            if check_upgrade(
+                tag.node_type,
+                tag.tier,
+                at,
+                economy.budget,
+                &terrain,
+                power_used,
+            )
+            .is_err()
+            {
+                return;
+            }
+
+            tag.tier += 1;
+            let price = terrain.upgrade_cost(tag.node_type, tag.tier, at);
+            economy.budget -= price;
+            paid.0 += price;
+            // links live on the node, so they carry over
+            sim.capacity *= 2;
+            sim.queue_limit *= 2;
+            // x and z are the footprint, bigger racks grow upward
+            tf.scale.y = 1.0 + TIER_SCALE_STEP * tag.tier as f32;
+        }
+        PlayerAction::Arrange { moves } => {
+            // every node with where it is now, where it ends up and what
+            // getting there costs
+            let layout: Vec<(Entity, BoardPos, BoardPos, Footprint, u32, i64)> = nodes
+                .iter()
+                .filter_map(|(node_e, pos, _)| {
+                    let (tag, _, _, paid, _, footprint) = placed.get(node_e).ok()?;
+                    let to = moves
+                        .iter()
+                        .find(|(from, _)| from == pos)
+                        .map_or(*pos, |(_, to)| *to);
+                    Some((
+                        node_e,
+                        *pos,
+                        to,
+                        *footprint,
+                        tag.node_type.tier_power(tag.tier),
+                        terrain.move_cost(tag.node_type, tag.tier, paid.0, *pos, to),
+                    ))
+                })
+                .collect();
+            let found = moves
+                .iter()
+                .all(|(from, _)| layout.iter().any(|(_, pos, ..)| pos == from));
+            let fits = layout.iter().all(|(node_e, _, to, footprint, ..)| {
+                footprint.tiles(*to).all(|covered| {
+                    tiles.iter().any(|(_, pos, _)| *pos == covered)
+                        && !terrain.is_blocked(covered)
+                        && layout.iter().all(|(other_e, _, at, other, ..)| {
+                            other_e == node_e || !other.contains(*at, covered)
+                        })
+                })
+            });
+            let powered = terrain.zones.iter().enumerate().all(|(zone, limits)| {
+                let drawn = |moved: bool| {
+                    layout
+                        .iter()
+                        .filter(|(_, from, to, ..)| {
+                            terrain.zone_at(if moved { *to } else { *from }) == Some(zone)
+                        })
+                        .map(|(.., power, _)| power)
+                        .sum::<u32>()
+                };
+                let after = drawn(true);
+                limits
+                    .power_limit
+                    .is_none_or(|limit| after <= limit || after <= drawn(false))
+            });
+            // moving into a dearer zone pays the difference, and moving out
+            // of one gets it back
+            let cost: i64 = layout.iter().map(|(.., cost)| cost).sum();
+            if !(found && fits && powered) || (cost > 0 && cost > economy.budget) {
+                return;
+            }
+            economy.budget -= cost;
+
+            let movers: Vec<_> = layout
+                .into_iter()
+                .filter(|(_, from, to, ..)| from != to)
+                .collect();
+            for (_, _, mut link) in &mut tiles {
+                if movers.iter().any(|(node_e, ..)| link.node == Some(*node_e)) {
+                    link.node = None;
+                }
+            }
+            for (node_e, _, to, footprint, _, cost) in movers {
+                for (tile_e, pos, mut link) in &mut tiles {
+                    if footprint.contains(to, *pos) {
+                        link.node = Some(node_e);
+                    }
+                    if *pos == to {
+                        commands.entity(node_e).insert((
+                            to,
+                            TileNodeLink {
+                                tile: tile_e,
+                                node: Some(node_e),
+                            },
+                        ));
+                    }
+                }
+                if let Ok((tag, _, mut tf, mut paid, ..)) = placed.get_mut(node_e) {
+                    paid.0 += cost;
+                    let from = tf.translation;
+                    let target =
+                        node_transform(tag.node_type, to, footprint.rotation, from.y).translation;
+                    tf.translation = target;
+                    moved.write(NodeMoved { node: node_e, from });
+                }
+            }
+        }
+        PlayerAction::SetState(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(
+        node_type: NodeType,
+        at: BoardPos,
+        budget: i64,
+        terrain: &Terrain,
+        power_used: u32,
+        taken: &[BoardPos],
+    ) -> Result<(), PlacementError> {
+        check_placement(node_type, 0, at, budget, terrain, power_used, |pos| {
+            (pos.x < GAME_BOARD_SIZE_X && pos.z < GAME_BOARD_SIZE_Z).then(|| taken.contains(&pos))
+        })
+    }
+
+    #[test]
+    fn placement_errors_come_in_order() {
+        let terrain = Terrain::default();
+        let at = BoardPos { x: 30, z: 30 };
+        let taken = [BoardPos { x: 3, z: 3 }];
+
+        // the database's far corner hangs off the board
+        let corner = BoardPos { x: 31, z: 0 };
+        assert_eq!(
+            place(NodeType::Database, corner, 0, &terrain, 0, &taken),
+            Err(PlacementError::OutOfBounds)
+        );
+        // the ocean strip between the zones
+        let shore = BoardPos { x: 19, z: 3 };
+        assert_eq!(
+            place(NodeType::Database, shore, 0, &terrain, 0, &taken),
+            Err(PlacementError::Blocked)
+        );
+        let near = BoardPos { x: 2, z: 2 };
+        assert_eq!(
+            place(NodeType::Database, near, 0, &terrain, 0, &taken),
+            Err(PlacementError::Occupied)
+        );
+        assert_eq!(
+            place(NodeType::Database, at, 0, &terrain, 0, &taken),
+            Err(PlacementError::InsufficientFunds)
+        );
+        assert_eq!(
+            place(NodeType::Database, at, 10_000, &terrain, 0, &taken),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn zones_price_the_anchor() {
+        let terrain = Terrain::default();
+        let west = BoardPos { x: 0, z: 0 };
+        let east = BoardPos { x: 10, z: 0 };
+        assert_eq!(
+            place(NodeType::Compute, west, 400, &terrain, 0, &[]),
+            Ok(())
+        );
+        assert_eq!(
+            place(NodeType::Compute, east, 400, &terrain, 0, &[]),
+            Err(PlacementError::InsufficientFunds)
+        );
+    }
+
+    #[test]
+    fn zones_cap_the_power_their_nodes_draw() {
+        let terrain = Terrain::default();
+        let west = BoardPos { x: 0, z: 0 };
+        let eu = BoardPos { x: 30, z: 30 };
+        let west_limit = terrain.zones[0].power_limit.unwrap();
+        let eu_limit = terrain.zones[2].power_limit.unwrap();
+
+        let headroom = west_limit - NodeType::Compute.power();
+        assert_eq!(
+            place(NodeType::Compute, west, 400, &terrain, headroom, &[]),
+            Ok(())
+        );
+        assert_eq!(
+            place(NodeType::Compute, west, 400, &terrain, headroom + 1, &[]),
+            Err(PlacementError::PowerLimit)
+        );
+        let headroom = eu_limit - NodeType::Database.power();
+        assert_eq!(
+            place(NodeType::Database, eu, 10_000, &terrain, headroom, &[]),
+            Ok(())
+        );
+        assert_eq!(
+            place(NodeType::Database, eu, 10_000, &terrain, headroom + 1, &[]),
+            Err(PlacementError::PowerLimit)
+        );
+        // the budget runs out before the power does
+        assert_eq!(
+            place(NodeType::Database, eu, 0, &terrain, eu_limit, &[]),
+            Err(PlacementError::InsufficientFunds)
+        );
+        assert_eq!(
+            place(
+                NodeType::Compute,
+                west,
+                400,
+                &Terrain::flat(),
+                u32::MAX / 2,
+                &[]
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn flat_terrain_has_no_limits_but_the_board() {
+        let terrain = Terrain::flat();
+        let shore = BoardPos { x: 20, z: 3 };
+        assert_eq!(
+            place(NodeType::Compute, shore, 400, &terrain, 0, &[]),
+            Ok(())
+        );
+        assert_eq!(
+            place(NodeType::Compute, shore, 399, &terrain, 0, &[]),
+            Err(PlacementError::InsufficientFunds)
+        );
+    }
+
+    #[test]
+    fn rotation_turns_the_footprint() {
+        let terrain = Terrain::flat();
+        let at = BoardPos { x: 30, z: 0 };
+        let fits = |rotation| {
+            check_placement(NodeType::Queue, rotation, at, 1_000, &terrain, 0, |pos| {
+                (pos.x < GAME_BOARD_SIZE_X).then_some(false)
+            })
+        };
+        assert_eq!(fits(0), Ok(()));
+        assert_eq!(fits(1), Err(PlacementError::OutOfBounds));
+    }
+
+    #[test]
+    fn upgrades_stop_at_the_top_tier() {
+        let terrain = Terrain::default();
+        let at = BoardPos { x: 0, z: 0 };
+        let cost = terrain.upgrade_cost(NodeType::Compute, 1, at);
+        let extra = NodeType::Compute.tier_power(1) - NodeType::Compute.tier_power(0);
+        let headroom = terrain.zones[0].power_limit.unwrap() - extra;
+        assert_eq!(
+            check_upgrade(NodeType::Compute, 0, at, cost, &terrain, headroom),
+            Ok(())
+        );
+        assert_eq!(
+            check_upgrade(NodeType::Compute, 0, at, cost - 1, &terrain, 0),
+            Err(PlacementError::InsufficientFunds)
+        );
+        assert_eq!(
+            check_upgrade(NodeType::Compute, 0, at, cost, &terrain, headroom + 1),
+            Err(PlacementError::PowerLimit)
+        );
+        assert_eq!(
+            check_upgrade(NodeType::Compute, MAX_TIER, at, 10_000, &terrain, 0),
+            Err(PlacementError::MaxTier)
+        );
+        assert_eq!(
+            check_upgrade(NodeType::Internet, 0, at, 10_000, &terrain, 0),
+            Err(PlacementError::MaxTier)
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use super::actions::{NodeMoved, NodeRemoved};
+use super::components::NodeTag;
+use super::constants::*;
+use super::resources::RenderAssets;
+
+use crate::sim::messages::SetNodeFailed;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+/// what an `Anim` does to its entity.
+#[derive(Clone, Copy, Debug)]
+pub enum AnimKind {
+    /// sink into the board while shrinking.
+    Sink,
+    /// fly off along `velocity`, falling and shrinking.
+    Debris { velocity: Vec3 },
+    /// rise along `velocity`, swelling and then thinning out.
+    Smoke { velocity: Vec3 },
+    /// move along `velocity`, for anchors that something else follows.
+    Drift { velocity: Vec3 },
+    /// blink on and off.
+    Flicker,
+}
+
+/// a short one-off animation. when it ends the entity either despawns or is
+/// left where the animation put it, visible.
+#[derive(Component, Clone, Debug)]
+pub struct Anim {
+    pub kind: AnimKind,
+    pub duration: f32,
+    pub elapsed: f32,
+    pub despawn: bool,
+    /// the transform on the first animated frame.
+    start: Option<Transform>,
+}
+
+impl Anim {
+    pub fn new(kind: AnimKind, duration: f32) -> Self {
+        Self {
+            kind,
+            duration,
+            elapsed: 0.0,
+            despawn: false,
+            start: None,
+        }
+    }
+
+    pub fn despawning(mut self) -> Self {
+        self.despawn = true;
+        self
+    }
+
+    /// how far along the animation is, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        (self.elapsed / self.duration).clamp(0.0, 1.0)
+    }
+}
+
+/// a node sliding over from `from` to where it was moved, leaving its height
+/// alone. kept apart from `Anim` so a node can fail while it moves.
+#[derive(Component, Clone, Debug)]
+pub struct Glide {
+    pub from: Vec3,
+    pub elapsed: f32,
+    /// where the node was moved to, read on the first frame.
+    to: Option<Vec3>,
+}
+
+impl Glide {
+    pub fn new(from: Vec3) -> Self {
+        Self {
+            from,
+            elapsed: 0.0,
+            to: None,
+        }
+    }
+}
+
+pub fn animate_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut anims: Query<(Entity, &mut Anim, &mut Transform, &mut Visibility)>,
+) {
+    let dt = time.delta_secs();
+
+    for (entity, mut anim, mut tf, mut visibility) in &mut anims {
+        let start = *anim.start.get_or_insert(*tf);
+        anim.elapsed += dt;
+        let (age, t) = (anim.elapsed, anim.progress());
+
+        match anim.kind {
+            AnimKind::Sink => {
+                tf.translation.y =
+                    start.translation.y - SINK_DEPTH * EaseFunction::QuadraticIn.sample_clamped(t);
+                tf.scale = start.scale * (1.0 - 0.6 * t);
+            }
+            AnimKind::Debris { velocity } => {
+                tf.translation =
+                    start.translation + velocity * age - Vec3::Y * 0.5 * DEBRIS_GRAVITY * age * age;
+                tf.scale = start.scale * (1.0 - t);
+            }
+            AnimKind::Smoke { velocity } => {
+                tf.translation = start.translation + velocity * age;
+                tf.scale = start.scale * (1.0 + 2.0 * t) * (1.0 - t * t);
+            }
+            AnimKind::Drift { velocity } => {
+                tf.translation = start.translation + velocity * age;
+            }
+            AnimKind::Flicker => {
+                *visibility = if ((age * FLICKER_HZ) as u32).is_multiple_of(2) {
+                    Visibility::Hidden
+                } else {
+                    Visibility::Inherited
+                };
+            }
+        }
+
+        if t < 1.0 {
+            continue;
+        }
+        if anim.despawn {
+            commands.entity(entity).despawn();
+        } else {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<Anim>();
+        }
+    }
+}
+
+/// deleted nodes are already gone from the board, so a copy of their mesh
+/// sinks away in their place and breaks into debris.
+pub fn node_removed_anim_system(
+    mut commands: Commands,
+    render_assets: Res<RenderAssets>,
+    mut reader: MessageReader<NodeRemoved>,
+) {
+    let mut rng = rand::rng();
+    for removed in reader.read() {
+        let (mesh, mat, _vfx) = render_assets.get_node_assets(removed.node_type);
+        commands.spawn((
+            Mesh3d(mesh),
+            MeshMaterial3d(mat.clone()),
+            removed.transform,
+            Pickable::IGNORE,
+            Anim::new(AnimKind::Sink, DESPAWN_SECS).despawning(),
+        ));
+
+        for _ in 0..DEBRIS_COUNT {
+            let angle = rng.random_range(0.0..std::f32::consts::TAU);
+            let velocity = Vec3::new(angle.cos(), rng.random_range(1.0..2.0), angle.sin())
+                * DEBRIS_SPEED
+                * rng.random_range(0.5..1.0);
+            commands.spawn((
+                Mesh3d(render_assets.debris_mesh.clone()),
+                MeshMaterial3d(mat.clone()),
+                Transform::from_translation(removed.transform.translation),
+                Pickable::IGNORE,
+                Anim::new(AnimKind::Debris { velocity }, DESPAWN_SECS).despawning(),
+            ));
+        }
+    }
+}
+
+/// a node going down flickers and puffs red smoke.
+pub fn node_failed_anim_system(
+    mut commands: Commands,
+    render_assets: Res<RenderAssets>,
+    mut reader: MessageReader<SetNodeFailed>,
+    nodes: Query<&Transform, With<NodeTag>>,
+) {
+    let mut rng = rand::rng();
+    for msg in reader.read() {
+        if !msg.failed {
+            continue;
+        }
+        let Ok(node_tf) = nodes.get(msg.node) else {
+            continue;
+        };
+        commands
+            .entity(msg.node)
+            .insert(Anim::new(AnimKind::Flicker, FAIL_FLICKER_SECS));
+
+        for _ in 0..SMOKE_PUFFS {
+            let offset = Vec3::new(
+                rng.random_range(-0.2..0.2),
+                0.2,
+                rng.random_range(-0.2..0.2),
+            );
+            let velocity = Vec3::new(offset.x, rng.random_range(0.4..0.8), offset.z);
+            commands.spawn((
+                Mesh3d(render_assets.smoke_mesh.clone()),
+                MeshMaterial3d(render_assets.smoke_mat.clone()),
+                Transform::from_translation(node_tf.translation + offset),
+                Pickable::IGNORE,
+                Anim::new(AnimKind::Smoke { velocity }, SMOKE_SECS).despawning(),
+            ));
+        }
+    }
+}
+
+/// nodes moved by a layout glide over to their new tiles.
+pub fn node_moved_anim_system(mut commands: Commands, mut reader: MessageReader<NodeMoved>) {
+    for msg in reader.read() {
+        if let Ok(mut node) = commands.get_entity(msg.node) {
+            node.insert(Glide::new(msg.from));
+        }
+    }
+}
+
+pub fn glide_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut glides: Query<(Entity, &mut Glide, &mut Transform)>,
+) {
+    for (entity, mut glide, mut tf) in &mut glides {
+        let to = *glide.to.get_or_insert(tf.translation);
+        glide.elapsed += time.delta_secs();
+        let t = glide.elapsed / LAYOUT_GLIDE_SECS;
+        let eased = EaseFunction::CubicInOut.sample_clamped(t);
+        tf.translation.x = glide.from.x.lerp(to.x, eased);
+        tf.translation.z = glide.from.z.lerp(to.z, eased);
+        if t >= 1.0 {
+            commands.entity(entity).remove::<Glide>();
+        }
+    }
+}
@@ -0,0 +1,775 @@
+use super::actions::{Occupancy, PlacementError, PlayerAction, check_placement, check_upgrade};
+use super::components::{BoardPos, Footprint, NodeLinks, NodeName, NodeTag, TileNodeLink, TileTag};
+use super::constants::{
+    GAME_BOARD_SIZE_X, GAME_BOARD_SIZE_Z, MAX_TIER, NODE_SPAWN_Y, TIER_SCALE_STEP,
+};
+use super::diagram::{parse_dot, to_dot, to_mermaid};
+use super::nodes::node_transform;
+use super::replay::ActionLog;
+use super::resources::{Economy, Game, RenderAssets};
+use super::terrain::Terrain;
+use super::types::{NodeType, ToolType};
+
+use crate::camera::CamState;
+use crate::sim::config::{ConfigTargetReadOnly, ConfigTargetReadOnlyItem, NodeConfig};
+use crate::sim::export::{LastExport, export_run, user_data_dir};
+use crate::sim::messages::ExportMetrics;
+use crate::sim::recorder::MetricsLog;
+
+use bevy::prelude::*;
+use bevy::window::FileDragAndDrop;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// one node of a blueprint, anchored relative to the blueprint's corner.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlueprintNode {
+    pub offset: BoardPos,
+    pub node_type: NodeType,
+    #[serde(default)]
+    pub rotation: u8,
+    #[serde(default)]
+    pub tier: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// settings changed after the node is built, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub config: Vec<NodeConfig>,
+}
+
+impl BlueprintNode {
+    /// where the node goes with the blueprint's corner at `at`, `None` past
+    /// what a position can hold.
+    pub fn anchor(&self, at: BoardPos) -> Option<BoardPos> {
+        Some(BoardPos {
+            x: at.x.checked_add(self.offset.x)?,
+            z: at.z.checked_add(self.offset.z)?,
+        })
+    }
+}
+
+/// what `Blueprint::capture` reads off a placed node.
+pub type PlacedNode<'a> = (
+    Entity,
+    &'a NodeTag,
+    &'a BoardPos,
+    &'a Footprint,
+    &'a NodeLinks,
+    Option<&'a NodeName>,
+    ConfigTargetReadOnlyItem<'a, 'a>,
+);
+
+/// a group of nodes and the links between them, ready to be stamped anywhere
+/// on a board.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Blueprint {
+    pub name: String,
+    pub nodes: Vec<BlueprintNode>,
+    /// (from, to) indices into `nodes`.
+    #[serde(default)]
+    pub links: Vec<(usize, usize)>,
+}
+
+impl Blueprint {
+    /// build a blueprint from placed nodes. links leaving the group are
+    /// dropped.
+    pub fn capture<'a>(name: &str, group: impl IntoIterator<Item = PlacedNode<'a>>) -> Self {
+        let group: Vec<PlacedNode> = group.into_iter().collect();
+        let min_x = group.iter().map(|(_, _, pos, ..)| pos.x).min().unwrap_or(0);
+        let min_z = group.iter().map(|(_, _, pos, ..)| pos.z).min().unwrap_or(0);
+
+        let mut links = Vec::new();
+        for (from, (.., out, _, _)) in group.iter().enumerate() {
+            for target in &out.out {
+                if let Some(to) = group.iter().position(|(e, ..)| e == target) {
+                    links.push((from, to));
+                }
+            }
+        }
+        let nodes = group
+            .iter()
+            .map(|(_, tag, pos, footprint, _, name, config)| BlueprintNode {
+                offset: BoardPos {
+                    x: pos.x - min_x,
+                    z: pos.z - min_z,
+                },
+                node_type: tag.node_type,
+                rotation: footprint.rotation,
+                tier: tag.tier,
+                name: name.map(|n| n.0.clone()),
+                config: config.settings(),
+            })
+            .collect();
+
+        Self {
+            name: name.to_string(),
+            nodes,
+            links,
+        }
+    }
+
+    /// what the blueprint costs before zone multipliers.
+    pub fn base_cost(&self) -> i64 {
+        self.nodes
+            .iter()
+            .map(|node| {
+                let upgrades: i64 = (1..=node.tier)
+                    .map(|tier| node.node_type.upgrade_cost(tier))
+                    .sum();
+                node.node_type.cost() + upgrades
+            })
+            .sum()
+    }
+
+    /// checks that every node fits with the corner at `at`, one after the
+    /// other, as `check_placement` and `check_upgrade` would when the actions
+    /// land. `power_used` is what a zone already draws.
+    pub fn check(
+        &self,
+        at: BoardPos,
+        budget: i64,
+        terrain: &Terrain,
+        power_used: impl Fn(Option<usize>) -> u32,
+        occupied: impl Fn(BoardPos) -> Option<bool>,
+    ) -> Result<(), PlacementError> {
+        let mut claimed: Vec<(Footprint, BoardPos)> = Vec::new();
+        let mut drawn: Vec<(Option<usize>, u32)> = Vec::new();
+        let mut spent = 0;
+
+        for node in &self.nodes {
+            let pos = node.anchor(at).ok_or(PlacementError::OutOfBounds)?;
+            let zone = terrain.zone_at(pos);
+            let zone_power = power_used(zone)
+                + drawn
+                    .iter()
+                    .filter(|(z, _)| *z == zone)
+                    .map(|(_, power)| power)
+                    .sum::<u32>();
+
+            check_placement(
+                node.node_type,
+                node.rotation,
+                pos,
+                budget - spent,
+                terrain,
+                zone_power,
+                |covered| {
+                    occupied(covered).map(|taken| {
+                        taken
+                            || claimed
+                                .iter()
+                                .any(|(footprint, anchor)| footprint.contains(*anchor, covered))
+                    })
+                },
+            )?;
+            spent += terrain.cost(node.node_type, pos);
+
+            for tier in 0..node.tier.min(MAX_TIER) {
+                check_upgrade(
+                    node.node_type,
+                    tier,
+                    pos,
+                    budget - spent,
+                    terrain,
+                    zone_power + node.node_type.tier_power(tier),
+                )?;
+                spent += terrain.upgrade_cost(node.node_type, tier + 1, pos);
+            }
+
+            claimed.push((node.node_type.footprint(node.rotation), pos));
+            drawn.push((zone, node.node_type.tier_power(node.tier.min(MAX_TIER))));
+        }
+        Ok(())
+    }
+
+    /// the corner to build the blueprint at on `terrain`, next to the
+    /// board's legacy nodes: the first spot, row by row, where all of it
+    /// fits, or the board's corner when `pinned`.
+    pub fn fit(
+        &self,
+        terrain: &Terrain,
+        budget: i64,
+        pinned: bool,
+    ) -> Result<BoardPos, PlacementError> {
+        let power_used = |zone| {
+            terrain
+                .legacy
+                .iter()
+                .filter(|node| terrain.zone_at(node.at) == zone)
+                .map(|node| node.node_type.power())
+                .sum()
+        };
+        let occupancy = Occupancy::of_terrain(terrain);
+        let occupied = |pos| occupancy.taken(pos);
+
+        let corners: Vec<BoardPos> = if pinned {
+            vec![BoardPos { x: 0, z: 0 }]
+        } else {
+            (0..GAME_BOARD_SIZE_Z)
+                .flat_map(|z| (0..GAME_BOARD_SIZE_X).map(move |x| BoardPos { x, z }))
+                .collect()
+        };
+        // running out of room is only worth reporting if nothing else went
+        // wrong anywhere
+        let mut worst = PlacementError::OutOfBounds;
+        for at in corners {
+            match self.check(at, budget, terrain, power_used, occupied) {
+                Ok(()) => return Ok(at),
+                Err(err) if pinned => return Err(err),
+                Err(
+                    err @ (PlacementError::InsufficientFunds
+                    | PlacementError::PowerLimit
+                    | PlacementError::MaxTier),
+                ) => worst = err,
+                Err(PlacementError::Occupied) if worst == PlacementError::OutOfBounds => {
+                    worst = PlacementError::Occupied
+                }
+                Err(_) => {}
+            }
+        }
+        Err(worst)
+    }
+
+    /// the actions that build the blueprint with its corner at `at`.
+    pub fn actions(&self, at: BoardPos) -> Vec<PlayerAction> {
+        let mut actions = Vec::new();
+        for node in &self.nodes {
+            let Some(pos) = node.anchor(at) else {
+                continue;
+            };
+            actions.push(PlayerAction::PlaceNode {
+                at: pos,
+                node_type: node.node_type,
+                rotation: node.rotation,
+            });
+            for _ in 0..node.tier {
+                actions.push(PlayerAction::Upgrade { at: pos });
+            }
+            if let Some(name) = &node.name {
+                actions.push(PlayerAction::Rename {
+                    at: pos,
+                    name: name.clone(),
+                });
+            }
+            for config in &node.config {
+                actions.push(PlayerAction::Configure {
+                    at: pos,
+                    config: *config,
+                });
+            }
+        }
+        for (from, to) in &self.links {
+            let from = self.nodes.get(*from).and_then(|node| node.anchor(at));
+            let to = self.nodes.get(*to).and_then(|node| node.anchor(at));
+            if let (Some(from), Some(to)) = (from, to) {
+                actions.push(PlayerAction::Link { from, to });
+            }
+        }
+        actions
+    }
+
+    /// write the blueprint into `dir`, named after it. a blueprint by another
+    /// name whose file name comes out the same keeps its file, this one gets
+    /// a numbered one.
+    pub fn save(&self, dir: &Path) -> io::Result<PathBuf> {
+        let slug: String = self
+            .name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        let mut path = dir.join(format!("{slug}.json"));
+        let mut n = 1;
+        while path.exists() && Self::load(&path).is_ok_and(|other| other.name != self.name) {
+            n += 1;
+            path = dir.join(format!("{slug}-{n}.json"));
+        }
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::create_dir_all(dir)?;
+        fs::write(&path, json)?;
+        Ok(path)
+    }
+
+    /// read a saved blueprint, or import a Graphviz diagram. diagrams are
+    /// named after their file.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let blueprint = if is_diagram(path) {
+            let mut blueprint = parse_dot(&text).map_err(io::Error::other)?;
+            if let Some(stem) = path.file_stem() {
+                blueprint.name = stem.to_string_lossy().into_owned();
+            }
+            blueprint
+        } else {
+            serde_json::from_str(&text).map_err(io::Error::other)?
+        };
+        blueprint.validate().map_err(io::Error::other)?;
+        Ok(blueprint)
+    }
+
+    /// files can say anything, so check what they say fits on a board.
+    fn validate(&self) -> Result<(), String> {
+        for node in &self.nodes {
+            if node.tier > MAX_TIER {
+                return Err(format!(
+                    "a node is at tier {}, past the top tier",
+                    node.tier
+                ));
+            }
+            if node.offset.x >= GAME_BOARD_SIZE_X || node.offset.z >= GAME_BOARD_SIZE_Z {
+                return Err(format!(
+                    "a node at ({}, {}) is off the board",
+                    node.offset.x, node.offset.z
+                ));
+            }
+        }
+        if let Some((from, to)) = self
+            .links
+            .iter()
+            .find(|(from, to)| *from >= self.nodes.len() || *to >= self.nodes.len())
+        {
+            return Err(format!("link {from} -> {to} names a node that isn't there"));
+        }
+        Ok(())
+    }
+}
+
+fn node(x: usize, z: usize, node_type: NodeType) -> BlueprintNode {
+    BlueprintNode {
+        offset: BoardPos { x, z },
+        node_type,
+        rotation: 0,
+        tier: 0,
+        name: None,
+        config: Vec::new(),
+    }
+}
+
+/// patterns every library starts with.
+fn builtin_blueprints() -> Vec<Blueprint> {
+    vec![
+        Blueprint {
+            name: "3-tier web app".to_string(),
+            nodes: vec![
+                node(0, 1, NodeType::LoadBalancer),
+                node(2, 0, NodeType::Compute),
+                node(2, 2, NodeType::Compute),
+                node(4, 1, NodeType::Database),
+            ],
+            links: vec![(0, 1), (0, 2), (1, 3), (2, 3)],
+        },
+        Blueprint {
+            name: "cache-aside pattern".to_string(),
+            nodes: vec![
+                node(0, 0, NodeType::Compute),
+                node(2, 0, NodeType::Cache),
+                node(4, 0, NodeType::Database),
+            ],
+            links: vec![(0, 1), (1, 2)],
+        },
+    ]
+}
+
+fn is_diagram(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "dot" || ext == "gv")
+}
+
+/// saved blueprints, and diagrams dropped in to be imported.
+pub fn blueprints_dir() -> PathBuf {
+    user_data_dir().join("blueprints")
+}
+
+/// the copied group, and the blueprints kept across boards.
+#[derive(Resource)]
+pub struct Blueprints {
+    pub clipboard: Option<Blueprint>,
+    pub library: Vec<Blueprint>,
+    /// how the last file dropped on the window went.
+    pub imported: Option<String>,
+}
+
+impl Default for Blueprints {
+    fn default() -> Self {
+        Self {
+            clipboard: None,
+            library: builtin_blueprints(),
+            imported: None,
+        }
+    }
+}
+
+/// keep the clipboard in the library under `name`, replacing a blueprint
+/// with the same name.
+#[derive(Message, Clone, Debug)]
+pub struct SaveBlueprint {
+    pub name: String,
+}
+
+/// the see-through nodes that follow the cursor while stamping, one per
+/// blueprint node.
+#[derive(Component)]
+pub struct StampGhost(pub usize);
+
+/// add the blueprints saved on earlier runs, and any diagrams dropped in
+/// next to them, to the library.
+pub fn load_blueprints_system(mut blueprints: ResMut<Blueprints>) {
+    let Ok(entries) = fs::read_dir(blueprints_dir()) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_diagram(path) || path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    for path in paths {
+        match Blueprint::load(&path) {
+            Ok(blueprint) => {
+                blueprints.library.retain(|b| b.name != blueprint.name);
+                blueprints.library.push(blueprint);
+            }
+            Err(err) => warn!("skipping blueprint {}: {err}", path.display()),
+        }
+    }
+}
+
+/// a diagram or blueprint file dropped on the window joins the library and
+/// is picked up for stamping, the stamp ghost shows where it fits.
+pub fn import_dropped_system(
+    mut drops: MessageReader<FileDragAndDrop>,
+    mut game: ResMut<Game>,
+    mut blueprints: ResMut<Blueprints>,
+) {
+    for drop in drops.read() {
+        let FileDragAndDrop::DroppedFile { path_buf, .. } = drop else {
+            continue;
+        };
+        let file = path_buf
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        match Blueprint::load(path_buf) {
+            Ok(blueprint) => {
+                blueprints.imported = Some(format!(
+                    "Imported {file}: {} nodes, {} links",
+                    blueprint.nodes.len(),
+                    blueprint.links.len()
+                ));
+                blueprints.library.retain(|b| b.name != blueprint.name);
+                blueprints.library.push(blueprint.clone());
+                blueprints.clipboard = Some(blueprint);
+                game.tool_selection = ToolType::Stamp;
+            }
+            Err(err) => blueprints.imported = Some(format!("Could not import {file}: {err}")),
+        }
+    }
+}
+
+pub fn save_blueprint_system(
+    mut reader: MessageReader<SaveBlueprint>,
+    mut blueprints: ResMut<Blueprints>,
+) {
+    for save in reader.read() {
+        let name = save.name.trim();
+        let Some(clipboard) = blueprints.clipboard.as_mut().filter(|_| !name.is_empty()) else {
+            continue;
+        };
+        clipboard.name = name.to_string();
+        let blueprint = clipboard.clone();
+        match blueprint.save(&blueprints_dir()) {
+            Ok(path) => info!("saved blueprint to {}", path.display()),
+            Err(err) => warn!("saving blueprint failed: {err}"),
+        }
+        blueprints.library.retain(|b| b.name != blueprint.name);
+        blueprints.library.push(blueprint);
+    }
+}
+
+/// export the run, then draw the board as DOT and Mermaid diagrams into the
+/// same directory.
+fn export_run_with_board(
+    log: &mut MetricsLog,
+    actions: &ActionLog,
+    board: &Blueprint,
+    last: &mut LastExport,
+) {
+    let exported = export_run(log, actions).and_then(|dir| {
+        fs::write(dir.join("architecture.dot"), to_dot(board))?;
+        fs::write(dir.join("architecture.mmd"), to_mermaid(board))?;
+        Ok(dir)
+    });
+    match exported {
+        Ok(dir) => {
+            info!("exported metrics to {}", dir.display());
+            last.0 = Some(Ok(dir));
+        }
+        Err(err) => {
+            warn!("metrics export failed: {err}");
+            last.0 = Some(Err(err.to_string()));
+        }
+    }
+}
+
+pub fn export_metrics_system(
+    mut reader: MessageReader<ExportMetrics>,
+    mut log: ResMut<MetricsLog>,
+    actions: Res<ActionLog>,
+    nodes: Query<(
+        Entity,
+        &NodeTag,
+        &BoardPos,
+        &Footprint,
+        &NodeLinks,
+        Option<&NodeName>,
+        ConfigTargetReadOnly,
+    )>,
+    mut last: ResMut<LastExport>,
+) {
+    if reader.read().count() > 0 {
+        let board = Blueprint::capture("board", nodes.iter());
+        export_run_with_board(&mut log, &actions, &board, &mut last);
+    }
+}
+
+pub fn auto_export_system(
+    mut log: ResMut<MetricsLog>,
+    actions: Res<ActionLog>,
+    nodes: Query<(
+        Entity,
+        &NodeTag,
+        &BoardPos,
+        &Footprint,
+        &NodeLinks,
+        Option<&NodeName>,
+        ConfigTargetReadOnly,
+    )>,
+    mut last: ResMut<LastExport>,
+) {
+    let board = Blueprint::capture("board", nodes.iter());
+    export_run_with_board(&mut log, &actions, &board, &mut last);
+}
+
+/// Ctrl+C copies the selected nodes, Ctrl+V picks up the copy for stamping
+/// and Escape puts it down again.
+pub fn blueprint_hotkeys_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut game: ResMut<Game>,
+    mut blueprints: ResMut<Blueprints>,
+    nodes: Query<(
+        Entity,
+        &NodeTag,
+        &BoardPos,
+        &Footprint,
+        &NodeLinks,
+        Option<&NodeName>,
+        ConfigTargetReadOnly,
+    )>,
+) {
+    if game.tool_selection == ToolType::Stamp && keys.just_pressed(KeyCode::Escape) {
+        game.tool_selection = ToolType::Select;
+        return;
+    }
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::KeyC) {
+        let selected = nodes.iter().filter(|(_, tag, ..)| tag.selected);
+        let blueprint = Blueprint::capture("Copied group", selected);
+        if !blueprint.nodes.is_empty() {
+            blueprints.clipboard = Some(blueprint);
+        }
+    }
+    if keys.just_pressed(KeyCode::KeyV) && blueprints.clipboard.is_some() {
+        game.tool_selection = ToolType::Stamp;
+    }
+}
+
+/// show the blueprint about to be stamped under the cursor, green if all of
+/// it fits and red if it doesn't.
+pub fn stamp_ghost_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    economy: Res<Economy>,
+    blueprints: Res<Blueprints>,
+    render_assets: Res<RenderAssets>,
+    terrain: Res<Terrain>,
+    tiles: Query<(&BoardPos, &TileNodeLink), With<TileTag>>,
+    placed: Query<(&NodeTag, &BoardPos)>,
+    mut ghosts: Query<(
+        &StampGhost,
+        &mut Mesh3d,
+        &mut MeshMaterial3d<StandardMaterial>,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+) {
+    let (ToolType::Stamp, Some(at), Some(blueprint)) = (
+        game.tool_selection,
+        game.hovered_tile,
+        blueprints.clipboard.as_ref(),
+    ) else {
+        for (.., mut visibility) in &mut ghosts {
+            *visibility = Visibility::Hidden;
+        }
+        return;
+    };
+
+    let occupancy = Occupancy::of(tiles.iter());
+    let valid = blueprint
+        .check(
+            at,
+            economy.budget,
+            &terrain,
+            |zone| terrain.power_used(zone, placed.iter()),
+            |pos| occupancy.taken(pos),
+        )
+        .is_ok();
+    let material = if valid {
+        render_assets.ghost_ok.clone()
+    } else {
+        render_assets.ghost_blocked.clone()
+    };
+    let ghost_at = |node: &BlueprintNode| {
+        let mut tf = node_transform(
+            node.node_type,
+            node.anchor(at)?,
+            node.rotation,
+            NODE_SPAWN_Y,
+        );
+        tf.scale.y = 1.0 + TIER_SCALE_STEP * node.tier as f32;
+        Some(tf)
+    };
+
+    let mut shown = vec![false; blueprint.nodes.len()];
+    for (ghost, mut mesh, mut ghost_m, mut tf, mut visibility) in &mut ghosts {
+        let Some((node, ghost_tf)) = blueprint
+            .nodes
+            .get(ghost.0)
+            .and_then(|node| Some((node, ghost_at(node)?)))
+        else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        mesh.0 = render_assets.get_node_assets(node.node_type).0;
+        ghost_m.0 = material.clone();
+        *tf = ghost_tf;
+        *visibility = Visibility::Inherited;
+        shown[ghost.0] = true;
+    }
+    for (i, node) in blueprint.nodes.iter().enumerate() {
+        let (false, Some(ghost_tf)) = (shown[i], ghost_at(node)) else {
+            continue;
+        };
+        commands.spawn((
+            Mesh3d(render_assets.get_node_assets(node.node_type).0),
+            MeshMaterial3d(material.clone()),
+            ghost_tf,
+            Pickable::IGNORE,
+            StampGhost(i),
+        ));
+    }
+}
+
+/// left click with the blueprint picked up builds it there, if all of it
+/// fits. the blueprint stays picked up for the next stamp.
+pub fn stamp_blueprint_system(
+    buttons: Res<ButtonInput<MouseButton>>,
+    cam_state: Res<State<CamState>>,
+    game: Res<Game>,
+    economy: Res<Economy>,
+    blueprints: Res<Blueprints>,
+    terrain: Res<Terrain>,
+    tiles: Query<(&BoardPos, &TileNodeLink), With<TileTag>>,
+    placed: Query<(&NodeTag, &BoardPos)>,
+    mut actions: MessageWriter<PlayerAction>,
+) {
+    let (ToolType::Stamp, Some(at), Some(blueprint)) = (
+        game.tool_selection,
+        game.hovered_tile,
+        blueprints.clipboard.as_ref(),
+    ) else {
+        return;
+    };
+    if !buttons.just_pressed(MouseButton::Left)
+        || *cam_state != CamState::Fixed
+        || buttons.pressed(MouseButton::Middle)
+    {
+        return;
+    }
+
+    let occupancy = Occupancy::of(tiles.iter());
+    let fits = blueprint.check(
+        at,
+        economy.budget,
+        &terrain,
+        |zone| terrain.power_used(zone, placed.iter()),
+        |pos| occupancy.taken(pos),
+    );
+    if fits.is_err() {
+        return;
+    }
+    actions.write_batch(blueprint.actions(at));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::components::CacheNode;
+    use crate::sim::types::EvictionPolicy;
+
+    #[test]
+    fn captured_nodes_keep_their_settings() {
+        let mut world = World::new();
+        let mut cache = CacheNode::default();
+        cache.store.set_policy(EvictionPolicy::Lfu);
+        cache.store.set_capacity(321, 0);
+        world.spawn((
+            NodeTag {
+                node_type: NodeType::Cache,
+                tier: 1,
+                selected: false,
+                base_y: 0.0,
+                curr_y: 0.0,
+            },
+            BoardPos { x: 5, z: 6 },
+            NodeType::Cache.footprint(0),
+            NodeLinks::default(),
+            cache,
+        ));
+
+        let mut nodes = world.query::<(
+            Entity,
+            &NodeTag,
+            &BoardPos,
+            &Footprint,
+            &NodeLinks,
+            Option<&NodeName>,
+            ConfigTargetReadOnly,
+        )>();
+        let blueprint = Blueprint::capture("copy", nodes.iter(&world));
+        assert_eq!(
+            blueprint.nodes[0].config,
+            [
+                NodeConfig::SetCachePolicy(EvictionPolicy::Lfu),
+                NodeConfig::SetCacheCapacity(321),
+            ]
+        );
+
+        let at = BoardPos { x: 1, z: 1 };
+        let configured: Vec<PlayerAction> = blueprint
+            .actions(at)
+            .into_iter()
+            .filter(
+                |action| matches!(action, PlayerAction::Configure { at: pos, .. } if *pos == at),
+            )
+            .collect();
+        assert_eq!(configured.len(), 2);
+    }
+}
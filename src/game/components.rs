@@ -1,6 +1,7 @@
 use super::types::NodeType;
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Component, Clone, Copy, Debug)]
 pub struct TileNodeLink {
@@ -8,6 +9,61 @@ pub struct TileNodeLink {
     pub node: Option<Entity>,
 }
 
+/// grid coordinates of a tile, and of the node standing on it.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct BoardPos {
+    pub x: usize,
+    pub z: usize,
+}
+
+/// the tiles a node covers, counted from its `BoardPos` towards +x and +z.
+/// width and depth are already swapped for odd quarter turns.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Footprint {
+    pub width: usize,
+    pub depth: usize,
+    /// quarter turns around y, 0..4.
+    pub rotation: u8,
+}
+
+impl Footprint {
+    pub fn new(width: usize, depth: usize, rotation: u8) -> Self {
+        let rotation = rotation % 4;
+        let (width, depth) = if rotation % 2 == 1 {
+            (depth, width)
+        } else {
+            (width, depth)
+        };
+        Self {
+            width,
+            depth,
+            rotation,
+        }
+    }
+
+    pub fn contains(&self, anchor: BoardPos, pos: BoardPos) -> bool {
+        (anchor.x..anchor.x + self.width).contains(&pos.x)
+            && (anchor.z..anchor.z + self.depth).contains(&pos.z)
+    }
+
+    pub fn tiles(self, anchor: BoardPos) -> impl Iterator<Item = BoardPos> {
+        (anchor.z..anchor.z + self.depth)
+            .flat_map(move |z| (anchor.x..anchor.x + self.width).map(move |x| BoardPos { x, z }))
+    }
+
+    /// world offset from the anchor tile to the middle of the footprint.
+    pub fn center_offset(&self) -> (f32, f32) {
+        ((self.width - 1) as f32 / 2.0, (self.depth - 1) as f32 / 2.0)
+    }
+}
+
+/// the terrain under a tile, copied in when the board is built.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct TileProps {
+    pub zone: Option<usize>,
+    pub blocked: bool,
+}
+
 #[derive(Component)]
 pub struct TileTag {
     pub selected: bool,
@@ -15,10 +71,66 @@ pub struct TileTag {
     pub curr_y: f32,
 }
 
+/// a name the player gave a node.
+#[derive(Component, Clone, Debug)]
+pub struct NodeName(pub String);
+
+/// what was paid for a node, some of it comes back when it's deleted.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Paid(pub i64);
+
 #[derive(Component)]
 pub struct NodeTag {
     pub node_type: NodeType,
+    /// vertical scaling step, see `MAX_TIER`.
+    pub tier: u8,
     pub selected: bool,
     pub base_y: f32,
     pub curr_y: f32,
 }
+
+/// directed connections from a node to the nodes it sends requests to.
+#[derive(Component, Default, Clone, Debug)]
+pub struct NodeLinks {
+    pub out: Vec<Entity>,
+}
+
+/// the see-through node that follows the cursor while placing.
+#[derive(Component)]
+pub struct PlacementGhost;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn odd_turns_swap_width_and_depth() {
+        let queue = Footprint::new(1, 3, 1);
+        assert_eq!((queue.width, queue.depth, queue.rotation), (3, 1, 1));
+        let queue = Footprint::new(1, 3, 6);
+        assert_eq!((queue.width, queue.depth, queue.rotation), (1, 3, 2));
+    }
+
+    #[test]
+    fn tiles_are_the_ones_it_contains() {
+        let anchor = BoardPos { x: 4, z: 7 };
+        let footprint = Footprint::new(2, 3, 0);
+        let tiles: Vec<BoardPos> = footprint.tiles(anchor).collect();
+        assert_eq!(tiles.len(), 6);
+        for z in 5..12 {
+            for x in 2..8 {
+                let pos = BoardPos { x, z };
+                assert_eq!(footprint.contains(anchor, pos), tiles.contains(&pos));
+            }
+        }
+        assert_eq!(tiles[0], anchor);
+        assert_eq!(tiles[5], BoardPos { x: 5, z: 9 });
+    }
+
+    #[test]
+    fn center_sits_between_the_tiles() {
+        assert_eq!(Footprint::new(1, 1, 0).center_offset(), (0.0, 0.0));
+        assert_eq!(Footprint::new(2, 2, 0).center_offset(), (0.5, 0.5));
+        assert_eq!(Footprint::new(1, 3, 1).center_offset(), (1.0, 0.0));
+    }
+}
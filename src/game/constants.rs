@@ -4,20 +4,62 @@ use bevy::prelude::*;
 pub const GAME_BOARD_SIZE_X: usize = 32;
 pub const GAME_BOARD_SIZE_Z: usize = 32;
 
+// Economy
+pub const START_BUDGET: i64 = 10_000;
+// share of a node's price given back when it is deleted
+pub const REFUND_RATIO: f64 = 0.5;
+// vertical scaling steps, every node is placed at tier 0
+pub const MAX_TIER: u8 = 2;
+
+// Terrain, extra ticks for a request between zones
+pub const ZONE_CROSSING_TICKS: u64 = 1;
+pub const REGION_CROSSING_TICKS: u64 = 4;
+// and one more for every this many km a link covers
+pub const KM_PER_LATENCY_TICK: f64 = 4_000.0;
+pub const EARTH_RADIUS_KM: f64 = 6_371.0;
+
 // Tile and node spawn heights
 pub const TILE_SPAWN_Y: f32 = 0.0;
-pub const TILE_SPAWN_DELAY: u64 = 50;
 pub const NODE_SPAWN_Y: f32 = 0.26;
 pub const SPAWN_FALL_Y: f32 = 0.5;
+// a new board's tiles drop in one after another over this long
+pub const SPAWN_SEQUENCE_SECS: f32 = 1.5;
+pub const SPAWN_DROP_SECS: f32 = 0.35;
+pub const SPAWN_EASE: EaseFunction = EaseFunction::BackOut;
 
 pub const TILE_LIFT_Y: f32 = 0.10;
 pub const NODE_HOVER_LIFT: f32 = 0.18;
+// nodes grow this much taller per tier
+pub const TIER_SCALE_STEP: f32 = 0.15;
 pub const HOVER_LIFT_SPEED: f32 = 14.0;
 
+// Removal and failure animations
+pub const DESPAWN_SECS: f32 = 0.6;
+pub const SINK_DEPTH: f32 = 0.6;
+pub const DEBRIS_COUNT: usize = 8;
+pub const DEBRIS_SPEED: f32 = 1.6;
+pub const DEBRIS_GRAVITY: f32 = 9.0;
+pub const FAIL_FLICKER_SECS: f32 = 1.2;
+pub const FLICKER_HZ: f32 = 12.0;
+pub const SMOKE_PUFFS: usize = 6;
+pub const SMOKE_SECS: f32 = 1.5;
+pub const SMOKE_COLOR: Color = Color::srgba(0.85, 0.15, 0.10, 0.55);
+
+// Node link gizmos
+pub const LINK_Y: f32 = 0.45;
+pub const LINK_COLOR: Color = Color::srgb(0.45, 0.75, 0.95);
+pub const LINK_PENDING_COLOR: Color = Color::srgb(0.95, 0.80, 0.35);
+
+// Tier trim, one ring per tier around an upgraded node's base
+pub const TIER_TRIM_Y: f32 = 0.08;
+pub const TIER_TRIM_SPACING: f32 = 0.06;
+pub const TIER_TRIM_COLOR: Color = Color::srgb(0.95, 0.72, 0.30);
+
 // render resources
 pub const TILE_PATH: &str = "models/Tile.glb#Mesh0/Primitive0";
 pub const TILE_COLOR: Color = Color::srgb(0.05, 0.05, 0.08); // matte black
 pub const TILE_VFX: Color   = Color::srgb(0.20, 0.20, 0.23);
+pub const TILE_BLOCKED_COLOR: Color = Color::srgb(0.02, 0.03, 0.05);
 
 pub const INTERNET_PATH: &str = "models/Internet.glb#Mesh0/Primitive0";
 pub const INTERNET_COLOR: Color = Color::srgb(0.25, 0.22, 0.30);
@@ -54,3 +96,22 @@ pub const CACHE_VFX: Color   = Color::srgb(0.35, 0.43, 0.37);
 pub const CDN_PATH: &str = "models/CDN.glb#Mesh0/Primitive0";
 pub const CDN_COLOR: Color = Color::srgb(0.22, 0.22, 0.32);
 pub const CDN_VFX: Color   = Color::srgb(0.37, 0.37, 0.47);
+
+pub const GHOST_OK_COLOR: Color      = Color::srgba(0.30, 0.85, 0.40, 0.45);
+pub const GHOST_BLOCKED_COLOR: Color = Color::srgba(0.90, 0.25, 0.20, 0.45);
+
+// Replay
+pub const CHECKPOINT_TICKS: u64 = 100;
+pub const SEEK_TICKS_PER_FRAME: u64 = 2_000;
+pub const REPLAY_STEP_SMALL: u64 = 10;
+pub const REPLAY_STEP_LARGE: u64 = 100;
+
+// Headless runs
+pub const BATCH_DEFAULT_TICKS: u64 = 6_000;
+
+// Auto layout
+/// free tiles between auto-laid-out layers, and between nodes in a layer.
+pub const LAYOUT_GAP: usize = 1;
+/// passes over the columns spent untangling links.
+pub const LAYOUT_SWEEPS: usize = 8;
+pub const LAYOUT_GLIDE_SECS: f32 = 0.8;
@@ -0,0 +1,646 @@
+use super::blueprint::{Blueprint, BlueprintNode};
+use super::components::BoardPos;
+use super::constants::{GAME_BOARD_SIZE_X, GAME_BOARD_SIZE_Z, MAX_TIER};
+use super::layout::layered_layout;
+use super::types::NodeType;
+
+use std::fmt;
+use std::fmt::Write as _;
+
+/// Graphviz measures laid-out positions in points and pinned ones in inches.
+/// a node is drawn about an inch across, so an inch is taken as a tile.
+const POINTS_PER_INCH: f64 = 72.0;
+
+/// why a diagram could not be read.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DiagramError {
+    Syntax(String),
+    /// the node has no `type` attribute, and neither its id nor its label
+    /// names a node type.
+    UnknownType(String),
+    Empty,
+    /// more nodes than the board has tiles.
+    TooManyNodes(usize),
+}
+
+impl fmt::Display for DiagramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(msg) => write!(f, "syntax error: {msg}"),
+            Self::UnknownType(id) => write!(f, "node {id} has no known type"),
+            Self::Empty => write!(f, "the graph has no nodes"),
+            Self::TooManyNodes(count) => {
+                write!(
+                    f,
+                    "the graph has {count} nodes, more than the board has tiles"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for DiagramError {}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn label(node: &BlueprintNode) -> &str {
+    node.name.as_deref().unwrap_or(node.node_type.name())
+}
+
+fn dot_shape(node_type: NodeType) -> &'static str {
+    match node_type {
+        NodeType::Internet => "ellipse",
+        NodeType::Database | NodeType::Storage => "cylinder",
+        NodeType::Queue => "cds",
+        _ => "box",
+    }
+}
+
+/// the blueprint as a Graphviz digraph. node types, tiers and board
+/// positions ride along as attributes, so `parse_dot` reads it back as is.
+/// positions are pinned, one inch a tile, with y pointing up the way
+/// Graphviz draws it.
+pub fn to_dot(blueprint: &Blueprint) -> String {
+    let mut dot = format!("digraph {} {{\n    rankdir=LR;\n", quote(&blueprint.name));
+    for (i, node) in blueprint.nodes.iter().enumerate() {
+        let _ = write!(
+            dot,
+            "    n{i} [label={}, type={:?}, shape={}, pos=\"{},-{}!\"",
+            quote(label(node)),
+            node.node_type,
+            dot_shape(node.node_type),
+            node.offset.x,
+            node.offset.z,
+        );
+        if node.tier > 0 {
+            let _ = write!(dot, ", tier={}", node.tier);
+        }
+        if node.rotation > 0 {
+            let _ = write!(dot, ", rotation={}", node.rotation);
+        }
+        dot.push_str("];\n");
+    }
+    for (from, to) in &blueprint.links {
+        let _ = writeln!(dot, "    n{from} -> n{to};");
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// the blueprint as a Mermaid flowchart.
+pub fn to_mermaid(blueprint: &Blueprint) -> String {
+    let mut mermaid = format!("---\ntitle: {}\n---\nflowchart LR\n", blueprint.name);
+    for (i, node) in blueprint.nodes.iter().enumerate() {
+        let text = format!("\"{}\"", label(node).replace('"', "#quot;"));
+        let shape = match node.node_type {
+            NodeType::Internet => format!("(({text}))"),
+            NodeType::Database | NodeType::Storage => format!("[({text})]"),
+            NodeType::Queue => format!("[/{text}/]"),
+            _ => format!("[{text}]"),
+        };
+        let _ = writeln!(mermaid, "    n{i}{shape}");
+    }
+    for (from, to) in &blueprint.links {
+        let _ = writeln!(mermaid, "    n{from} --> n{to}");
+    }
+    mermaid
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    Id(String),
+    /// `->` or `--`, undirected edges are read as pointing forward.
+    Edge,
+    Punct(char),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, DiagramError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '#' => {
+                chars.by_ref().find(|c| *c == '\n');
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                chars.by_ref().find(|c| *c == '\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if last == '*' => break,
+                        Some(c) => last = c,
+                        None => return Err(DiagramError::Syntax("unclosed comment".into())),
+                    }
+                }
+            }
+            '-' if matches!(chars.peek(), Some('>' | '-')) => {
+                chars.next();
+                tokens.push(Token::Edge);
+            }
+            '{' | '}' | '[' | ']' | '=' | ';' | ',' | ':' => tokens.push(Token::Punct(c)),
+            '"' => {
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(c @ ('"' | '\\')) => id.push(c),
+                            Some(c) => {
+                                id.push('\\');
+                                id.push(c);
+                            }
+                            None => return Err(DiagramError::Syntax("unclosed string".into())),
+                        },
+                        Some(c) => id.push(c),
+                        None => return Err(DiagramError::Syntax("unclosed string".into())),
+                    }
+                }
+                tokens.push(Token::Id(id));
+            }
+            c if c.is_alphanumeric() || matches!(c, '_' | '.' | '-') || !c.is_ascii() => {
+                let mut id = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_alphanumeric() || matches!(next, '_' | '.') || !next.is_ascii()) {
+                        break;
+                    }
+                    id.push(next);
+                    chars.next();
+                }
+                tokens.push(Token::Id(id));
+            }
+            other => return Err(DiagramError::Syntax(format!("unexpected '{other}'"))),
+        }
+    }
+    Ok(tokens)
+}
+
+type Attrs = Vec<(String, String)>;
+
+fn attr<'a>(attrs: &'a Attrs, key: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .rev()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v.as_str())
+}
+
+#[derive(Default)]
+struct DotGraph {
+    name: Option<String>,
+    ids: Vec<String>,
+    attrs: Vec<Attrs>,
+    edges: Vec<(usize, usize)>,
+    node_defaults: Attrs,
+}
+
+impl DotGraph {
+    fn node(&mut self, id: &str) -> usize {
+        if let Some(i) = self.ids.iter().position(|known| known == id) {
+            return i;
+        }
+        self.ids.push(id.to_string());
+        self.attrs.push(self.node_defaults.clone());
+        self.ids.len() - 1
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, punct: char) -> bool {
+        if self.peek() == Some(&Token::Punct(punct)) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn keyword(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Id(id)) if id.eq_ignore_ascii_case(word)) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn id(&mut self) -> Result<String, DiagramError> {
+        match self.next() {
+            Some(Token::Id(id)) => Ok(id),
+            Some(other) => Err(DiagramError::Syntax(format!(
+                "expected a name, got {other:?}"
+            ))),
+            None => Err(DiagramError::Syntax("unexpected end".into())),
+        }
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), DiagramError> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(DiagramError::Syntax(format!("expected '{punct}'")))
+        }
+    }
+
+    /// a node id, with any `:port:compass` suffix dropped.
+    fn node_id(&mut self) -> Result<String, DiagramError> {
+        let id = self.id()?;
+        while self.eat(':') {
+            self.id()?;
+        }
+        Ok(id)
+    }
+
+    /// the target of an edge: one node, or a `{a b c}` list of them.
+    fn edge_operand(&mut self, graph: &mut DotGraph) -> Result<Vec<usize>, DiagramError> {
+        if !self.eat('{') {
+            return Ok(vec![graph.node(&self.node_id()?)]);
+        }
+        let mut nodes = Vec::new();
+        while !self.eat('}') {
+            if self.eat(';') || self.eat(',') {
+                continue;
+            }
+            nodes.push(graph.node(&self.node_id()?));
+        }
+        Ok(nodes)
+    }
+
+    /// one or more `[key=value, ...]` lists.
+    fn attr_lists(&mut self) -> Result<Attrs, DiagramError> {
+        let mut attrs = Attrs::new();
+        while self.eat('[') {
+            while !self.eat(']') {
+                let key = self.id()?;
+                self.expect('=')?;
+                let value = self.id()?;
+                attrs.push((key, value));
+                let _ = self.eat(',') || self.eat(';');
+            }
+        }
+        Ok(attrs)
+    }
+
+    /// statements up to the closing brace of the current block. subgraphs
+    /// are flattened into the graph.
+    fn statements(&mut self, graph: &mut DotGraph) -> Result<(), DiagramError> {
+        loop {
+            match self.next() {
+                None => return Err(DiagramError::Syntax("missing '}'".into())),
+                Some(Token::Punct('}')) => return Ok(()),
+                Some(Token::Punct(';' | ',')) => {}
+                Some(Token::Punct('{')) => self.statements(graph)?,
+                Some(Token::Id(id)) if id.eq_ignore_ascii_case("subgraph") => {
+                    if matches!(self.peek(), Some(Token::Id(_))) {
+                        self.id()?;
+                    }
+                    self.expect('{')?;
+                    self.statements(graph)?;
+                }
+                Some(Token::Id(id))
+                    if ["graph", "node", "edge"]
+                        .iter()
+                        .any(|kw| id.eq_ignore_ascii_case(kw))
+                        && self.peek() == Some(&Token::Punct('[')) =>
+                {
+                    let attrs = self.attr_lists()?;
+                    if id.eq_ignore_ascii_case("node") {
+                        graph.node_defaults.extend(attrs);
+                    }
+                }
+                Some(Token::Id(first)) => {
+                    // graph attributes like rankdir=LR
+                    if self.eat('=') {
+                        self.id()?;
+                        continue;
+                    }
+                    while self.eat(':') {
+                        self.id()?;
+                    }
+                    let mut chain = vec![vec![graph.node(&first)]];
+                    while self.peek() == Some(&Token::Edge) {
+                        self.pos += 1;
+                        chain.push(self.edge_operand(graph)?);
+                    }
+                    let attrs = self.attr_lists()?;
+
+                    if chain.len() == 1 {
+                        graph.attrs[chain[0][0]].extend(attrs);
+                        continue;
+                    }
+                    for pair in chain.windows(2) {
+                        for (&from, &to) in pair[0]
+                            .iter()
+                            .flat_map(|from| pair[1].iter().map(move |to| (from, to)))
+                        {
+                            if from != to && !graph.edges.contains(&(from, to)) {
+                                graph.edges.push((from, to));
+                            }
+                        }
+                    }
+                }
+                Some(other) => {
+                    return Err(DiagramError::Syntax(format!("unexpected {other:?}")));
+                }
+            }
+        }
+    }
+}
+
+/// a node's `pos` in inches. a trailing `!` pins the node, and pinned
+/// positions are written in inches, the ones a layout wrote out in points.
+fn parse_pos(value: &str) -> Option<(f64, f64)> {
+    let (value, pinned) = match value.strip_suffix('!') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let (x, y) = value.split_once(',')?;
+    let (x, y): (f64, f64) = (x.trim().parse().ok()?, y.trim().parse().ok()?);
+    let scale = if pinned { 1.0 } else { POINTS_PER_INCH };
+    (x.is_finite() && y.is_finite()).then(|| (x / scale, y / scale))
+}
+
+/// Graphviz positions as tile offsets, an inch a tile and y flipped to run
+/// down the board. `None` when nodes would end up on top of each other or
+/// off the board, so the caller lays them out instead.
+fn tile_offsets(nodes: &[BlueprintNode], pos: &[(f64, f64)]) -> Option<Vec<BoardPos>> {
+    let min_x = pos.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let max_y = pos.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+    let offsets: Vec<BoardPos> = pos
+        .iter()
+        .map(|(x, y)| BoardPos {
+            x: (x - min_x).round() as usize,
+            z: (max_y - y).round() as usize,
+        })
+        .collect();
+
+    let mut covered = Vec::new();
+    for (node, at) in nodes.iter().zip(&offsets) {
+        for tile in node.node_type.footprint(node.rotation).tiles(*at) {
+            if tile.x >= GAME_BOARD_SIZE_X || tile.z >= GAME_BOARD_SIZE_Z || covered.contains(&tile)
+            {
+                return None;
+            }
+            covered.push(tile);
+        }
+    }
+    Some(offsets)
+}
+
+/// read a Graphviz graph as a blueprint. each node needs a `type` attribute,
+/// or an id or label that names a type; `tier`, `rotation` and `label` are
+/// kept too. when every node has a `pos` and the nodes keep apart at an
+/// inch a tile, they keep their places, otherwise they are laid out in
+/// layers along the links.
+pub fn parse_dot(text: &str) -> Result<Blueprint, DiagramError> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+    };
+    let mut graph = DotGraph::default();
+    parser.keyword("strict");
+    if !(parser.keyword("digraph") || parser.keyword("graph")) {
+        return Err(DiagramError::Syntax("expected 'graph' or 'digraph'".into()));
+    }
+    if matches!(parser.peek(), Some(Token::Id(_))) {
+        graph.name = Some(parser.id()?);
+    }
+    parser.expect('{')?;
+    parser.statements(&mut graph)?;
+    if graph.ids.is_empty() {
+        return Err(DiagramError::Empty);
+    }
+    if graph.ids.len() > GAME_BOARD_SIZE_X * GAME_BOARD_SIZE_Z {
+        return Err(DiagramError::TooManyNodes(graph.ids.len()));
+    }
+
+    let mut nodes = Vec::new();
+    let mut placed = Vec::new();
+    for (id, attrs) in graph.ids.iter().zip(&graph.attrs) {
+        let label = attr(attrs, "label");
+        let node_type = attr(attrs, "type")
+            .or(attr(attrs, "node_type"))
+            .and_then(NodeType::parse)
+            .or_else(|| NodeType::parse(id))
+            .or_else(|| label.and_then(NodeType::parse))
+            .ok_or_else(|| DiagramError::UnknownType(id.clone()))?;
+        let name = label.unwrap_or(id);
+        let name = (NodeType::parse(name) != Some(node_type)).then(|| name.to_string());
+
+        placed.push(attr(attrs, "pos").and_then(parse_pos));
+        nodes.push(BlueprintNode {
+            offset: BoardPos { x: 0, z: 0 },
+            node_type,
+            rotation: attr(attrs, "rotation")
+                .and_then(|r| r.parse::<u8>().ok())
+                .unwrap_or(0)
+                % 4,
+            tier: attr(attrs, "tier")
+                .and_then(|t| t.parse::<u8>().ok())
+                .unwrap_or(0)
+                .min(MAX_TIER),
+            name,
+            config: Vec::new(),
+        });
+    }
+
+    let offsets = placed
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .and_then(|pos| tile_offsets(&nodes, &pos))
+        .unwrap_or_else(|| layered_layout(&nodes, &graph.edges));
+    for (node, offset) in nodes.iter_mut().zip(offsets) {
+        node.offset = offset;
+    }
+
+    Ok(Blueprint {
+        name: graph.name.unwrap_or_else(|| "Imported diagram".to_string()),
+        nodes,
+        links: graph.edges,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(x: usize, z: usize, node_type: NodeType) -> BlueprintNode {
+        BlueprintNode {
+            offset: BoardPos { x, z },
+            node_type,
+            rotation: 0,
+            tier: 0,
+            name: None,
+            config: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn tokenize_skips_comments_and_unescapes_strings() {
+        let tokens =
+            tokenize("a -> \"b \\\"c\\\"\"; // gone\n/* also\ngone */ # and this\n[x=1]").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Id("a".into()),
+                Token::Edge,
+                Token::Id("b \"c\"".into()),
+                Token::Punct(';'),
+                Token::Punct('['),
+                Token::Id("x".into()),
+                Token::Punct('='),
+                Token::Id("1".into()),
+                Token::Punct(']'),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_reports_unclosed_input() {
+        assert!(matches!(tokenize("\"open"), Err(DiagramError::Syntax(_))));
+        assert!(matches!(tokenize("/* open"), Err(DiagramError::Syntax(_))));
+    }
+
+    #[test]
+    fn parse_reads_types_labels_and_edge_chains() {
+        let blueprint = parse_dot(
+            "digraph shop {
+                node [type=Compute];
+                lb [type=LoadBalancer, tier=1];
+                db [label=\"Orders\", type=Database];
+                lb -> {api worker} -> db;
+                subgraph cluster_x { cache [type=cache] }
+                api -> cache;
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(blueprint.name, "shop");
+        let types: Vec<NodeType> = blueprint.nodes.iter().map(|n| n.node_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                NodeType::LoadBalancer,
+                NodeType::Database,
+                NodeType::Compute,
+                NodeType::Compute,
+                NodeType::Cache,
+            ]
+        );
+        assert_eq!(blueprint.nodes[0].tier, 1);
+        assert_eq!(blueprint.nodes[1].name.as_deref(), Some("Orders"));
+        assert_eq!(
+            blueprint.links,
+            vec![(0, 2), (0, 3), (2, 1), (3, 1), (2, 4)]
+        );
+    }
+
+    #[test]
+    fn parse_reports_bad_graphs() {
+        assert_eq!(parse_dot("digraph {}").unwrap_err(), DiagramError::Empty);
+        assert_eq!(
+            parse_dot("digraph { mystery }").unwrap_err(),
+            DiagramError::UnknownType("mystery".into())
+        );
+        assert!(matches!(
+            parse_dot("flowchart {}"),
+            Err(DiagramError::Syntax(_))
+        ));
+        assert!(matches!(
+            parse_dot("digraph { a -> }"),
+            Err(DiagramError::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn to_dot_round_trips() {
+        let mut db = node(2, 3, NodeType::Database);
+        db.tier = 2;
+        db.name = Some("main \"db\"".into());
+        let mut queue = node(5, 0, NodeType::Queue);
+        queue.rotation = 1;
+        let blueprint = Blueprint {
+            name: "round trip".into(),
+            nodes: vec![node(0, 1, NodeType::Internet), db, queue],
+            links: vec![(0, 1), (1, 2)],
+        };
+
+        let back = parse_dot(&to_dot(&blueprint)).unwrap();
+        assert_eq!(back.name, blueprint.name);
+        assert_eq!(back.links, blueprint.links);
+        for (a, b) in blueprint.nodes.iter().zip(&back.nodes) {
+            assert_eq!(a.offset, b.offset);
+            assert_eq!(a.node_type, b.node_type);
+            assert_eq!(a.rotation, b.rotation);
+            assert_eq!(a.tier, b.tier);
+            assert_eq!(a.name, b.name);
+        }
+    }
+
+    #[test]
+    fn laid_out_positions_are_read_in_points() {
+        // what `dot -Tdot` writes: points, y pointing up
+        let blueprint = parse_dot(
+            "digraph {
+                a [type=Compute, pos=\"27,162\"];
+                b [type=Compute, pos=\"99,162\"];
+                c [type=Cache, pos=\"27,18\"];
+            }",
+        )
+        .unwrap();
+        let offsets: Vec<BoardPos> = blueprint.nodes.iter().map(|n| n.offset).collect();
+        assert_eq!(
+            offsets,
+            vec![
+                BoardPos { x: 0, z: 0 },
+                BoardPos { x: 1, z: 0 },
+                BoardPos { x: 0, z: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn overlapping_positions_fall_back_to_layout() {
+        let blueprint = parse_dot(
+            "digraph {
+                a [type=Compute, pos=\"1,0!\"];
+                b [type=Database, pos=\"0,0!\"];
+                a -> b;
+            }",
+        )
+        .unwrap();
+        let [a, b] = [&blueprint.nodes[0], &blueprint.nodes[1]];
+        assert!(b.offset.x > a.offset.x);
+    }
+
+    #[test]
+    fn graphs_bigger_than_the_board_are_refused() {
+        let count = GAME_BOARD_SIZE_X * GAME_BOARD_SIZE_Z + 1;
+        let nodes: String = (0..count)
+            .map(|i| format!("n{i} [type=Compute]; "))
+            .collect();
+        assert_eq!(
+            parse_dot(&format!("digraph {{ {nodes} }}")).unwrap_err(),
+            DiagramError::TooManyNodes(count)
+        );
+    }
+}
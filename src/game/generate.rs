@@ -0,0 +1,306 @@
+use super::components::BoardPos;
+use super::constants::{GAME_BOARD_SIZE_X, GAME_BOARD_SIZE_Z};
+use super::replay::new_seed;
+use super::terrain::{Area, LegacyNode, Terrain, Zone};
+use super::types::NodeType;
+
+use bevy::prelude::*;
+use rand::Rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// zones a generated board can draw from: name, region and tint.
+const ZONE_PRESETS: [(&str, &str, [f32; 3]); 6] = [
+    ("us-west", "us", [0.05, 0.07, 0.11]),
+    ("us-east", "us", [0.05, 0.10, 0.09]),
+    ("eu-west", "eu", [0.10, 0.07, 0.11]),
+    ("eu-central", "eu", [0.09, 0.06, 0.08]),
+    ("ap-south", "ap", [0.10, 0.09, 0.05]),
+    ("sa-east", "sa", [0.06, 0.10, 0.06]),
+];
+
+/// the legacy system a generated board can come with: a source behind a load
+/// balancer, two computes and a database they share.
+const LEGACY_LAYOUT: [(NodeType, usize, usize); 5] = [
+    (NodeType::Internet, 0, 0),
+    (NodeType::LoadBalancer, 2, 0),
+    (NodeType::Compute, 4, 0),
+    (NodeType::Compute, 4, 2),
+    (NodeType::Database, 6, 0),
+];
+const LEGACY_LINKS: [(usize, usize); 5] = [(0, 1), (1, 2), (1, 3), (2, 4), (3, 4)];
+
+/// bits of a board code that hold the options rather than the seed.
+const OPTION_BITS: u32 = 4;
+
+/// how the next board is generated, chosen in the setup menu. with every
+/// option off the board is the standard one.
+#[derive(Resource, Clone, Debug)]
+pub struct BoardGen {
+    pub seed: u64,
+    pub obstacles: bool,
+    pub irregular: bool,
+    pub legacy: bool,
+    pub random_zones: bool,
+}
+
+impl Default for BoardGen {
+    fn default() -> Self {
+        let mut options = Self {
+            seed: 0,
+            obstacles: false,
+            irregular: false,
+            legacy: false,
+            random_zones: false,
+        };
+        options.roll_seed();
+        options
+    }
+}
+
+impl BoardGen {
+    /// a fresh seed, short enough to read out to someone else.
+    pub fn roll_seed(&mut self) {
+        self.seed = new_seed() % 1_000_000;
+    }
+
+    pub fn any(&self) -> bool {
+        self.obstacles || self.irregular || self.legacy || self.random_zones
+    }
+
+    /// the seed with the options packed under it, what players share. the
+    /// same code always gives the same board.
+    pub fn code(&self) -> u64 {
+        let options = [
+            self.obstacles,
+            self.irregular,
+            self.legacy,
+            self.random_zones,
+        ];
+        options
+            .iter()
+            .enumerate()
+            .fold(self.seed << OPTION_BITS, |code, (bit, on)| {
+                code | (u64::from(*on) << bit)
+            })
+    }
+
+    /// take the seed and options from a shared `code`.
+    pub fn set_code(&mut self, code: u64) {
+        let on = |bit: u32| code & (1 << bit) != 0;
+        self.seed = code >> OPTION_BITS;
+        self.obstacles = on(0);
+        self.irregular = on(1);
+        self.legacy = on(2);
+        self.random_zones = on(3);
+    }
+}
+
+fn random_area(rng: &mut ChaCha8Rng, max_width: usize, max_depth: usize) -> Area {
+    let width = rng.random_range(1..=max_width);
+    let depth = rng.random_range(1..=max_depth);
+    Area {
+        x: rng.random_range(0..=GAME_BOARD_SIZE_X - width),
+        z: rng.random_range(0..=GAME_BOARD_SIZE_Z - depth),
+        width,
+        depth,
+    }
+}
+
+/// split the board into vertical bands, one zone each.
+fn random_zones(rng: &mut ChaCha8Rng) -> Vec<Zone> {
+    let count = rng.random_range(2..=4);
+    let mut presets: Vec<usize> = (0..ZONE_PRESETS.len()).collect();
+    let mut cuts: Vec<usize> = Vec::new();
+    while cuts.len() < count - 1 {
+        let cut = rng.random_range(4..GAME_BOARD_SIZE_X - 4);
+        if cuts.iter().all(|c| c.abs_diff(cut) >= 4) {
+            cuts.push(cut);
+        }
+    }
+    cuts.sort_unstable();
+    cuts.push(GAME_BOARD_SIZE_X);
+
+    let mut start = 0;
+    cuts.into_iter()
+        .map(|end| {
+            let (name, region, tint) =
+                ZONE_PRESETS[presets.remove(rng.random_range(0..presets.len()))];
+            let zone = Zone {
+                name: name.to_string(),
+                region: region.to_string(),
+                area: Area {
+                    x: start,
+                    z: 0,
+                    width: end - start,
+                    depth: GAME_BOARD_SIZE_Z,
+                },
+                tint,
+                cost_multiplier: rng.random_range(18..=28) as f64 / 20.0,
+                power_limit: Some(rng.random_range(8..=16) * 5),
+            };
+            start = end;
+            zone
+        })
+        .collect()
+}
+
+/// tiles the legacy layout would cover when anchored at `origin`.
+fn legacy_tiles(origin: BoardPos) -> impl Iterator<Item = BoardPos> {
+    LEGACY_LAYOUT
+        .into_iter()
+        .flat_map(move |(node_type, dx, dz)| {
+            node_type.footprint(0).tiles(BoardPos {
+                x: origin.x + dx,
+                z: origin.z + dz,
+            })
+        })
+}
+
+/// find a free spot for the legacy system, giving up after a few tries.
+fn place_legacy(rng: &mut ChaCha8Rng, terrain: &mut Terrain) {
+    for _ in 0..64 {
+        let origin = BoardPos {
+            x: rng.random_range(0..GAME_BOARD_SIZE_X - 8),
+            z: rng.random_range(0..GAME_BOARD_SIZE_Z - 3),
+        };
+        let zone = terrain.zone_at(origin);
+        let fits = legacy_tiles(origin).all(|pos| {
+            !terrain.is_blocked(pos) && !terrain.is_missing(pos) && terrain.zone_at(pos) == zone
+        });
+        if !fits {
+            continue;
+        }
+
+        let at = |dx, dz| BoardPos {
+            x: origin.x + dx,
+            z: origin.z + dz,
+        };
+        terrain.legacy = LEGACY_LAYOUT
+            .iter()
+            .map(|(node_type, dx, dz)| LegacyNode {
+                at: at(*dx, *dz),
+                node_type: *node_type,
+                rotation: 0,
+            })
+            .collect();
+        terrain.legacy_links = LEGACY_LINKS
+            .iter()
+            .map(|(from, to)| (terrain.legacy[*from].at, terrain.legacy[*to].at))
+            .collect();
+        return;
+    }
+}
+
+/// build a board from the options. the same seed and options always give
+/// the same board, so a seed can be shared.
+pub fn generate_terrain(options: &BoardGen, base: &Terrain) -> Terrain {
+    let mut terrain = base.clone();
+    if !options.any() {
+        return terrain;
+    }
+    let mut rng = ChaCha8Rng::seed_from_u64(options.seed);
+    terrain.seed = Some(options.code());
+
+    if options.random_zones {
+        terrain.zones = random_zones(&mut rng);
+        terrain.blocked.clear();
+    }
+    if options.irregular {
+        // bite chunks out of the edges
+        for _ in 0..rng.random_range(3..=6) {
+            let mut area = random_area(&mut rng, 6, 6);
+            match rng.random_range(0..4) {
+                0 => area.x = 0,
+                1 => area.x = GAME_BOARD_SIZE_X - area.width,
+                2 => area.z = 0,
+                _ => area.z = GAME_BOARD_SIZE_Z - area.depth,
+            }
+            terrain.missing.push(area);
+        }
+    }
+    if options.obstacles {
+        for _ in 0..rng.random_range(4..=10) {
+            terrain.blocked.push(random_area(&mut rng, 3, 3));
+        }
+    }
+    if options.legacy {
+        place_legacy(&mut rng, &mut terrain);
+    }
+    terrain
+}
+
+/// the board the setup options generate, in `terrain`'s projection. the
+/// setup menu tries specs on it before the run builds them on it.
+pub fn board_for(options: &BoardGen, terrain: &Terrain) -> Terrain {
+    let base = Terrain {
+        projection: terrain.projection,
+        ..default()
+    };
+    generate_terrain(options, &base)
+}
+
+/// generate the board chosen in the setup menu, keeping its projection.
+pub fn generate_board_system(options: Res<BoardGen>, mut terrain: ResMut<Terrain>) {
+    *terrain = board_for(&options, &terrain);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(seed: u64) -> BoardGen {
+        BoardGen {
+            seed,
+            obstacles: true,
+            irregular: true,
+            legacy: true,
+            random_zones: true,
+        }
+    }
+
+    fn json(terrain: &Terrain) -> String {
+        serde_json::to_string(terrain).unwrap()
+    }
+
+    #[test]
+    fn the_same_seed_and_options_give_the_same_board() {
+        let base = Terrain::default();
+        let first = generate_terrain(&options(4242), &base);
+        let second = generate_terrain(&options(4242), &base);
+        assert_eq!(json(&first), json(&second));
+        assert_ne!(json(&first), json(&generate_terrain(&options(4243), &base)));
+    }
+
+    #[test]
+    fn codes_carry_the_options() {
+        let mut shared = options(123_456);
+        shared.irregular = false;
+        let mut entered = BoardGen::default();
+        entered.set_code(shared.code());
+        assert_eq!(entered.seed, 123_456);
+        assert_eq!(
+            (
+                entered.obstacles,
+                entered.irregular,
+                entered.legacy,
+                entered.random_zones
+            ),
+            (true, false, true, true)
+        );
+
+        let base = Terrain::default();
+        let board = generate_terrain(&entered, &base);
+        assert_eq!(json(&board), json(&generate_terrain(&shared, &base)));
+        assert_eq!(board.seed, Some(shared.code()));
+    }
+
+    #[test]
+    fn no_options_keep_the_standard_board() {
+        let mut plain = options(99);
+        plain.set_code(99 << OPTION_BITS);
+        assert!(!plain.any());
+        let base = Terrain::default();
+        assert_eq!(json(&generate_terrain(&plain, &base)), json(&base));
+    }
+}
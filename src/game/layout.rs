@@ -0,0 +1,523 @@
+use super::actions::{Occupancy, PlayerAction};
+use super::blueprint::{Blueprint, BlueprintNode};
+use super::components::{
+    BoardPos, Footprint, NodeLinks, NodeName, NodeTag, Paid, TileNodeLink, TileTag,
+};
+use super::constants::{GAME_BOARD_SIZE_X, GAME_BOARD_SIZE_Z, LAYOUT_GAP, LAYOUT_SWEEPS};
+use super::resources::Economy;
+use super::terrain::Terrain;
+use super::types::NodeType;
+
+use crate::sim::config::ConfigTargetReadOnly;
+
+use bevy::prelude::*;
+
+/// lay the selected nodes out again, or the whole board if none are
+/// selected.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct AutoLayout;
+
+/// where requests end up, so they go in the last column.
+fn is_data_tier(node_type: NodeType) -> bool {
+    matches!(node_type, NodeType::Database | NodeType::Storage)
+}
+
+/// the links that don't close a cycle, found by walking depth first from the
+/// traffic sources, then from each remaining node in turn. links from the
+/// data tier back to the nodes in front of it are left out up front.
+fn acyclic_links(nodes: &[BlueprintNode], links: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let links: Vec<(usize, usize)> = links
+        .iter()
+        .copied()
+        .filter(|(from, to)| {
+            !is_data_tier(nodes[*from].node_type) || is_data_tier(nodes[*to].node_type)
+        })
+        .collect();
+    let mut roots: Vec<usize> = (0..nodes.len()).collect();
+    roots.sort_by_key(|i| nodes[*i].node_type != NodeType::Internet);
+    let (mut on_path, mut done) = (vec![false; nodes.len()], vec![false; nodes.len()]);
+    let mut kept = Vec::new();
+    for root in roots {
+        if done[root] {
+            continue;
+        }
+        // the path walked so far, each node with the next link to follow
+        let mut path = vec![(root, 0)];
+        on_path[root] = true;
+        while let Some((node, next)) = path.last_mut() {
+            let node = *node;
+            let Some(i) = links[*next..].iter().position(|(from, _)| *from == node) else {
+                path.pop();
+                on_path[node] = false;
+                done[node] = true;
+                continue;
+            };
+            let to = links[*next + i].1;
+            *next += i + 1;
+            if on_path[to] {
+                continue;
+            }
+            kept.push((node, to));
+            if !done[to] {
+                on_path[to] = true;
+                path.push((to, 0));
+            }
+        }
+    }
+    kept
+}
+
+/// each node goes in the column after the longest chain of links leading to
+/// it. databases and storage nothing reads from go in the last column.
+fn assign_layers(nodes: &[BlueprintNode], links: &[(usize, usize)]) -> Vec<usize> {
+    let mut layer = vec![0; nodes.len()];
+    for _ in 0..nodes.len() {
+        let mut changed = false;
+        for &(from, to) in links {
+            if layer[to] <= layer[from] {
+                layer[to] = layer[from] + 1;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let last = layer.iter().copied().max().unwrap_or(0);
+    for (i, node) in nodes.iter().enumerate() {
+        if is_data_tier(node.node_type) && !links.iter().any(|(from, _)| *from == i) {
+            layer[i] = last;
+        }
+    }
+    layer
+}
+
+/// pairs of links that cross, counting only links leaving the same column.
+fn crossings(edges: &[(usize, usize)], column: &[usize], slot: &[usize]) -> usize {
+    let mut count = 0;
+    for (i, a) in edges.iter().enumerate() {
+        for b in &edges[i + 1..] {
+            if column[a.0] == column[b.0]
+                && a.0 != b.0
+                && a.1 != b.1
+                && (slot[a.0] < slot[b.0]) != (slot[a.1] < slot[b.1])
+            {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+/// a Sugiyama-style layout flowing left to right: nodes go in columns along
+/// their links, links passing over a column get a placeholder in it, each
+/// column is reordered around the average place of its neighbours to
+/// untangle the links, and nodes are lined up with what feeds them.
+/// returns offsets from the top left corner.
+pub fn layered_layout(nodes: &[BlueprintNode], links: &[(usize, usize)]) -> Vec<BoardPos> {
+    let count = nodes.len();
+    let links = acyclic_links(nodes, links);
+    let layer = assign_layers(nodes, &links);
+    let layers = layer.iter().max().map_or(0, |l| l + 1);
+
+    // placeholders are numbered after the nodes
+    let mut order: Vec<Vec<usize>> = vec![Vec::new(); layers];
+    for (i, l) in layer.iter().enumerate() {
+        order[*l].push(i);
+    }
+    let mut column_of = layer.clone();
+    let mut edges = Vec::new();
+    for &(from, to) in &links {
+        let mut prev = from;
+        let passed = layer[from] + 1..layer[to];
+        for (l, column) in order
+            .iter_mut()
+            .enumerate()
+            .take(passed.end)
+            .skip(passed.start)
+        {
+            let placeholder = column_of.len();
+            column_of.push(l);
+            column.push(placeholder);
+            edges.push((prev, placeholder));
+            prev = placeholder;
+        }
+        edges.push((prev, to));
+    }
+
+    let mut slot = vec![0; column_of.len()];
+    for column in &order {
+        for (i, v) in column.iter().enumerate() {
+            slot[*v] = i;
+        }
+    }
+    let mut best = (crossings(&edges, &column_of, &slot), order.clone());
+    for sweep in 0..LAYOUT_SWEEPS {
+        let down = sweep % 2 == 0;
+        let columns: Vec<usize> = if down {
+            (1..layers).collect()
+        } else {
+            (0..layers.saturating_sub(1)).rev().collect()
+        };
+        for l in columns {
+            let barycenter = |v: usize| {
+                let neighbours: Vec<usize> = edges
+                    .iter()
+                    .filter_map(|&(from, to)| {
+                        if down {
+                            (to == v).then_some(slot[from])
+                        } else {
+                            (from == v).then_some(slot[to])
+                        }
+                    })
+                    .collect();
+                if neighbours.is_empty() {
+                    slot[v] as f32
+                } else {
+                    neighbours.iter().sum::<usize>() as f32 / neighbours.len() as f32
+                }
+            };
+            let mut keyed: Vec<(f32, usize)> =
+                order[l].iter().map(|v| (barycenter(*v), *v)).collect();
+            keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+            order[l] = keyed.into_iter().map(|(_, v)| v).collect();
+            for (i, v) in order[l].iter().enumerate() {
+                slot[*v] = i;
+            }
+        }
+        let crossed = crossings(&edges, &column_of, &slot);
+        if crossed < best.0 {
+            best = (crossed, order.clone());
+        }
+    }
+    let order = best.1;
+
+    let footprints: Vec<Footprint> = nodes
+        .iter()
+        .map(|node| node.node_type.footprint(node.rotation))
+        .collect();
+    let mut widths = vec![0; layers];
+    for (footprint, l) in footprints.iter().zip(&layer) {
+        widths[*l] = widths[*l].max(footprint.width);
+    }
+    let columns: Vec<usize> = widths
+        .iter()
+        .scan(0, |x, width| {
+            let column = *x;
+            *x += width + LAYOUT_GAP;
+            Some(column)
+        })
+        .collect();
+
+    // top to bottom in each column, as level as can be with what feeds each
+    // node. placeholders follow their link but take no room
+    let mut row = vec![0; column_of.len()];
+    for column in &order {
+        let mut free = 0;
+        for &v in column {
+            let fed_from: Vec<usize> = edges
+                .iter()
+                .filter(|(_, to)| *to == v)
+                .map(|(from, _)| row[*from])
+                .collect();
+            let wanted = if fed_from.is_empty() {
+                free
+            } else {
+                (fed_from.iter().sum::<usize>() + fed_from.len() / 2) / fed_from.len()
+            };
+            row[v] = wanted.max(free);
+            if v < count {
+                free = row[v] + footprints[v].depth + LAYOUT_GAP;
+            }
+        }
+    }
+
+    (0..count)
+        .map(|i| BoardPos {
+            x: columns[layer[i]],
+            z: row[i],
+        })
+        .collect()
+}
+
+/// where each node of `group`, now at `current`, goes when it is laid out
+/// again. all of it goes where it moves the nodes the least; if it doesn't
+/// fit anywhere in one piece, each node goes as close to its place as it can,
+/// around blocked and taken tiles. `None` if some node has nowhere to go.
+/// moving isn't buying, so prices are left to the `Arrange` action, which
+/// charges zone price differences.
+pub fn arrange(
+    group: &Blueprint,
+    current: &[BoardPos],
+    terrain: &Terrain,
+    power_used: impl Fn(Option<usize>) -> u32,
+    occupied: impl Fn(BoardPos) -> Option<bool>,
+) -> Option<Vec<BoardPos>> {
+    let mut laid_out = group.clone();
+    for (node, offset) in laid_out
+        .nodes
+        .iter_mut()
+        .zip(layered_layout(&group.nodes, &group.links))
+    {
+        node.offset = offset;
+    }
+    let distance = |a: BoardPos, b: BoardPos| a.x.abs_diff(b.x) + a.z.abs_diff(b.z);
+    let board: Vec<BoardPos> = (0..GAME_BOARD_SIZE_Z)
+        .flat_map(|z| (0..GAME_BOARD_SIZE_X).map(move |x| BoardPos { x, z }))
+        .collect();
+
+    let whole = board
+        .iter()
+        .filter(|at| {
+            laid_out
+                .check(**at, i64::MAX, terrain, &power_used, &occupied)
+                .is_ok()
+        })
+        .min_by_key(|at| {
+            laid_out
+                .nodes
+                .iter()
+                .zip(current)
+                .filter_map(|(node, pos)| Some(distance(node.anchor(**at)?, *pos)))
+                .sum::<usize>()
+        });
+    if let Some(at) = whole {
+        return laid_out.nodes.iter().map(|node| node.anchor(*at)).collect();
+    }
+
+    // node by node, column by column, from the group's top left corner
+    let origin = BoardPos {
+        x: current.iter().map(|pos| pos.x).min().unwrap_or(0),
+        z: current.iter().map(|pos| pos.z).min().unwrap_or(0),
+    };
+    let mut queue: Vec<usize> = (0..laid_out.nodes.len()).collect();
+    queue.sort_by_key(|i| (laid_out.nodes[*i].offset.x, laid_out.nodes[*i].offset.z));
+    let mut targets = current.to_vec();
+    let mut claimed: Vec<(Footprint, BoardPos, Option<usize>, u32)> = Vec::new();
+    for i in queue {
+        let node = &laid_out.nodes[i];
+        let wanted = node.anchor(origin)?;
+        let single = Blueprint {
+            name: String::new(),
+            nodes: vec![BlueprintNode {
+                offset: BoardPos { x: 0, z: 0 },
+                ..node.clone()
+            }],
+            links: Vec::new(),
+        };
+        let mut spots = board.clone();
+        spots.sort_by_key(|pos| distance(*pos, wanted));
+        let spot = spots.into_iter().find(|at| {
+            single
+                .check(
+                    *at,
+                    i64::MAX,
+                    terrain,
+                    |zone| {
+                        power_used(zone)
+                            + claimed
+                                .iter()
+                                .filter(|(.., z, _)| *z == zone)
+                                .map(|(.., power)| power)
+                                .sum::<u32>()
+                    },
+                    |pos| {
+                        occupied(pos).map(|taken| {
+                            taken
+                                || claimed
+                                    .iter()
+                                    .any(|(footprint, anchor, ..)| footprint.contains(*anchor, pos))
+                        })
+                    },
+                )
+                .is_ok()
+        })?;
+        claimed.push((
+            node.node_type.footprint(node.rotation),
+            spot,
+            terrain.zone_at(spot),
+            node.node_type.tier_power(node.tier),
+        ));
+        targets[i] = spot;
+    }
+    Some(targets)
+}
+
+/// work out the new layout and send it as one action, so replays move the
+/// same nodes to the same tiles.
+pub fn auto_layout_system(
+    mut reader: MessageReader<AutoLayout>,
+    economy: Res<Economy>,
+    terrain: Res<Terrain>,
+    tiles: Query<(&BoardPos, &TileNodeLink), With<TileTag>>,
+    nodes: Query<(
+        Entity,
+        &NodeTag,
+        &BoardPos,
+        &Footprint,
+        &NodeLinks,
+        Option<&NodeName>,
+        ConfigTargetReadOnly,
+    )>,
+    paid: Query<&Paid>,
+    mut actions: MessageWriter<PlayerAction>,
+) {
+    if reader.read().count() == 0 {
+        return;
+    }
+    let any_selected = nodes.iter().any(|(_, tag, ..)| tag.selected);
+    let group: Vec<_> = nodes
+        .iter()
+        .filter(|(_, tag, ..)| tag.selected || !any_selected)
+        .collect();
+    if group.is_empty() {
+        return;
+    }
+    let moving: Vec<Entity> = group.iter().map(|(node_e, ..)| *node_e).collect();
+    let current: Vec<BoardPos> = group.iter().map(|(_, _, pos, ..)| **pos).collect();
+    let bought: Vec<(NodeType, u8, i64)> = group
+        .iter()
+        .map(|(node_e, tag, ..)| {
+            let paid = paid.get(*node_e).map_or(0, |paid| paid.0);
+            (tag.node_type, tag.tier, paid)
+        })
+        .collect();
+    let blueprint = Blueprint::capture("layout", group);
+
+    // nodes left where they are keep their tiles and their zone's power
+    let occupancy = Occupancy::ignoring(tiles.iter(), &moving);
+    let staying = || {
+        nodes
+            .iter()
+            .filter(|(node_e, ..)| !moving.contains(node_e))
+            .map(|(_, tag, pos, ..)| (tag, pos))
+    };
+    let Some(targets) = arrange(
+        &blueprint,
+        &current,
+        &terrain,
+        |zone| terrain.power_used(zone, staying()),
+        |pos| occupancy.taken(pos),
+    ) else {
+        warn!("no room to lay out {} nodes", current.len());
+        return;
+    };
+
+    let cost: i64 = bought
+        .iter()
+        .zip(current.iter().zip(&targets))
+        .map(|((node_type, tier, paid), (from, to))| {
+            terrain.move_cost(*node_type, *tier, *paid, *from, *to)
+        })
+        .sum();
+    if cost > 0 && cost > economy.budget {
+        warn!(
+            "laying out {} nodes costs ${cost}, more than the budget",
+            current.len()
+        );
+        return;
+    }
+
+    let moves: Vec<(BoardPos, BoardPos)> = current
+        .into_iter()
+        .zip(targets)
+        .filter(|(from, to)| from != to)
+        .collect();
+    if !moves.is_empty() {
+        actions.write(PlayerAction::Arrange { moves });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(node_type: NodeType) -> BlueprintNode {
+        BlueprintNode {
+            offset: BoardPos { x: 0, z: 0 },
+            node_type,
+            rotation: 0,
+            tier: 0,
+            name: None,
+            config: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn cycles_lose_the_link_that_closes_them() {
+        let nodes = vec![
+            node(NodeType::Internet),
+            node(NodeType::Compute),
+            node(NodeType::Compute),
+        ];
+        let kept = acyclic_links(&nodes, &[(1, 2), (2, 1), (0, 1)]);
+        assert_eq!(kept, vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn data_tier_links_back_are_dropped() {
+        let nodes = vec![node(NodeType::Compute), node(NodeType::Database)];
+        assert_eq!(acyclic_links(&nodes, &[(0, 1), (1, 0)]), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn long_chains_do_not_recurse() {
+        let count = GAME_BOARD_SIZE_X * GAME_BOARD_SIZE_Z;
+        let nodes = vec![node(NodeType::Compute); count];
+        let links: Vec<(usize, usize)> = (1..count).map(|i| (i - 1, i)).collect();
+        assert_eq!(acyclic_links(&nodes, &links), links);
+    }
+
+    #[test]
+    fn layers_follow_the_longest_chain() {
+        let nodes = vec![
+            node(NodeType::Internet),
+            node(NodeType::LoadBalancer),
+            node(NodeType::Compute),
+            node(NodeType::Compute),
+            node(NodeType::Storage),
+        ];
+        // storage nothing reads from goes last, however short its chain
+        let layer = assign_layers(&nodes, &[(0, 1), (1, 2), (2, 3), (1, 4)]);
+        assert_eq!(layer, vec![0, 1, 2, 3, 3]);
+    }
+
+    #[test]
+    fn crossings_count_links_that_swap_places() {
+        // 0 and 1 in the first column, 2 and 3 in the second
+        let column = [0, 0, 1, 1];
+        let edges = [(0, 3), (1, 2)];
+        assert_eq!(crossings(&edges, &column, &[0, 1, 0, 1]), 1);
+        assert_eq!(crossings(&edges, &column, &[0, 1, 1, 0]), 0);
+        // links sharing an end never cross
+        assert_eq!(crossings(&[(0, 2), (0, 3)], &column, &[0, 1, 1, 0]), 0);
+    }
+
+    #[test]
+    fn layout_untangles_and_keeps_nodes_apart() {
+        let nodes = vec![
+            node(NodeType::Internet),
+            node(NodeType::Compute),
+            node(NodeType::Compute),
+            node(NodeType::Database),
+            node(NodeType::Cache),
+        ];
+        let links = [(0, 1), (0, 2), (1, 4), (2, 3)];
+        let offsets = layered_layout(&nodes, &links);
+
+        for (from, to) in links {
+            assert!(offsets[from].x < offsets[to].x);
+        }
+        let mut covered = Vec::new();
+        for (node, at) in nodes.iter().zip(&offsets) {
+            for tile in node.node_type.footprint(node.rotation).tiles(*at) {
+                assert!(!covered.contains(&tile), "{tile:?} is used twice");
+                covered.push(tile);
+            }
+        }
+        // the compute feeding the cache sits on the cache's side
+        let above = |a: usize, b: usize| offsets[a].z < offsets[b].z;
+        assert_eq!(above(1, 2), above(4, 3));
+    }
+}
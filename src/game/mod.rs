@@ -1,15 +1,27 @@
+pub mod actions;
+pub mod anim;
+pub mod blueprint;
 pub mod components;
 pub mod constants;
+pub mod diagram;
+pub mod generate;
+pub mod layout;
 pub mod tiles;
 pub mod nodes;
+pub mod placement;
 pub mod types;
 pub mod plugin;
+pub mod replay;
 pub mod resources;
+pub mod scenario;
 pub mod setup;
+pub mod spec;
+pub mod spawn;
 pub mod state;
 pub mod systems;
+pub mod terrain;
 
-pub use plugin::GameLogicPlugin;
+pub use plugin::{GameCorePlugin, GameLogicPlugin};
 pub use state::GameState;
 pub use resources::Game;
 pub use types::NodeType;
\ No newline at end of file
@@ -1,27 +1,47 @@
-use super::components::{NodeTag, TileNodeLink, TileTag};
+use super::actions::{OpenContextMenu, PlayerAction};
+use super::components::{BoardPos, NodeLinks, NodeTag, TileNodeLink, TileProps, TileTag};
 use super::constants::*;
 use super::resources::{Game, RenderAssets};
+use super::spawn::SpawnDrop;
 use super::types::{NodeType, ToolType};
 
 use crate::camera::CamState;
 
 use bevy::prelude::*;
 
+/// where a node anchored at `at` sits: centered on its footprint, turned by
+/// `rotation` quarters and stretched over the tiles it covers.
+pub fn node_transform(node_type: NodeType, at: BoardPos, rotation: u8, y: f32) -> Transform {
+    let footprint = node_type.footprint(rotation);
+    let unrotated = node_type.footprint(0);
+    let (dx, dz) = footprint.center_offset();
+    Transform::from_xyz(at.x as f32 + dx, y, at.z as f32 + dz)
+        .with_rotation(Quat::from_rotation_y(
+            footprint.rotation as f32 * std::f32::consts::FRAC_PI_2,
+        ))
+        .with_scale(Vec3::new(
+            unrotated.width as f32,
+            1.0,
+            unrotated.depth as f32,
+        ))
+}
+
+/// spawn a node anchored on `tile`. the caller links every covered tile.
 pub fn spawn_node(
     commands: &mut Commands,
-    link: &mut TileNodeLink,
+    tile: Entity,
     render_assets: &RenderAssets,
     node_type: NodeType,
-    x: usize,
-    z: usize,
-) {
+    at: BoardPos,
+    rotation: u8,
+) -> Entity {
     let (mesh, mat, _vfx) = render_assets.get_node_assets(node_type);
 
     let node_e = commands
         .spawn((
             Mesh3d(mesh),
             MeshMaterial3d(mat),
-            Transform::from_xyz(x as f32, NODE_SPAWN_Y + SPAWN_FALL_Y, z as f32),
+            node_transform(node_type, at, rotation, NODE_SPAWN_Y + SPAWN_FALL_Y),
             Pickable::default(),
         ))
         .observe(node_click_event)
@@ -31,29 +51,41 @@ pub fn spawn_node(
 
     commands.entity(node_e).insert((
         TileNodeLink {
-            tile: link.tile,
+            tile,
             node: Some(node_e),
         },
         NodeTag {
             selected: false,
             node_type: node_type,
+            tier: 0,
             base_y: NODE_SPAWN_Y,
             curr_y: NODE_SPAWN_Y + SPAWN_FALL_Y,
         },
+        NodeLinks::default(),
+        at,
+        node_type.footprint(rotation),
+        SpawnDrop::default(),
+        Visibility::Hidden,
     ));
 
-    link.node = Some(node_e);
+    node_e
 }
 
 fn node_click_event(
     mut click: On<Pointer<Click>>,
-    mut commands: Commands,
-    game: ResMut<Game>,
+    mut game: ResMut<Game>,
+    mut actions: MessageWriter<PlayerAction>,
     buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
     cam_state: Res<State<CamState>>,
-    mut links: Query<&mut TileNodeLink>,
+    mut tags: Query<(Entity, &mut NodeTag)>,
+    positions: Query<&BoardPos, With<NodeTag>>,
+    mut menus: MessageWriter<OpenContextMenu>,
 ) {
-    if click.event.button != PointerButton::Primary {
+    if !matches!(
+        click.event.button,
+        PointerButton::Primary | PointerButton::Secondary
+    ) {
         return;
     }
 
@@ -63,10 +95,53 @@ fn node_click_event(
         return;
     }
 
-    if game.tool_selection != ToolType::Delete {
+    if click.event.button == PointerButton::Secondary {
+        click.propagate(false);
+        if let Ok(at) = positions.get(click.entity) {
+            menus.write(OpenContextMenu {
+                at: *at,
+                node: Some(click.entity),
+                cursor: click.pointer_location.position,
+            });
+        }
         return;
     }
 
+    match game.tool_selection {
+        ToolType::Select => {
+            click.propagate(false);
+            // shift-click grows the selection, or takes the node back out
+            let extend = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+            for (node_e, mut tag) in &mut tags {
+                if node_e == click.entity {
+                    tag.selected = !(extend && tag.selected);
+                } else if !extend {
+                    tag.selected = false;
+                }
+            }
+            return;
+        }
+        ToolType::Link => {
+            click.propagate(false);
+            let Some((source, target)) = link_nodes(&mut game, click.entity) else {
+                return;
+            };
+            match (positions.get(source), positions.get(target)) {
+                (Ok(from), Ok(to)) => {
+                    actions.write(PlayerAction::Link {
+                        from: *from,
+                        to: *to,
+                    });
+                }
+                // source was deleted while pending, start over from this node.
+                _ => game.link_source = Some(target),
+            }
+            return;
+        }
+        ToolType::Delete => {}
+        _ => return,
+    }
+
     // Compute board coords from picking hit position.
     let Some(world_position) = click.event.hit.position else {
         click.propagate(false);
@@ -84,18 +159,38 @@ fn node_click_event(
         return;
     }
 
-    let Ok(node_link) = links.get(click.entity) else {
-        return;
+    click.propagate(false);
+    actions.write(PlayerAction::DeleteNode {
+        at: BoardPos {
+            x: tile_x,
+            z: tile_z,
+        },
+    });
+}
+
+/// first click picks the source node, second click returns the (source, target)
+/// pair to connect. clicking the source again cancels.
+fn link_nodes(game: &mut Game, clicked: Entity) -> Option<(Entity, Entity)> {
+    let Some(source) = game.link_source.take() else {
+        game.link_source = Some(clicked);
+        return None;
     };
+    if source == clicked {
+        return None;
+    }
+    Some((source, clicked))
+}
 
-    let Some(node_e) = node_link.node else {
+/// connect `source` to `target`, ignoring duplicates and self links.
+pub fn connect_nodes(node_links: &mut Query<&mut NodeLinks>, source: Entity, target: Entity) {
+    if source == target {
+        return;
+    }
+    let Ok(mut out) = node_links.get_mut(source) else {
         return;
     };
-    let tile_e = node_link.tile;
-    click.propagate(false);
-    commands.entity(node_e).despawn();
-    if let Ok(mut tile_l) = links.get_mut(tile_e) {
-        tile_l.node = None;
+    if !out.out.contains(&target) {
+        out.out.push(target);
     }
 }
 
@@ -103,16 +198,15 @@ fn node_hover_over_event(
     over: On<Pointer<Over>>,
     render_assets: Res<RenderAssets>,
     mut nodes: Query<
-        (
-            &NodeTag,
-            &TileNodeLink,
-            &mut MeshMaterial3d<StandardMaterial>,
-        ),
+        (&NodeTag, &mut MeshMaterial3d<StandardMaterial>),
         (With<NodeTag>, Without<TileTag>),
     >,
-    mut tiles: Query<&mut MeshMaterial3d<StandardMaterial>, (With<TileTag>, Without<NodeTag>)>,
+    mut tiles: Query<
+        (&TileNodeLink, &mut MeshMaterial3d<StandardMaterial>),
+        (With<TileTag>, Without<NodeTag>),
+    >,
 ) {
-    let Ok((node_tag, link, mut node_m)) = nodes.get_mut(over.entity) else {
+    let Ok((node_tag, mut node_m)) = nodes.get_mut(over.entity) else {
         return;
     };
 
@@ -120,9 +214,11 @@ fn node_hover_over_event(
     let (_mesh, _mat, vfx) = render_assets.get_node_assets(node_tag.node_type);
     node_m.0 = vfx.clone();
 
-    // Hover the linked tile
-    if let Ok(mut tile_m) = tiles.get_mut(link.tile) {
-        tile_m.0 = render_assets.tile_vfx.clone();
+    // Hover every tile the node covers
+    for (link, mut tile_m) in &mut tiles {
+        if link.node == Some(over.entity) {
+            tile_m.0 = render_assets.tile_vfx.clone();
+        }
     }
 }
 
@@ -130,16 +226,19 @@ fn node_hover_out_event(
     out: On<Pointer<Out>>,
     render_assets: Res<RenderAssets>,
     mut nodes: Query<
+        (&NodeTag, &mut MeshMaterial3d<StandardMaterial>),
+        (With<NodeTag>, Without<TileTag>),
+    >,
+    mut tiles: Query<
         (
-            &NodeTag,
             &TileNodeLink,
+            &TileProps,
             &mut MeshMaterial3d<StandardMaterial>,
         ),
-        (With<NodeTag>, Without<TileTag>),
+        (With<TileTag>, Without<NodeTag>),
     >,
-    mut tiles: Query<&mut MeshMaterial3d<StandardMaterial>, (With<TileTag>, Without<NodeTag>)>,
 ) {
-    let Ok((node_tag, link, mut node_m)) = nodes.get_mut(out.entity) else {
+    let Ok((node_tag, mut node_m)) = nodes.get_mut(out.entity) else {
         return;
     };
 
@@ -147,8 +246,10 @@ fn node_hover_out_event(
     let (_mesh, mat, _vfx) = render_assets.get_node_assets(node_tag.node_type);
     node_m.0 = mat.clone();
 
-    // Unhover linked tile -> base tile material
-    if let Ok(mut tile_m) = tiles.get_mut(link.tile) {
-        tile_m.0 = render_assets.tile_mat.clone();
+    // Unhover covered tiles -> base tile material
+    for (link, props, mut tile_m) in &mut tiles {
+        if link.node == Some(out.entity) {
+            tile_m.0 = render_assets.tile_material(props);
+        }
     }
 }
@@ -0,0 +1,207 @@
+use super::actions::{Occupancy, PlayerAction, check_placement};
+use super::components::{BoardPos, NodeTag, PlacementGhost, TileNodeLink, TileProps, TileTag};
+use super::constants::NODE_SPAWN_Y;
+use super::nodes::node_transform;
+use super::resources::{Economy, Game, RenderAssets};
+use super::terrain::Terrain;
+use super::types::{NodeType, ToolType};
+
+use crate::camera::CamState;
+
+use bevy::prelude::*;
+
+/// one held-down left click with the Add tool. placements only land at the
+/// next tick, so the stroke remembers what it already asked for.
+#[derive(Default)]
+pub struct PaintStroke {
+    active: bool,
+    claimed: Vec<(BoardPos, NodeType, u8)>,
+    spent: i64,
+}
+
+impl PaintStroke {
+    fn claims(&self, pos: BoardPos) -> bool {
+        self.claimed.iter().any(|(anchor, node_type, rotation)| {
+            node_type.footprint(*rotation).contains(*anchor, pos)
+        })
+    }
+
+    fn power_in(&self, terrain: &Terrain, zone: Option<usize>) -> u32 {
+        self.claimed
+            .iter()
+            .filter(|(anchor, ..)| terrain.zone_at(*anchor) == zone)
+            .map(|(_, node_type, _)| node_type.power())
+            .sum()
+    }
+}
+
+/// with the Add tool, tint every tile the new node would cover.
+pub fn placement_preview_system(
+    game: Res<Game>,
+    render_assets: Res<RenderAssets>,
+    mut previewed: Local<Vec<Entity>>,
+    mut tiles: Query<
+        (
+            Entity,
+            &BoardPos,
+            &TileProps,
+            &mut MeshMaterial3d<StandardMaterial>,
+        ),
+        With<TileTag>,
+    >,
+) {
+    let covered: Vec<Entity> = match (game.tool_selection, game.hovered_tile) {
+        (ToolType::Add(node_type), Some(anchor)) => {
+            let footprint = node_type.footprint(game.placement_rotation);
+            tiles
+                .iter()
+                .filter(|(_, pos, ..)| footprint.contains(anchor, **pos))
+                .map(|(tile_e, ..)| tile_e)
+                .collect()
+        }
+        _ => Vec::new(),
+    };
+    if covered == *previewed {
+        return;
+    }
+
+    // the hovered tile keeps its hover tint
+    for tile_e in previewed.drain(..) {
+        if covered.contains(&tile_e) {
+            continue;
+        }
+        if let Ok((_, pos, props, mut tile_m)) = tiles.get_mut(tile_e)
+            && game.hovered_tile != Some(*pos)
+        {
+            tile_m.0 = render_assets.tile_material(props);
+        }
+    }
+    for tile_e in &covered {
+        if let Ok((.., mut tile_m)) = tiles.get_mut(*tile_e) {
+            tile_m.0 = render_assets.tile_vfx.clone();
+        }
+    }
+    *previewed = covered;
+}
+
+/// show the node about to be placed under the cursor, green if it fits and
+/// red if it doesn't.
+pub fn placement_ghost_system(
+    mut commands: Commands,
+    game: Res<Game>,
+    economy: Res<Economy>,
+    render_assets: Res<RenderAssets>,
+    terrain: Res<Terrain>,
+    tiles: Query<(&BoardPos, &TileNodeLink), With<TileTag>>,
+    placed: Query<(&NodeTag, &BoardPos)>,
+    mut ghost: Query<
+        (
+            &mut Mesh3d,
+            &mut MeshMaterial3d<StandardMaterial>,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        With<PlacementGhost>,
+    >,
+) {
+    let (ToolType::Add(node_type), Some(at)) = (game.tool_selection, game.hovered_tile) else {
+        for (.., mut visibility) in &mut ghost {
+            *visibility = Visibility::Hidden;
+        }
+        return;
+    };
+
+    let occupancy = Occupancy::of(tiles.iter());
+    let valid = check_placement(
+        node_type,
+        game.placement_rotation,
+        at,
+        economy.budget,
+        &terrain,
+        terrain.power_used(terrain.zone_at(at), placed.iter()),
+        |pos| occupancy.taken(pos),
+    )
+    .is_ok();
+    let (mesh, ..) = render_assets.get_node_assets(node_type);
+    let material = if valid {
+        render_assets.ghost_ok.clone()
+    } else {
+        render_assets.ghost_blocked.clone()
+    };
+    let transform = node_transform(node_type, at, game.placement_rotation, NODE_SPAWN_Y);
+
+    let Ok((mut ghost_mesh, mut ghost_m, mut tf, mut visibility)) = ghost.single_mut() else {
+        commands.spawn((
+            Mesh3d(mesh),
+            MeshMaterial3d(material),
+            transform,
+            Pickable::IGNORE,
+            PlacementGhost,
+        ));
+        return;
+    };
+    ghost_mesh.0 = mesh;
+    ghost_m.0 = material;
+    *tf = transform;
+    *visibility = Visibility::Inherited;
+}
+
+/// hold the left button with the Add tool to place on every tile the cursor
+/// passes over.
+pub fn paint_placement_system(
+    buttons: Res<ButtonInput<MouseButton>>,
+    cam_state: Res<State<CamState>>,
+    game: Res<Game>,
+    economy: Res<Economy>,
+    terrain: Res<Terrain>,
+    tiles: Query<(&BoardPos, &TileNodeLink), With<TileTag>>,
+    placed: Query<(&NodeTag, &BoardPos)>,
+    mut stroke: Local<PaintStroke>,
+    mut actions: MessageWriter<PlayerAction>,
+) {
+    let ToolType::Add(node_type) = game.tool_selection else {
+        *stroke = PaintStroke::default();
+        return;
+    };
+    if !buttons.pressed(MouseButton::Left) {
+        *stroke = PaintStroke::default();
+        return;
+    }
+    // strokes start on the board, not on a button dragged off the HUD
+    if buttons.just_pressed(MouseButton::Left) {
+        stroke.active = game.hovered_tile.is_some()
+            && *cam_state == CamState::Fixed
+            && !buttons.pressed(MouseButton::Middle);
+    }
+    let (true, Some(at)) = (stroke.active, game.hovered_tile) else {
+        return;
+    };
+
+    let rotation = game.placement_rotation;
+    let zone = terrain.zone_at(at);
+    let occupancy = Occupancy::of(tiles.iter());
+    let placement = check_placement(
+        node_type,
+        rotation,
+        at,
+        economy.budget - stroke.spent,
+        &terrain,
+        terrain.power_used(zone, placed.iter()) + stroke.power_in(&terrain, zone),
+        |pos| {
+            occupancy
+                .taken(pos)
+                .map(|taken| taken || stroke.claims(pos))
+        },
+    );
+    if placement.is_err() {
+        return;
+    }
+
+    actions.write(PlayerAction::PlaceNode {
+        at,
+        node_type,
+        rotation,
+    });
+    stroke.claimed.push((at, node_type, rotation));
+    stroke.spent += terrain.cost(node_type, at);
+}
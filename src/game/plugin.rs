@@ -1,30 +1,174 @@
-use super::resources::Game;
-use super::setup::setup_game_system;
+use super::actions::{
+    ApplyAction, NodeMoved, NodeRemoved, OpenContextMenu, PendingAction, PlayerAction,
+    apply_action_system,
+};
+use super::anim::{
+    animate_system, glide_system, node_failed_anim_system, node_moved_anim_system,
+    node_removed_anim_system,
+};
+use super::blueprint::{
+    Blueprints, SaveBlueprint, auto_export_system, blueprint_hotkeys_system, export_metrics_system,
+    import_dropped_system, load_blueprints_system, save_blueprint_system, stamp_blueprint_system,
+    stamp_ghost_system,
+};
+use super::generate::{BoardGen, generate_board_system};
+use super::layout::{AutoLayout, auto_layout_system};
+use super::placement::{paint_placement_system, placement_ghost_system, placement_preview_system};
+use super::replay::{
+    ActionLog, Replay, SaveBoard, apply_actions_system, checkpoint_system, not_replaying,
+    record_state_changes_system, replay_seek_system, save_board_system, start_run_system,
+};
+use super::resources::{Economy, Game};
+use super::setup::{setup_board_system, setup_lights_system};
+use super::spawn::{SpawnOrder, spawn_drop_system};
+use super::spec::SpecImport;
 use super::state::GameState;
 use super::systems::{
-    init_asset_handles_system, reset_hover_materials_system, update_selection_lift_system,
+    check_bankruptcy_system, draw_node_links_system, draw_tier_trim_system,
+    init_asset_handles_system, reset_hover_materials_system, rotate_placement_system,
+    update_selection_lift_system,
 };
+use super::terrain::Terrain;
 
 use crate::camera::{CamPlugin, CamState};
+use crate::sim::SimPlugin;
+use crate::sim::recorder::record_metrics_system;
+use crate::sim::systems::{
+    advance_clock_system, failover_system, set_node_failed_system, sim_running,
+    storage_failure_system,
+};
 use crate::ui::UIPlugin;
 
 use bevy::prelude::*;
 
+/// the full game: rendering, camera, UI and picking on top of the rules.
 pub struct GameLogicPlugin;
 
 impl Plugin for GameLogicPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((MeshPickingPlugin, CamPlugin, UIPlugin))
-            .init_resource::<Game>()
-            .init_state::<GameState>()
+        app.add_plugins((MeshPickingPlugin, CamPlugin, UIPlugin, GameCorePlugin))
+            .init_resource::<BoardGen>()
+            .init_resource::<Blueprints>()
+            .init_resource::<SpecImport>()
+            .add_message::<SaveBlueprint>()
+            .add_message::<AutoLayout>()
+            .add_systems(Startup, load_blueprints_system)
             .add_systems(
-                OnEnter(GameState::Playing),
-                (init_asset_handles_system, setup_game_system).chain(),
+                OnExit(GameState::Setup),
+                (
+                    generate_board_system.before(start_run_system),
+                    init_asset_handles_system
+                        .after(generate_board_system)
+                        .before(start_run_system),
+                    setup_lights_system.after(setup_board_system),
+                ),
             )
             .add_systems(
                 Update,
                 update_selection_lift_system.run_if(in_state(CamState::Fixed)),
             )
+            .add_systems(
+                Update,
+                spawn_drop_system.run_if(not(in_state(GameState::Setup))),
+            )
+            .add_systems(
+                Update,
+                (
+                    node_removed_anim_system,
+                    node_failed_anim_system,
+                    node_moved_anim_system,
+                    animate_system,
+                    glide_system,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    rotate_placement_system,
+                    blueprint_hotkeys_system,
+                    import_dropped_system,
+                    placement_preview_system,
+                    placement_ghost_system,
+                    paint_placement_system,
+                    stamp_ghost_system,
+                    stamp_blueprint_system,
+                    save_blueprint_system,
+                    auto_layout_system,
+                )
+                    .chain()
+                    .run_if(not(in_state(GameState::Setup))),
+            )
+            .add_systems(
+                Update,
+                (draw_node_links_system, draw_tier_trim_system)
+                    .run_if(not(in_state(GameState::Setup))),
+            )
             .add_systems(OnEnter(CamState::Free), reset_hover_materials_system);
     }
 }
+
+/// the board, the simulation and the action log, without anything that needs
+/// a window. the headless runner uses this on its own.
+pub struct GameCorePlugin;
+
+impl Plugin for GameCorePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(SimPlugin)
+            .init_resource::<Game>()
+            .init_resource::<Economy>()
+            .init_resource::<Terrain>()
+            .init_resource::<SpawnOrder>()
+            .init_resource::<ActionLog>()
+            .init_resource::<Replay>()
+            .init_resource::<PendingAction>()
+            .add_message::<PlayerAction>()
+            .add_message::<NodeRemoved>()
+            .add_message::<NodeMoved>()
+            .add_message::<OpenContextMenu>()
+            .add_message::<SaveBoard>()
+            .init_state::<GameState>()
+            .add_systems(
+                OnExit(GameState::Setup),
+                (start_run_system, setup_board_system).chain(),
+            )
+            .add_systems(OnEnter(GameState::GameOver), auto_export_system)
+            .add_systems(
+                ApplyAction,
+                (
+                    apply_action_system,
+                    set_node_failed_system,
+                    storage_failure_system,
+                    failover_system,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    apply_actions_system
+                        .before(advance_clock_system)
+                        .run_if(not(in_state(GameState::Setup))),
+                    checkpoint_system
+                        .after(record_metrics_system)
+                        .run_if(sim_running),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    record_state_changes_system,
+                    replay_seek_system,
+                    save_board_system,
+                    export_metrics_system,
+                )
+                    .run_if(not(in_state(GameState::Setup))),
+            )
+            .add_systems(
+                Update,
+                check_bankruptcy_system
+                    .run_if(in_state(GameState::Playing).or(in_state(GameState::Fast)))
+                    .run_if(not_replaying),
+            );
+    }
+}
@@ -0,0 +1,451 @@
+use super::actions::{ApplyAction, PendingAction, PlayerAction};
+use super::components::{NodeTag, TileNodeLink, TileTag};
+use super::constants::{CHECKPOINT_TICKS, SEEK_TICKS_PER_FRAME};
+use super::resources::{Economy, Game};
+use super::spec::SpecImport;
+use super::state::GameState;
+use super::terrain::Terrain;
+
+use crate::sim::export::user_data_dir;
+use crate::sim::recorder::MetricsLog;
+use crate::sim::resources::{
+    Backpressure, FastForward, QueueAcks, SimClock, SimRng, SimStats, Trace, Transit,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// an action stamped with the tick it was applied before.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LoggedAction {
+    pub tick: u64,
+    pub action: PlayerAction,
+}
+
+/// everything needed to re-run a game: its seed and terrain, the player's
+/// actions and state digests to check the re-run against.
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+pub struct ActionLog {
+    pub seed: u64,
+    /// boards saved before terrain existed load as flat.
+    #[serde(default = "Terrain::flat")]
+    pub terrain: Terrain,
+    pub actions: Vec<LoggedAction>,
+    pub checkpoints: Vec<(u64, u64)>,
+}
+
+impl ActionLog {
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(io::Error::other)
+    }
+}
+
+/// write the action log so far to the saves directory. replaying it rebuilds
+/// the board, which is what the headless runner does.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct SaveBoard;
+
+#[derive(Resource, Default)]
+pub struct Replay {
+    pub active: bool,
+    /// number of log entries already applied to the world.
+    pub cursor: usize,
+    pub seek: Option<u64>,
+    /// tick the live run had reached when the replay started.
+    pub live_tick: u64,
+    /// leave replay mode once the current seek lands.
+    pub leaving: bool,
+    pub diverged_at: Option<u64>,
+    /// live actions dropped because the board can't be changed mid-replay.
+    pub ignored: usize,
+}
+
+impl Replay {
+    pub fn start(&mut self, live_tick: u64) {
+        *self = Replay {
+            active: true,
+            live_tick,
+            seek: Some(0),
+            ..default()
+        };
+    }
+
+    pub fn seek_to(&mut self, tick: u64) {
+        self.seek = Some(tick);
+    }
+
+    pub fn go_live(&mut self) {
+        self.seek = Some(self.live_tick);
+        self.leaving = true;
+    }
+}
+
+pub fn not_replaying(replay: Res<Replay>) -> bool {
+    !replay.active
+}
+
+pub fn new_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+/// remove every node and put the simulation back at tick zero.
+fn reset_simulation(world: &mut World, seed: u64) {
+    let nodes: Vec<Entity> = world
+        .query_filtered::<Entity, With<NodeTag>>()
+        .iter(world)
+        .collect();
+    for node_e in nodes {
+        world.despawn(node_e);
+    }
+    for mut link in world
+        .query_filtered::<&mut TileNodeLink, With<TileTag>>()
+        .iter_mut(world)
+    {
+        link.node = None;
+    }
+
+    world.insert_resource(SimClock::default());
+    world.insert_resource(SimRng::seeded(seed));
+    world.insert_resource(SimStats::default());
+    world.insert_resource(Transit::default());
+    world.insert_resource(Backpressure::default());
+    world.insert_resource(QueueAcks::default());
+    world.insert_resource(Trace::default());
+    world.insert_resource(MetricsLog::default());
+    world.insert_resource(Economy::default());
+    world.resource_mut::<Game>().link_source = None;
+}
+
+/// every new game gets a fresh seed and an empty action log. the previous
+/// board is cleared so the new one starts from scratch.
+pub fn start_run_system(world: &mut World) {
+    let tiles: Vec<Entity> = world
+        .query_filtered::<Entity, With<TileTag>>()
+        .iter(world)
+        .collect();
+    for tile_e in tiles {
+        world.despawn(tile_e);
+    }
+
+    let seed = new_seed();
+    reset_simulation(world, seed);
+    // the board's legacy nodes are the first thing in the log, so replays
+    // rebuild them too
+    let terrain = world.resource::<Terrain>().clone();
+    let legacy = terrain.legacy.iter().map(|node| PlayerAction::PlaceNode {
+        at: node.at,
+        node_type: node.node_type,
+        rotation: node.rotation,
+    });
+    let links = terrain
+        .legacy_links
+        .iter()
+        .map(|(from, to)| PlayerAction::Link {
+            from: *from,
+            to: *to,
+        });
+    // then the architecture spec chosen for the run, paid for like anything
+    // else. the setup menu only starts runs it builds on
+    let spec = match world.get_resource::<SpecImport>() {
+        Some(import) => import.actions(&terrain).unwrap_or_else(|errors| {
+            for err in errors {
+                warn!("not building {}: {err}", import.label());
+            }
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    let actions = legacy
+        .chain(links)
+        .chain(spec)
+        .map(|action| LoggedAction { tick: 0, action })
+        .collect();
+    world.insert_resource(ActionLog {
+        seed,
+        terrain,
+        actions,
+        ..default()
+    });
+    world.insert_resource(Replay::default());
+}
+
+/// apply queued player actions before the next tick runs. live actions are
+/// logged; during a replay they are ignored and the log is played back.
+pub fn apply_actions_system(world: &mut World) {
+    let tick = world.resource::<SimClock>().tick;
+    let replaying = world.resource::<Replay>().active;
+
+    let live: Vec<PlayerAction> = world
+        .resource_mut::<Messages<PlayerAction>>()
+        .drain()
+        .collect();
+
+    let mut actions = Vec::new();
+    world.resource_scope(|world, mut replay: Mut<Replay>| {
+        let mut log = world.resource_mut::<ActionLog>();
+        while let Some(entry) = log.actions.get(replay.cursor)
+            && entry.tick <= tick
+        {
+            actions.push(entry.action.clone());
+            replay.cursor += 1;
+        }
+        if replaying {
+            replay.ignored += live.len();
+        } else {
+            for action in live {
+                log.actions.push(LoggedAction {
+                    tick,
+                    action: action.clone(),
+                });
+                actions.push(action);
+            }
+            replay.cursor = log.actions.len();
+        }
+    });
+
+    for action in actions {
+        world.resource_mut::<PendingAction>().0 = Some(action);
+        world.run_schedule(ApplyAction);
+    }
+}
+
+/// state changes are part of the log too, even though replays ignore them.
+pub fn record_state_changes_system(
+    mut transitions: MessageReader<StateTransitionEvent<GameState>>,
+    clock: Res<SimClock>,
+    mut replay: ResMut<Replay>,
+    mut log: ResMut<ActionLog>,
+) {
+    for transition in transitions.read() {
+        let Some(state) = transition.entered else {
+            continue;
+        };
+        if replay.active || state == GameState::Setup {
+            continue;
+        }
+        // actions still waiting to be applied, like the board's legacy
+        // nodes, must not be skipped
+        let caught_up = replay.cursor == log.actions.len();
+        log.actions.push(LoggedAction {
+            tick: clock.tick,
+            action: PlayerAction::SetState(state),
+        });
+        if caught_up {
+            replay.cursor = log.actions.len();
+        }
+    }
+}
+
+/// FNV-1a over the words' bytes. saved replays compare against digests
+/// taken by other builds, so this can't be std's hasher, which is free to
+/// change between releases.
+fn digest(words: &[u64]) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    words
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .fold(OFFSET, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+        })
+}
+
+/// digest of the simulation state, recorded live and compared on replay.
+pub fn checkpoint_system(
+    clock: Res<SimClock>,
+    stats: Res<SimStats>,
+    economy: Res<Economy>,
+    rng: Res<SimRng>,
+    mut log: ResMut<ActionLog>,
+    mut replay: ResMut<Replay>,
+) {
+    if clock.tick == 0 || !clock.tick.is_multiple_of(CHECKPOINT_TICKS) {
+        return;
+    }
+
+    let digest = digest(&[
+        stats.generated,
+        stats.completed,
+        stats.dropped,
+        stats.throttled,
+        stats.total_latency_ticks,
+        economy.budget as u64,
+        rng.0.get_word_pos() as u64,
+    ]);
+
+    match log.checkpoints.iter().find(|(tick, _)| *tick == clock.tick) {
+        Some((_, expected)) => {
+            if *expected != digest && replay.diverged_at.is_none() {
+                warn!("replay diverged at tick {}", clock.tick);
+                replay.diverged_at = Some(clock.tick);
+            }
+        }
+        None => log.checkpoints.push((clock.tick, digest)),
+    }
+}
+
+pub fn save_board_system(mut reader: MessageReader<SaveBoard>, log: Res<ActionLog>) {
+    if reader.read().count() == 0 {
+        return;
+    }
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let dir = user_data_dir().join("saves");
+    let path = dir.join(format!("board-{stamp}.json"));
+    match fs::create_dir_all(&dir).and_then(|_| log.save(&path)) {
+        Ok(()) => info!("saved board to {}", path.display()),
+        Err(err) => warn!("saving board failed: {err}"),
+    }
+}
+
+/// jump to the requested tick. seeking backwards re-runs from the seed;
+/// long seeks are spread over several frames.
+pub fn replay_seek_system(world: &mut World) {
+    let Some(target) = world.resource::<Replay>().seek else {
+        return;
+    };
+
+    if target < world.resource::<SimClock>().tick {
+        let seed = world.resource::<ActionLog>().seed;
+        reset_simulation(world, seed);
+        let mut replay = world.resource_mut::<Replay>();
+        replay.cursor = 0;
+        replay.diverged_at = None;
+    }
+
+    world.resource_mut::<FastForward>().0 = true;
+    for _ in 0..SEEK_TICKS_PER_FRAME {
+        if world.resource::<SimClock>().tick >= target {
+            break;
+        }
+        world.run_schedule(FixedUpdate);
+    }
+    world.resource_mut::<FastForward>().0 = false;
+
+    if world.resource::<SimClock>().tick >= target {
+        let mut replay = world.resource_mut::<Replay>();
+        replay.seek = None;
+        if replay.leaving {
+            replay.active = false;
+            replay.leaving = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameCorePlugin;
+    use crate::game::components::{BoardPos, NodeLinks};
+    use crate::game::resources::RenderAssets;
+    use crate::game::types::NodeType;
+    use bevy::state::app::StatesPlugin;
+
+    const TICKS: u64 = 600;
+
+    /// internet → lb → two computes, one of them deleted mid-run.
+    fn log() -> ActionLog {
+        let at = |x, z| BoardPos { x, z };
+        let place = |x, node_type| PlayerAction::PlaceNode {
+            at: at(x, 0),
+            node_type,
+            rotation: 0,
+        };
+        let link = |from, to| PlayerAction::Link {
+            from: at(from, 0),
+            to: at(to, 0),
+        };
+        let actions = [
+            (0, place(0, NodeType::Internet)),
+            (0, place(2, NodeType::LoadBalancer)),
+            (0, place(4, NodeType::Compute)),
+            (0, place(6, NodeType::Compute)),
+            (0, link(0, 2)),
+            (0, link(2, 4)),
+            (0, link(2, 6)),
+            (250, PlayerAction::DeleteNode { at: at(6, 0) }),
+        ];
+        ActionLog {
+            seed: 7,
+            terrain: Terrain::flat(),
+            actions: actions
+                .into_iter()
+                .map(|(tick, action)| LoggedAction { tick, action })
+                .collect(),
+            checkpoints: Vec::new(),
+        }
+    }
+
+    /// plays `log` back headless, the way the batch runner does. with
+    /// `frames` the `Update` schedule runs after every tick too, like the
+    /// game does while seeking.
+    fn run(log: ActionLog, frames: bool) -> World {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, GameCorePlugin))
+            .init_resource::<RenderAssets>()
+            .insert_resource(log.terrain.clone());
+        app.finish();
+        app.cleanup();
+        app.world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Paused);
+        app.update();
+
+        let world = app.world_mut();
+        world.insert_resource(SimRng::seeded(log.seed));
+        world.insert_resource(log);
+        world.insert_resource(Replay {
+            active: true,
+            ..default()
+        });
+        world.resource_mut::<FastForward>().0 = true;
+        while world.resource::<SimClock>().tick < TICKS {
+            world.run_schedule(FixedUpdate);
+            if frames {
+                world.run_schedule(Update);
+            }
+        }
+        std::mem::take(world)
+    }
+
+    #[test]
+    fn deletes_replay_the_same_with_or_without_frames() {
+        let mut first = run(log(), false);
+        let recorded = first.resource::<ActionLog>().clone();
+        assert_eq!(recorded.checkpoints.len() as u64, TICKS / CHECKPOINT_TICKS);
+        assert!(first.resource::<SimStats>().completed > 0);
+
+        let mut second = run(recorded.clone(), true);
+        assert_eq!(second.resource::<Replay>().diverged_at, None);
+        assert_eq!(
+            second.resource::<ActionLog>().checkpoints,
+            recorded.checkpoints
+        );
+
+        // nothing links to the deleted compute any more
+        for world in [&mut first, &mut second] {
+            let nodes: Vec<Entity> = world
+                .query_filtered::<Entity, With<NodeTag>>()
+                .iter(world)
+                .collect();
+            assert_eq!(nodes.len(), 3);
+            for links in world.query::<&NodeLinks>().iter(world) {
+                assert!(links.out.iter().all(|e| nodes.contains(e)));
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use super::components::{BoardPos, TileProps};
+use super::constants::START_BUDGET;
 use super::types::{NodeType, ToolType};
 
 use bevy::prelude::*;
@@ -7,11 +9,31 @@ pub struct Game {
     pub board_size_x: usize,
     pub board_size_z: usize,
     pub tool_selection: ToolType,
+    pub link_source: Option<Entity>,
+    /// quarter turns applied to nodes placed with the Add tool.
+    pub placement_rotation: u8,
+    pub hovered_tile: Option<BoardPos>,
+}
+
+#[derive(Resource)]
+pub struct Economy {
+    pub budget: i64,
+    /// anchors of the board's legacy nodes already handed out for free.
+    pub legacy_claimed: Vec<BoardPos>,
+}
+
+impl Default for Economy {
+    fn default() -> Self {
+        Self {
+            budget: START_BUDGET,
+            legacy_claimed: Vec::new(),
+        }
+    }
 }
 
 /// this resource stores all game handles (Mesh, Materials, Shaders, etc)
 /// mainly for instancing and gpu acceleration (I think)
-#[derive(Resource, Clone)]
+#[derive(Resource, Clone, Default)]
 pub struct RenderAssets {
     pub tile_mesh: Handle<Mesh>,
     pub tile_mat: Handle<StandardMaterial>,
@@ -43,9 +65,29 @@ pub struct RenderAssets {
     pub cdn_mesh: Handle<Mesh>,
     pub cdn_mat: Handle<StandardMaterial>,
     pub cdn_vfx: Handle<StandardMaterial>,
+    pub tile_blocked: Handle<StandardMaterial>,
+    /// base tile material per terrain zone, in zone order.
+    pub zone_mats: Vec<Handle<StandardMaterial>>,
+    pub ghost_ok: Handle<StandardMaterial>,
+    pub ghost_blocked: Handle<StandardMaterial>,
+    pub debris_mesh: Handle<Mesh>,
+    pub smoke_mesh: Handle<Mesh>,
+    pub smoke_mat: Handle<StandardMaterial>,
 }
 
 impl RenderAssets {
+    /// the material a tile rests at, from its zone.
+    pub fn tile_material(&self, props: &TileProps) -> Handle<StandardMaterial> {
+        if props.blocked {
+            return self.tile_blocked.clone();
+        }
+        props
+            .zone
+            .and_then(|zone| self.zone_mats.get(zone))
+            .unwrap_or(&self.tile_mat)
+            .clone()
+    }
+
     /// look up the mesh+material handles for a node type.
     pub fn get_node_assets(
         &self,
@@ -0,0 +1,39 @@
+use super::constants::BATCH_DEFAULT_TICKS;
+use super::terrain::Terrain;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// how a saved board is put under test by the headless runner.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scenario {
+    /// ticks to run, unless the game ends first.
+    pub ticks: u64,
+    /// replaces the seed the board was saved with.
+    pub seed: Option<u64>,
+    /// replaces the starting budget.
+    pub budget: Option<i64>,
+    /// replaces the terrain the board was saved with.
+    pub terrain: Option<Terrain>,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            ticks: BATCH_DEFAULT_TICKS,
+            seed: None,
+            budget: None,
+            terrain: None,
+        }
+    }
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(io::Error::other)
+    }
+}
@@ -1,20 +1,14 @@
 use super::constants::*;
+use super::components::{BoardPos, TileProps};
 use super::resources::{Game, RenderAssets};
+use super::spawn::{SpawnDrop, SpawnOrder};
+use super::terrain::Terrain;
 use super::tiles::spawn_tile;
 
-use std::{thread, time::Duration};
 use bevy::prelude::*;
 
-/// setup the game board and lights
-pub fn setup_game_system(
-    mut commands: Commands,
-    render_assets: Res<RenderAssets>,
-    mut game: ResMut<Game>,
-) {
-    game.board_size_x = GAME_BOARD_SIZE_X;
-    game.board_size_z = GAME_BOARD_SIZE_Z;
-
-    // spawn lights
+/// setup the lights over the board
+pub fn setup_lights_system(mut commands: Commands, game: Res<Game>) {
     let mid_x = game.board_size_x as f32 / 2.0;
     let mid_z = game.board_size_z as f32 / 2.0;
     commands.spawn((
@@ -46,16 +40,40 @@ pub fn setup_game_system(
         },
         Transform::from_xyz(0.0, 10.0, 0.0),
     ));
+}
+
+/// setup the game board. tiles drop in one after another in the chosen
+/// order instead of appearing all at once.
+pub fn setup_board_system(
+    mut commands: Commands,
+    render_assets: Res<RenderAssets>,
+    terrain: Res<Terrain>,
+    spawn_order: Res<SpawnOrder>,
+    mut game: ResMut<Game>,
+) {
+    // terrain, zones and link distances are all laid out for this size, so
+    // the board isn't resizable
+    game.board_size_x = GAME_BOARD_SIZE_X;
+    game.board_size_z = GAME_BOARD_SIZE_Z;
+
+    let tiles: Vec<BoardPos> = (0..game.board_size_z)
+        .flat_map(|z| (0..game.board_size_x).map(move |x| BoardPos { x, z }))
+        .filter(|pos| !terrain.is_missing(*pos))
+        .collect();
+    let delays = spawn_order.delays(&tiles);
 
-    // spawn board
-    for z in 0..game.board_size_z {
-        for x in 0..game.board_size_x {
-            spawn_tile(
-                &mut commands,
-                &render_assets,
-                Vec3::new(x as f32, TILE_SPAWN_Y, z as f32),
-            );
-            // thread::sleep(Duration::from_millis(TILE_SPAWN_DELAY));
-        }
+    for (pos, delay) in tiles.into_iter().zip(delays) {
+        let tile_e = spawn_tile(
+            &mut commands,
+            &render_assets,
+            Vec3::new(pos.x as f32, TILE_SPAWN_Y, pos.z as f32),
+            TileProps {
+                zone: terrain.zone_at(pos),
+                blocked: terrain.is_blocked(pos),
+            },
+        );
+        commands
+            .entity(tile_e)
+            .insert((SpawnDrop::after(delay), Visibility::Hidden));
     }
 }
@@ -0,0 +1,233 @@
+use super::components::{BoardPos, NodeTag, TileNodeLink, TileTag};
+use super::constants::{SPAWN_DROP_SECS, SPAWN_EASE, SPAWN_FALL_Y, SPAWN_SEQUENCE_SECS};
+
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+
+/// the order tiles drop in when a new board is built.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SpawnOrder {
+    /// diagonal sweeps from one corner to the other.
+    #[default]
+    Wave,
+    /// rings outward from the middle.
+    Spiral,
+    Random,
+}
+
+impl SpawnOrder {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Wave => "Wave",
+            Self::Spiral => "Spiral",
+            Self::Random => "Random",
+        }
+    }
+
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Wave => Self::Spiral,
+            Self::Spiral => Self::Random,
+            Self::Random => Self::Wave,
+        }
+    }
+
+    /// how long each tile waits before it starts to drop, in seconds. the
+    /// whole sequence takes `SPAWN_SEQUENCE_SECS` however big the board is.
+    pub fn delays(self, tiles: &[BoardPos]) -> Vec<f32> {
+        let (max_x, max_z) = tiles
+            .iter()
+            .fold((0, 0), |(x, z), pos| (x.max(pos.x), z.max(pos.z)));
+        // sort keys, tiles with equal keys drop together
+        let keys: Vec<f32> = match self {
+            Self::Wave => tiles.iter().map(|pos| (pos.x + pos.z) as f32).collect(),
+            Self::Spiral => {
+                let mid = Vec2::new(max_x as f32 / 2.0, max_z as f32 / 2.0);
+                tiles
+                    .iter()
+                    .map(|pos| {
+                        let offset = Vec2::new(pos.x as f32, pos.z as f32) - mid;
+                        let ring = offset.x.abs().max(offset.y.abs()).round();
+                        let turn = (offset.y.atan2(offset.x) + std::f32::consts::PI)
+                            / std::f32::consts::TAU;
+                        ring + turn.min(0.999)
+                    })
+                    .collect()
+            }
+            Self::Random => {
+                let mut keys: Vec<f32> = (0..tiles.len()).map(|i| i as f32).collect();
+                keys.shuffle(&mut rand::rng());
+                keys
+            }
+        };
+
+        let mut sorted = keys.clone();
+        sorted.sort_by(f32::total_cmp);
+        sorted.dedup();
+        let steps = sorted.len().saturating_sub(1).max(1) as f32;
+        keys.iter()
+            .map(|key| {
+                let rank = sorted.partition_point(|k| k < key);
+                rank as f32 / steps * SPAWN_SEQUENCE_SECS
+            })
+            .collect()
+    }
+}
+
+/// a tile or node still falling into place. it stays hidden for `delay`
+/// seconds, then drops `SPAWN_FALL_Y` over `SPAWN_DROP_SECS`.
+#[derive(Component, Default)]
+pub struct SpawnDrop {
+    pub delay: f32,
+    pub elapsed: f32,
+}
+
+impl SpawnDrop {
+    pub fn after(delay: f32) -> Self {
+        Self {
+            delay,
+            elapsed: 0.0,
+        }
+    }
+
+    /// advance by `dt` and return how far above its resting height the
+    /// entity is, or `None` while it is still waiting.
+    fn advance(&mut self, dt: f32) -> Option<f32> {
+        self.elapsed += dt;
+        let t = (self.elapsed - self.delay) / SPAWN_DROP_SECS;
+        (t >= 0.0).then(|| SPAWN_FALL_Y * (1.0 - SPAWN_EASE.sample_clamped(t)))
+    }
+
+    fn finished(&self) -> bool {
+        self.elapsed >= self.delay + SPAWN_DROP_SECS
+    }
+}
+
+/// drop tiles and nodes into place. nodes wait for the tile under them, and
+/// any key press lands everything at once.
+pub fn spawn_drop_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut tiles: Query<
+        (
+            Entity,
+            &mut SpawnDrop,
+            &mut TileTag,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        Without<NodeTag>,
+    >,
+    mut nodes: Query<
+        (
+            Entity,
+            &mut SpawnDrop,
+            &mut NodeTag,
+            &TileNodeLink,
+            &mut Transform,
+            &mut Visibility,
+        ),
+        Without<TileTag>,
+    >,
+) {
+    let dt = if keys.get_just_pressed().next().is_some() {
+        f32::INFINITY
+    } else {
+        time.delta_secs()
+    };
+
+    for (node_e, mut drop, mut tag, link, mut tf, mut visibility) in &mut nodes {
+        if tiles.contains(link.tile) && dt.is_finite() {
+            continue;
+        }
+        let Some(height) = drop.advance(dt) else {
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        tag.curr_y = height;
+        tf.translation.y = tag.base_y + height;
+        if drop.finished() {
+            commands.entity(node_e).remove::<SpawnDrop>();
+        }
+    }
+
+    for (tile_e, mut drop, mut tag, mut tf, mut visibility) in &mut tiles {
+        let Some(height) = drop.advance(dt) else {
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        tag.curr_y = height;
+        tf.translation.y = tag.base_y + height;
+        if drop.finished() {
+            commands.entity(tile_e).remove::<SpawnDrop>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(size: usize) -> Vec<BoardPos> {
+        (0..size)
+            .flat_map(|z| (0..size).map(move |x| BoardPos { x, z }))
+            .collect()
+    }
+
+    #[test]
+    fn every_order_fits_the_sequence() {
+        let tiles = board(6);
+        for order in [SpawnOrder::Wave, SpawnOrder::Spiral, SpawnOrder::Random] {
+            let delays = order.delays(&tiles);
+            assert_eq!(delays.len(), tiles.len());
+            assert!(
+                delays
+                    .iter()
+                    .all(|d| (0.0..=SPAWN_SEQUENCE_SECS).contains(d))
+            );
+            assert_eq!(delays.iter().copied().fold(f32::MAX, f32::min), 0.0);
+            assert_eq!(
+                delays.iter().copied().fold(0.0, f32::max),
+                SPAWN_SEQUENCE_SECS
+            );
+        }
+    }
+
+    #[test]
+    fn waves_roll_in_from_the_corner() {
+        let tiles = board(3);
+        let delays = SpawnOrder::Wave.delays(&tiles);
+        let at = |x: usize, z: usize| delays[z * 3 + x];
+        assert_eq!(at(0, 0), 0.0);
+        assert_eq!(at(1, 0), at(0, 1));
+        assert!(at(1, 0) < at(1, 1));
+        assert_eq!(at(2, 2), SPAWN_SEQUENCE_SECS);
+    }
+
+    #[test]
+    fn spirals_start_in_the_middle() {
+        let tiles = board(5);
+        let delays = SpawnOrder::Spiral.delays(&tiles);
+        let at = |x: usize, z: usize| delays[z * 5 + x];
+        assert_eq!(at(2, 2), 0.0);
+        assert!(at(1, 2) < at(0, 2));
+        assert!(at(3, 3) < at(4, 4));
+    }
+
+    #[test]
+    fn random_drops_one_tile_at_a_time() {
+        let mut delays = SpawnOrder::Random.delays(&board(4));
+        delays.sort_by(f32::total_cmp);
+        delays.dedup();
+        assert_eq!(delays.len(), 16);
+    }
+
+    #[test]
+    fn a_single_tile_drops_at_once() {
+        let tiles = [BoardPos { x: 3, z: 3 }];
+        for order in [SpawnOrder::Wave, SpawnOrder::Spiral, SpawnOrder::Random] {
+            assert_eq!(order.delays(&tiles), vec![0.0]);
+        }
+    }
+}
@@ -0,0 +1,855 @@
+use super::actions::{PlacementError, PlayerAction};
+use super::blueprint::{Blueprint, BlueprintNode};
+use super::components::BoardPos;
+use super::constants::{GAME_BOARD_SIZE_X, GAME_BOARD_SIZE_Z, MAX_TIER};
+use super::layout::layered_layout;
+use super::resources::Economy;
+use super::terrain::Terrain;
+use super::types::NodeType;
+
+use crate::sim::config::NodeConfig;
+use crate::sim::constants::{
+    CACHE_CAPACITY_STEP, MAX_REPLICATION_FACTOR, QUEUE_DEPTH_STEP, REPLICATION_LAG_STEP,
+    REQUEST_RATE_STEP, STORAGE_BANDWIDTH_STEP_MB, ZIPF_EXPONENTS,
+};
+use crate::sim::export::user_data_dir;
+use crate::sim::types::{DbRole, EvictionPolicy, Geo, KeyDistribution, RequestClass};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// the values a setting takes and the change that gives it one.
+enum Values {
+    /// a fraction from 0 to 1.
+    Ratio(fn(f32) -> NodeConfig),
+    /// a whole number from the first bound to the second.
+    Count(u64, u64, fn(u64) -> NodeConfig),
+    /// one of a few choices, by name.
+    Choice(fn() -> Vec<(String, NodeConfig)>),
+}
+
+impl Values {
+    /// the change that gives the setting `value`, `None` if it's out of
+    /// range.
+    fn config(&self, value: &SpecValue) -> Option<NodeConfig> {
+        match (self, value) {
+            (Self::Ratio(set), SpecValue::Number(n)) if (0.0..=1.0).contains(n) => {
+                Some(set(*n as f32))
+            }
+            (Self::Count(min, max, set), SpecValue::Number(n))
+                if n.fract() == 0.0 && (*min as f64..=*max as f64).contains(n) =>
+            {
+                Some(set(*n as u64))
+            }
+            (Self::Choice(choices), SpecValue::Name(name)) => choices()
+                .into_iter()
+                .find(|(choice, _)| choice.eq_ignore_ascii_case(name))
+                .map(|(_, config)| config),
+            _ => None,
+        }
+    }
+
+    /// what `config` takes, as the error puts it.
+    fn range(&self) -> String {
+        match self {
+            Self::Ratio(_) => "a number from 0 to 1".to_string(),
+            Self::Count(min, max, _) => format!("a whole number from {min} to {max}"),
+            Self::Choice(choices) => {
+                let names: Vec<String> = choices().into_iter().map(|(name, _)| name).collect();
+                format!("one of {}", names.join(", "))
+            }
+        }
+    }
+}
+
+/// a setting a spec can give: the name it goes by, the node type it belongs
+/// to and the values it takes.
+type Setting = (&'static str, NodeType, Values);
+
+/// settings the inspector only gives a floor still stop somewhere: a
+/// hundred of its steps.
+const OPEN_ENDED_STEPS: u64 = 100;
+
+const SETTINGS: [Setting; 16] = [
+    (
+        "rate",
+        NodeType::Internet,
+        Values::Count(0, REQUEST_RATE_STEP as u64 * OPEN_ENDED_STEPS, |rate| {
+            NodeConfig::SetRate(rate as u32)
+        }),
+    ),
+    (
+        "write_ratio",
+        NodeType::Internet,
+        Values::Ratio(NodeConfig::SetWriteRatio),
+    ),
+    (
+        "attack_ratio",
+        NodeType::Internet,
+        Values::Ratio(NodeConfig::SetAttackRatio),
+    ),
+    (
+        "static_ratio",
+        NodeType::Internet,
+        Values::Ratio(NodeConfig::SetStaticRatio),
+    ),
+    (
+        "key_distribution",
+        NodeType::Internet,
+        Values::Choice(|| {
+            let zipf = ZIPF_EXPONENTS.map(|s| KeyDistribution::Zipf { s });
+            [KeyDistribution::Uniform]
+                .into_iter()
+                .chain(zipf)
+                .map(|dist| {
+                    let name = match dist {
+                        KeyDistribution::Uniform => "uniform".to_string(),
+                        KeyDistribution::Zipf { s } => format!("zipf-{s}"),
+                    };
+                    (name, NodeConfig::SetKeyDistribution(dist))
+                })
+                .collect()
+        }),
+    ),
+    (
+        "geo",
+        NodeType::Internet,
+        Values::Choice(|| {
+            Geo::ALL
+                .map(|geo| (geo.name().to_string(), NodeConfig::SetGeo(geo)))
+                .to_vec()
+        }),
+    ),
+    (
+        "attack_class",
+        NodeType::Internet,
+        Values::Choice(|| {
+            RequestClass::ATTACKS
+                .map(|class| (class.name().to_string(), NodeConfig::SetAttackClass(class)))
+                .to_vec()
+        }),
+    ),
+    (
+        "policy",
+        NodeType::Cache,
+        Values::Choice(|| {
+            EvictionPolicy::ALL
+                .map(|policy| {
+                    (
+                        policy.name().to_string(),
+                        NodeConfig::SetCachePolicy(policy),
+                    )
+                })
+                .to_vec()
+        }),
+    ),
+    (
+        "capacity",
+        NodeType::Cache,
+        Values::Count(
+            0,
+            CACHE_CAPACITY_STEP as u64 * OPEN_ENDED_STEPS,
+            |capacity| NodeConfig::SetCacheCapacity(capacity as usize),
+        ),
+    ),
+    (
+        "hit_fraction",
+        NodeType::CDN,
+        Values::Ratio(NodeConfig::SetCdnHit),
+    ),
+    (
+        "replication",
+        NodeType::Storage,
+        Values::Count(1, MAX_REPLICATION_FACTOR as u64, |factor| {
+            NodeConfig::SetReplication(factor as u32)
+        }),
+    ),
+    (
+        "bandwidth",
+        NodeType::Storage,
+        Values::Count(
+            STORAGE_BANDWIDTH_STEP_MB as u64,
+            STORAGE_BANDWIDTH_STEP_MB as u64 * OPEN_ENDED_STEPS,
+            |bandwidth| NodeConfig::SetBandwidth(bandwidth as u32),
+        ),
+    ),
+    (
+        "role",
+        NodeType::Database,
+        Values::Choice(|| {
+            [DbRole::Primary, DbRole::Replica]
+                .map(|role| (role.name().to_string(), NodeConfig::SetDbRole(role)))
+                .to_vec()
+        }),
+    ),
+    (
+        "lag",
+        NodeType::Database,
+        Values::Count(
+            0,
+            REPLICATION_LAG_STEP * OPEN_ENDED_STEPS,
+            NodeConfig::SetLag,
+        ),
+    ),
+    (
+        "depth",
+        NodeType::Queue,
+        Values::Count(1, QUEUE_DEPTH_STEP as u64 * OPEN_ENDED_STEPS, |depth| {
+            NodeConfig::SetQueueDepth(depth as usize)
+        }),
+    ),
+    (
+        "concurrency",
+        NodeType::Compute,
+        Values::Count(1, OPEN_ENDED_STEPS, |concurrency| {
+            NodeConfig::SetConcurrency(concurrency as u32)
+        }),
+    ),
+];
+
+/// a setting's value as written: a number, or the name of a choice.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SpecValue {
+    Number(f64),
+    Name(String),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpecFile {
+    name: Option<String>,
+    nodes: Vec<SpecNode>,
+    /// chains of node names, `a -> b -> c`.
+    #[serde(default)]
+    links: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpecNode {
+    name: String,
+    #[serde(rename = "type")]
+    node_type: String,
+    #[serde(default)]
+    tier: u8,
+    #[serde(default)]
+    rotation: u8,
+    at: Option<[usize; 2]>,
+    #[serde(default)]
+    config: BTreeMap<String, SpecValue>,
+}
+
+/// what is wrong with a spec.
+#[derive(Clone, PartialEq, Debug)]
+pub enum SpecError {
+    Read(String),
+    Parse(String),
+    Empty,
+    /// more nodes than the board has tiles.
+    TooManyNodes(usize),
+    DuplicateName(String),
+    UnknownType {
+        node: String,
+        node_type: String,
+    },
+    UnknownSetting {
+        node: String,
+        setting: String,
+    },
+    /// a value the setting can't take. `range` says what it can.
+    OutOfRange {
+        node: String,
+        setting: String,
+        range: String,
+    },
+    MaxTier {
+        node: String,
+        tier: u8,
+    },
+    OffBoard {
+        node: String,
+        at: [usize; 2],
+    },
+    BadLink(String),
+    UnknownNode {
+        link: String,
+        name: String,
+    },
+    SelfLink(String),
+    /// some nodes have a board position and some don't.
+    PartlyPinned,
+    /// nowhere on the board takes the whole architecture.
+    DoesNotFit(PlacementError),
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(msg) => write!(f, "could not read the spec: {msg}"),
+            Self::Parse(msg) => write!(f, "{msg}"),
+            Self::Empty => write!(f, "the spec has no nodes"),
+            Self::TooManyNodes(count) => {
+                write!(
+                    f,
+                    "the spec has {count} nodes, more than the board has tiles"
+                )
+            }
+            Self::DuplicateName(name) => write!(f, "more than one node is called '{name}'"),
+            Self::UnknownType { node, node_type } => {
+                let known: Vec<String> = NodeType::all().map(|(t, _)| format!("{t:?}")).collect();
+                write!(
+                    f,
+                    "node '{node}' has unknown type '{node_type}', expected one of {}",
+                    known.join(", ")
+                )
+            }
+            Self::UnknownSetting { node, setting } => {
+                write!(f, "node '{node}' has no setting '{setting}'")
+            }
+            Self::OutOfRange {
+                node,
+                setting,
+                range,
+            } => {
+                write!(f, "'{setting}' on node '{node}' must be {range}")
+            }
+            Self::MaxTier { node, tier } => {
+                write!(f, "node '{node}' can't be built at tier {tier}")
+            }
+            Self::OffBoard { node, at: [x, z] } => {
+                write!(f, "node '{node}' is at [{x}, {z}], off the board")
+            }
+            Self::BadLink(link) => write!(f, "link '{link}' is not of the form 'a -> b'"),
+            Self::UnknownNode { link, name } => {
+                write!(f, "link '{link}' names '{name}', which is not a node")
+            }
+            Self::SelfLink(name) => write!(f, "node '{name}' is linked to itself"),
+            Self::PartlyPinned => {
+                write!(f, "either every node has a position ('at') or none does")
+            }
+            Self::DoesNotFit(err) => {
+                write!(f, "the architecture doesn't fit, it would go {err}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+/// the settings a node of `node_type` has, by name.
+fn settings(node_type: NodeType) -> impl Iterator<Item = &'static Setting> {
+    SETTINGS.iter().filter(move |(_, t, ..)| *t == node_type)
+}
+
+/// an architecture written down as YAML or TOML, read and checked and
+/// ready to be built.
+///
+/// ```yaml
+/// name: shop
+/// nodes:
+///   - { name: users, type: internet, config: { rate: 20, write_ratio: 0.3 } }
+///   - { name: edge, type: load-balancer }
+///   - { name: web, type: compute, tier: 1, config: { concurrency: 8 } }
+///   - { name: hot, type: cache, config: { policy: lfu, capacity: 500 } }
+///   - { name: orders, type: database }
+/// links:
+///   - users -> edge -> web -> hot -> orders
+/// ```
+///
+/// a node's `config` gives its settings their values: amounts and ratios as
+/// numbers, choices like the cache `policy` by name. nodes are laid out along their links unless
+/// every one of them has an `at: [x, z]` board position.
+#[derive(Clone, Debug)]
+pub struct ArchitectureSpec {
+    pub blueprint: Blueprint,
+    /// the nodes keep the board positions the spec gave them.
+    pub pinned: bool,
+}
+
+impl ArchitectureSpec {
+    /// read a `.yaml`, `.yml` or `.toml` spec. every problem found is
+    /// reported, not just the first.
+    pub fn load(path: &Path) -> Result<Self, Vec<SpecError>> {
+        let text =
+            fs::read_to_string(path).map_err(|err| vec![SpecError::Read(err.to_string())])?;
+        let toml = path.extension().is_some_and(|ext| ext == "toml");
+        let mut spec = Self::parse(&text, toml)?;
+        if spec.blueprint.name.is_empty()
+            && let Some(stem) = path.file_stem()
+        {
+            spec.blueprint.name = stem.to_string_lossy().into_owned();
+        }
+        Ok(spec)
+    }
+
+    pub fn parse(text: &str, toml: bool) -> Result<Self, Vec<SpecError>> {
+        let file: SpecFile = if toml {
+            toml::from_str(text).map_err(|err| vec![SpecError::Parse(err.to_string())])?
+        } else {
+            serde_yaml_ng::from_str(text).map_err(|err| vec![SpecError::Parse(err.to_string())])?
+        };
+        Self::build(file)
+    }
+
+    fn build(file: SpecFile) -> Result<Self, Vec<SpecError>> {
+        let mut errors = Vec::new();
+        if file.nodes.is_empty() {
+            errors.push(SpecError::Empty);
+        }
+        if file.nodes.len() > GAME_BOARD_SIZE_X * GAME_BOARD_SIZE_Z {
+            errors.push(SpecError::TooManyNodes(file.nodes.len()));
+        }
+
+        let mut nodes = Vec::new();
+        for (i, spec) in file.nodes.iter().enumerate() {
+            let node = spec.name.clone();
+            if file.nodes[..i].iter().any(|other| other.name == spec.name) {
+                errors.push(SpecError::DuplicateName(node.clone()));
+            }
+            let Some(node_type) = NodeType::parse(&spec.node_type.replace(['-', '_', ' '], ""))
+            else {
+                errors.push(SpecError::UnknownType {
+                    node,
+                    node_type: spec.node_type.clone(),
+                });
+                continue;
+            };
+            if spec.tier > MAX_TIER || (spec.tier > 0 && !node_type.upgradable()) {
+                errors.push(SpecError::MaxTier {
+                    node: node.clone(),
+                    tier: spec.tier,
+                });
+            }
+
+            if let Some(at @ [x, z]) = spec.at
+                && (x >= GAME_BOARD_SIZE_X || z >= GAME_BOARD_SIZE_Z)
+            {
+                errors.push(SpecError::OffBoard {
+                    node: node.clone(),
+                    at,
+                });
+            }
+
+            let mut config = Vec::new();
+            for (setting, value) in &spec.config {
+                let Some((_, _, values)) = settings(node_type).find(|(name, ..)| name == setting)
+                else {
+                    errors.push(SpecError::UnknownSetting {
+                        node: node.clone(),
+                        setting: setting.clone(),
+                    });
+                    continue;
+                };
+                match values.config(value) {
+                    Some(set) => config.push(set),
+                    None => errors.push(SpecError::OutOfRange {
+                        node: node.clone(),
+                        setting: setting.clone(),
+                        range: values.range(),
+                    }),
+                }
+            }
+
+            nodes.push(BlueprintNode {
+                offset: spec
+                    .at
+                    .map_or(BoardPos { x: 0, z: 0 }, |[x, z]| BoardPos { x, z }),
+                node_type,
+                rotation: spec.rotation % 4,
+                tier: spec.tier,
+                name: Some(node),
+                config,
+            });
+        }
+
+        let mut links = Vec::new();
+        for link in &file.links {
+            let names: Vec<&str> = link.split("->").map(str::trim).collect();
+            if names.len() < 2 || names.iter().any(|name| name.is_empty()) {
+                errors.push(SpecError::BadLink(link.clone()));
+                continue;
+            }
+            let mut ends = Vec::new();
+            for name in &names {
+                match file.nodes.iter().position(|node| node.name == *name) {
+                    Some(i) => ends.push(i),
+                    None => errors.push(SpecError::UnknownNode {
+                        link: link.clone(),
+                        name: name.to_string(),
+                    }),
+                }
+            }
+            if ends.len() < names.len() {
+                continue;
+            }
+            for pair in ends.windows(2) {
+                if pair[0] == pair[1] {
+                    errors.push(SpecError::SelfLink(file.nodes[pair[0]].name.clone()));
+                } else if !links.contains(&(pair[0], pair[1])) {
+                    links.push((pair[0], pair[1]));
+                }
+            }
+        }
+
+        let pinned = file.nodes.iter().filter(|node| node.at.is_some()).count();
+        if pinned > 0 && pinned < file.nodes.len() {
+            errors.push(SpecError::PartlyPinned);
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let pinned = pinned > 0;
+        if !pinned {
+            let offsets = layered_layout(&nodes, &links);
+            for (node, offset) in nodes.iter_mut().zip(offsets) {
+                node.offset = offset;
+            }
+        }
+        Ok(Self {
+            blueprint: Blueprint {
+                name: file.name.unwrap_or_default(),
+                nodes,
+                links,
+            },
+            pinned,
+        })
+    }
+
+    /// the actions that build the architecture on `terrain`, next to the
+    /// board's legacy nodes. pinned specs go where they say, the rest go in
+    /// the first spot, row by row, where all of it fits.
+    pub fn actions(&self, terrain: &Terrain, budget: i64) -> Result<Vec<PlayerAction>, SpecError> {
+        self.blueprint
+            .fit(terrain, budget, self.pinned)
+            .map(|at| self.blueprint.actions(at))
+            .map_err(SpecError::DoesNotFit)
+    }
+}
+
+fn is_spec(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "yaml" || ext == "yml" || ext == "toml")
+}
+
+/// where the setup menu looks for specs.
+pub fn specs_dir() -> PathBuf {
+    user_data_dir().join("specs")
+}
+
+/// the spec chosen in the setup menu or on the command line, built when the
+/// next run starts.
+#[derive(Resource, Default)]
+pub struct SpecImport {
+    pub path: Option<PathBuf>,
+    pub spec: Option<ArchitectureSpec>,
+    pub errors: Vec<SpecError>,
+}
+
+impl SpecImport {
+    pub fn select(&mut self, path: Option<PathBuf>) {
+        self.spec = None;
+        self.errors.clear();
+        if let Some(path) = &path {
+            match ArchitectureSpec::load(path) {
+                Ok(spec) => self.spec = Some(spec),
+                Err(errors) => self.errors = errors,
+            }
+        }
+        self.path = path;
+    }
+
+    /// move on to the next spec in the specs directory, then to none.
+    pub fn cycle(&mut self) {
+        let mut paths: Vec<PathBuf> = fs::read_dir(specs_dir())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| is_spec(path))
+            .collect();
+        paths.sort();
+        let next = match &self.path {
+            None => paths.first().cloned(),
+            Some(current) => paths
+                .iter()
+                .position(|path| path == current)
+                .and_then(|i| paths.get(i + 1))
+                .cloned(),
+        };
+        self.select(next);
+    }
+
+    /// the actions that build the chosen spec when a run starts on
+    /// `terrain`, with the budget every run starts with. the setup menu
+    /// only starts a run this succeeds for.
+    pub fn actions(&self, terrain: &Terrain) -> Result<Vec<PlayerAction>, Vec<SpecError>> {
+        if !self.errors.is_empty() {
+            return Err(self.errors.clone());
+        }
+        match &self.spec {
+            Some(spec) => spec
+                .actions(terrain, Economy::default().budget)
+                .map_err(|err| vec![err]),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        self.path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map_or("None".to_string(), |name| {
+                name.to_string_lossy().into_owned()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn errors(yaml: &str) -> Vec<SpecError> {
+        ArchitectureSpec::parse(yaml, false).unwrap_err()
+    }
+
+    #[test]
+    fn parses_yaml_and_toml_alike() {
+        let yaml = ArchitectureSpec::parse(
+            "name: shop
+nodes:
+  - { name: users, type: internet, config: { rate: 20, write_ratio: 0.3, geo: eu } }
+  - { name: edge, type: load-balancer }
+  - { name: web, type: compute, tier: 1, config: { concurrency: 2 } }
+links:
+  - users -> edge -> web
+",
+            false,
+        )
+        .unwrap();
+        let toml = ArchitectureSpec::parse(
+            r#"name = "shop"
+links = ["users -> edge -> web"]
+[[nodes]]
+name = "users"
+type = "internet"
+config = { rate = 20, write_ratio = 0.3, geo = "EU" }
+[[nodes]]
+name = "edge"
+type = "load_balancer"
+[[nodes]]
+name = "web"
+type = "compute"
+tier = 1
+config = { concurrency = 2 }
+"#,
+            true,
+        )
+        .unwrap();
+
+        for spec in [yaml, toml] {
+            assert_eq!(spec.blueprint.name, "shop");
+            assert!(!spec.pinned);
+            assert_eq!(spec.blueprint.links, vec![(0, 1), (1, 2)]);
+            let nodes = &spec.blueprint.nodes;
+            assert_eq!(
+                nodes[0].config,
+                vec![
+                    NodeConfig::SetGeo(Geo::Europe),
+                    NodeConfig::SetRate(20),
+                    NodeConfig::SetWriteRatio(0.3),
+                ]
+            );
+            assert_eq!(nodes[2].config, vec![NodeConfig::SetConcurrency(2)]);
+            assert_eq!(nodes[2].tier, 1);
+        }
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let found = errors(
+            "nodes:
+  - { name: a, type: compute, tier: 9 }
+  - { name: a, type: mainframe }
+  - { name: c, type: cache, config: { policy: -1, colour: 1 } }
+links:
+  - a -> ghost
+  - c
+  - c -> c
+",
+        );
+        assert_eq!(
+            found,
+            vec![
+                SpecError::MaxTier {
+                    node: "a".into(),
+                    tier: 9
+                },
+                SpecError::DuplicateName("a".into()),
+                SpecError::UnknownType {
+                    node: "a".into(),
+                    node_type: "mainframe".into()
+                },
+                SpecError::UnknownSetting {
+                    node: "c".into(),
+                    setting: "colour".into()
+                },
+                SpecError::OutOfRange {
+                    node: "c".into(),
+                    setting: "policy".into(),
+                    range: "one of LRU, LFU, FIFO, TTL".into()
+                },
+                SpecError::UnknownNode {
+                    link: "a -> ghost".into(),
+                    name: "ghost".into()
+                },
+                SpecError::BadLink("c".into()),
+                SpecError::SelfLink("c".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_values_against_their_range() {
+        let found = errors(
+            "nodes:
+  - name: users
+    type: internet
+    config: { rate: 9999999999, write_ratio: 1.5, key_distribution: zipf-0.8, geo: mars }
+  - { name: web, type: compute, config: { concurrency: 2.5 } }
+  - { name: files, type: storage, config: { replication: 0 } }
+",
+        );
+        let range = |node: &str, setting: &str, range: &str| SpecError::OutOfRange {
+            node: node.into(),
+            setting: setting.into(),
+            range: range.into(),
+        };
+        assert_eq!(
+            found,
+            vec![
+                range("users", "geo", "one of NA, EU, APAC, Other"),
+                range("users", "rate", "a whole number from 0 to 500"),
+                range("users", "write_ratio", "a number from 0 to 1"),
+                range("web", "concurrency", "a whole number from 1 to 100"),
+                range("files", "replication", "a whole number from 1 to 3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn choices_go_by_name() {
+        let spec = ArchitectureSpec::parse(
+            "nodes:
+  - { name: hot, type: cache, config: { policy: lfu, capacity: 500 } }
+  - { name: db, type: database, config: { role: replica, lag: 40 } }
+  - { name: users, type: internet, config: { key_distribution: uniform, attack_class: ddos } }
+",
+            false,
+        )
+        .unwrap();
+        let configs: Vec<&[NodeConfig]> = spec
+            .blueprint
+            .nodes
+            .iter()
+            .map(|node| &node.config[..])
+            .collect();
+        assert_eq!(
+            configs,
+            [
+                &[
+                    NodeConfig::SetCacheCapacity(500),
+                    NodeConfig::SetCachePolicy(EvictionPolicy::Lfu),
+                ][..],
+                &[
+                    NodeConfig::SetLag(40),
+                    NodeConfig::SetDbRole(DbRole::Replica)
+                ],
+                &[
+                    NodeConfig::SetAttackClass(RequestClass::DdosFlood),
+                    NodeConfig::SetKeyDistribution(KeyDistribution::Uniform),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_positions() {
+        let found = errors(
+            "nodes:
+  - { name: a, type: compute, at: [0, 0] }
+  - { name: b, type: compute, at: [18446744073709551615, 0] }
+  - { name: c, type: compute }
+",
+        );
+        assert_eq!(
+            found,
+            vec![
+                SpecError::OffBoard {
+                    node: "b".into(),
+                    at: [usize::MAX, 0]
+                },
+                SpecError::PartlyPinned,
+            ]
+        );
+    }
+
+    #[test]
+    fn pinned_specs_build_where_they_say() {
+        let spec = ArchitectureSpec::parse(
+            "nodes:
+  - { name: a, type: compute, at: [2, 3] }
+  - { name: b, type: cache, at: [4, 3] }
+links: [a -> b]
+",
+            false,
+        )
+        .unwrap();
+        assert!(spec.pinned);
+        let actions = spec.actions(&Terrain::flat(), i64::MAX).unwrap();
+        assert!(matches!(
+            actions[0],
+            PlayerAction::PlaceNode {
+                at: BoardPos { x: 2, z: 3 },
+                node_type: NodeType::Compute,
+                ..
+            }
+        ));
+        assert!(matches!(
+            actions.last(),
+            Some(PlayerAction::Link {
+                from: BoardPos { x: 2, z: 3 },
+                to: BoardPos { x: 4, z: 3 },
+            })
+        ));
+        assert_eq!(
+            spec.actions(&Terrain::flat(), 0).unwrap_err(),
+            SpecError::DoesNotFit(PlacementError::InsufficientFunds)
+        );
+    }
+
+    #[test]
+    fn empty_specs_are_errors() {
+        assert_eq!(errors("nodes: []"), vec![SpecError::Empty]);
+        assert!(matches!(
+            errors("nodes: [{ name: a, type: compute, colour: red }]")[..],
+            [SpecError::Parse(_)]
+        ));
+    }
+
+    #[test]
+    fn specs_bigger_than_the_board_are_refused() {
+        let count = GAME_BOARD_SIZE_X * GAME_BOARD_SIZE_Z + 1;
+        let nodes: String = (0..count)
+            .map(|i| format!("  - {{ name: n{i}, type: compute }}\n"))
+            .collect();
+        assert_eq!(
+            errors(&format!("nodes:\n{nodes}")),
+            vec![SpecError::TooManyNodes(count)]
+        );
+    }
+}
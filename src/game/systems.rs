@@ -1,12 +1,18 @@
 use super::constants::*;
-use crate::game::components::{NodeTag, TileTag};
-use crate::game::resources::RenderAssets;
+use crate::game::components::{Footprint, NodeLinks, NodeTag, TileProps, TileTag};
+use crate::game::resources::{Economy, Game, RenderAssets};
+use crate::game::spawn::SpawnDrop;
+use crate::game::state::GameState;
+use crate::game::terrain::Terrain;
+use crate::game::types::ToolType;
 use bevy::prelude::*;
 
 pub fn init_asset_handles_system(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    terrain: Res<Terrain>,
 ) {
     let tile_mesh: Handle<Mesh> = asset_server.load(TILE_PATH);
     let tile_mat: Handle<StandardMaterial> = materials.add(StandardMaterial {
@@ -22,6 +28,26 @@ pub fn init_asset_handles_system(
         ..default()
     });
 
+    let tile_blocked: Handle<StandardMaterial> = materials.add(StandardMaterial {
+        base_color: TILE_BLOCKED_COLOR,
+        perceptual_roughness: 0.9,
+        metallic: 0.1,
+        ..default()
+    });
+    let zone_mats: Vec<Handle<StandardMaterial>> = terrain
+        .zones
+        .iter()
+        .map(|zone| {
+            let [r, g, b] = zone.tint;
+            materials.add(StandardMaterial {
+                base_color: Color::srgb(r, g, b),
+                perceptual_roughness: 0.5,
+                metallic: 0.5,
+                ..default()
+            })
+        })
+        .collect();
+
     let internet_mesh: Handle<Mesh> = asset_server.load(INTERNET_PATH);
     let internet_mat: Handle<StandardMaterial> = materials.add(StandardMaterial {
         base_color: INTERNET_COLOR,
@@ -148,10 +174,35 @@ pub fn init_asset_handles_system(
         ..default()
     });
 
+    // unlit so the ghost reads the same under any light
+    let ghost_ok: Handle<StandardMaterial> = materials.add(StandardMaterial {
+        base_color: GHOST_OK_COLOR,
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+    let ghost_blocked: Handle<StandardMaterial> = materials.add(StandardMaterial {
+        base_color: GHOST_BLOCKED_COLOR,
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+
+    let debris_mesh: Handle<Mesh> = meshes.add(Cuboid::from_length(0.08));
+    let smoke_mesh: Handle<Mesh> = meshes.add(Sphere::new(0.12));
+    let smoke_mat: Handle<StandardMaterial> = materials.add(StandardMaterial {
+        base_color: SMOKE_COLOR,
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+
     commands.insert_resource(RenderAssets {
         tile_mesh,
         tile_mat,
         tile_vfx,
+        tile_blocked,
+        zone_mats,
 
         internet_mesh,
         internet_mat,
@@ -188,19 +239,29 @@ pub fn init_asset_handles_system(
         cdn_mesh,
         cdn_mat,
         cdn_vfx,
+
+        ghost_ok,
+        ghost_blocked,
+
+        debris_mesh,
+        smoke_mesh,
+        smoke_mat,
     });
 }
 
 pub fn reset_hover_materials_system(
     render_assets: Res<RenderAssets>,
-    mut tile_mats: Query<&mut MeshMaterial3d<StandardMaterial>, (With<TileTag>, Without<NodeTag>)>,
+    mut tile_mats: Query<
+        (&TileProps, &mut MeshMaterial3d<StandardMaterial>),
+        (With<TileTag>, Without<NodeTag>),
+    >,
     mut node_mats: Query<
         (&NodeTag, &mut MeshMaterial3d<StandardMaterial>),
         (With<NodeTag>, Without<TileTag>),
     >,
 ) {
-    for mut tile_m in &mut tile_mats {
-        tile_m.0 = render_assets.tile_mat.clone();
+    for (props, mut tile_m) in &mut tile_mats {
+        tile_m.0 = render_assets.tile_material(props);
     }
 
     for (node, mut node_m) in &mut node_mats {
@@ -211,8 +272,14 @@ pub fn reset_hover_materials_system(
 
 pub fn update_selection_lift_system(
     time: Res<Time>,
-    mut tiles: Query<(&mut Transform, &mut TileTag), (With<TileTag>, Without<NodeTag>)>,
-    mut nodes: Query<(&mut Transform, &mut NodeTag), (With<NodeTag>, Without<TileTag>)>,
+    mut tiles: Query<
+        (&mut Transform, &mut TileTag),
+        (With<TileTag>, Without<NodeTag>, Without<SpawnDrop>),
+    >,
+    mut nodes: Query<
+        (&mut Transform, &mut NodeTag),
+        (With<NodeTag>, Without<TileTag>, Without<SpawnDrop>),
+    >,
 ) {
     let dt = time.delta_secs();
 
@@ -228,3 +295,71 @@ pub fn update_selection_lift_system(
         tf.translation.y = tag.base_y + tag.curr_y;
     }
 }
+
+/// upgraded nodes wear a ring of trim per tier around their base.
+pub fn draw_tier_trim_system(
+    mut gizmos: Gizmos,
+    nodes: Query<(&Transform, &NodeTag, &Footprint)>,
+) {
+    for (tf, tag, footprint) in &nodes {
+        let radius = footprint.width.max(footprint.depth) as f32 * 0.5;
+        for ring in 1..=tag.tier {
+            let center = Vec3::new(
+                tf.translation.x,
+                TIER_TRIM_Y + ring as f32 * TIER_TRIM_SPACING,
+                tf.translation.z,
+            );
+            gizmos.circle(
+                Isometry3d::new(center, Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+                radius,
+                TIER_TRIM_COLOR,
+            );
+        }
+    }
+}
+
+pub fn draw_node_links_system(
+    mut gizmos: Gizmos,
+    game: Res<Game>,
+    nodes: Query<(&Transform, &NodeLinks), With<NodeTag>>,
+    positions: Query<&Transform, With<NodeTag>>,
+) {
+    for (tf, links) in &nodes {
+        let start = Vec3::new(tf.translation.x, LINK_Y, tf.translation.z);
+        for target in &links.out {
+            let Ok(target_tf) = positions.get(*target) else {
+                continue;
+            };
+            let end = Vec3::new(target_tf.translation.x, LINK_Y, target_tf.translation.z);
+            gizmos.arrow(start, end, LINK_COLOR);
+        }
+    }
+
+    if let Some(source) = game.link_source
+        && let Ok(tf) = positions.get(source)
+    {
+        let center = Vec3::new(tf.translation.x, LINK_Y, tf.translation.z);
+        gizmos.circle(
+            Isometry3d::new(center, Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+            0.45,
+            LINK_PENDING_COLOR,
+        );
+    }
+}
+
+/// running out of money ends the game.
+pub fn check_bankruptcy_system(
+    economy: Res<Economy>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if economy.budget < 0 {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+/// R turns the node about to be placed by a quarter.
+pub fn rotate_placement_system(keys: Res<ButtonInput<KeyCode>>, mut game: ResMut<Game>) {
+    if keys.just_pressed(KeyCode::KeyR) && matches!(game.tool_selection, ToolType::Add(_)) {
+        game.placement_rotation = (game.placement_rotation + 1) % 4;
+    }
+}
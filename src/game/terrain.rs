@@ -0,0 +1,341 @@
+use super::components::{BoardPos, NodeTag};
+use super::constants::{
+    EARTH_RADIUS_KM, GAME_BOARD_SIZE_X, GAME_BOARD_SIZE_Z, KM_PER_LATENCY_TICK,
+    REGION_CROSSING_TICKS, ZONE_CROSSING_TICKS,
+};
+use super::types::NodeType;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// a rectangle of tiles, `width` along x and `depth` along z.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Area {
+    pub x: usize,
+    pub z: usize,
+    pub width: usize,
+    pub depth: usize,
+}
+
+impl Area {
+    pub fn contains(&self, pos: BoardPos) -> bool {
+        (self.x..self.x + self.width).contains(&pos.x)
+            && (self.z..self.z + self.depth).contains(&pos.z)
+    }
+}
+
+/// how the board is laid over the globe, which decides how far apart two
+/// tiles are. the board spans every longitude along x and runs north to
+/// south along z.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum Projection {
+    /// everything sits in one building, distance adds nothing.
+    Local,
+    /// straight-line tile distance at the equator's scale.
+    Flat,
+    /// rows are evenly spaced in latitude.
+    #[default]
+    Equirectangular,
+    /// rows are spaced like a web map, so the top and bottom rows cover
+    /// little ground.
+    Mercator,
+}
+
+impl Projection {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Local => "Local",
+            Self::Flat => "Flat",
+            Self::Equirectangular => "Equirectangular",
+            Self::Mercator => "Mercator",
+        }
+    }
+
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Local => Self::Flat,
+            Self::Flat => Self::Equirectangular,
+            Self::Equirectangular => Self::Mercator,
+            Self::Mercator => Self::Local,
+        }
+    }
+
+    /// latitude and longitude of a tile's center, in radians.
+    fn lat_lon(self, pos: BoardPos) -> (f64, f64) {
+        use std::f64::consts::PI;
+        let u = (pos.x as f64 + 0.5) / GAME_BOARD_SIZE_X as f64;
+        let v = (pos.z as f64 + 0.5) / GAME_BOARD_SIZE_Z as f64;
+        let lat = match self {
+            Self::Mercator => (PI * (1.0 - 2.0 * v)).sinh().atan(),
+            _ => PI * (0.5 - v),
+        };
+        (lat, PI * (2.0 * u - 1.0))
+    }
+
+    pub fn distance_km(self, a: BoardPos, b: BoardPos) -> f64 {
+        match self {
+            Self::Local => 0.0,
+            Self::Flat => {
+                let km_per_tile =
+                    2.0 * std::f64::consts::PI * EARTH_RADIUS_KM / GAME_BOARD_SIZE_X as f64;
+                let dx = a.x as f64 - b.x as f64;
+                let dz = a.z as f64 - b.z as f64;
+                dx.hypot(dz) * km_per_tile
+            }
+            Self::Equirectangular | Self::Mercator => {
+                // haversine
+                let (lat_a, lon_a) = self.lat_lon(a);
+                let (lat_b, lon_b) = self.lat_lon(b);
+                let h = ((lat_b - lat_a) / 2.0).sin().powi(2)
+                    + lat_a.cos() * lat_b.cos() * ((lon_b - lon_a) / 2.0).sin().powi(2);
+                2.0 * EARTH_RADIUS_KM * h.sqrt().min(1.0).asin()
+            }
+        }
+    }
+}
+
+fn one() -> f64 {
+    1.0
+}
+
+/// a datacenter zone. zones in the same region are close together, crossing
+/// regions is what costs latency.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Zone {
+    pub name: String,
+    pub region: String,
+    pub area: Area,
+    /// srgb tint for the zone's tiles.
+    pub tint: [f32; 3],
+    /// scales what nodes cost to place here.
+    #[serde(default = "one")]
+    pub cost_multiplier: f64,
+    /// total power the zone's racks can draw, in kW. unlimited if unset.
+    #[serde(default)]
+    pub power_limit: Option<u32>,
+}
+
+/// a node that comes with the board, placed for free when the run starts.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct LegacyNode {
+    pub at: BoardPos,
+    pub node_type: NodeType,
+    #[serde(default)]
+    pub rotation: u8,
+}
+
+/// what the board is made of: its zones, the tiles nothing can go on and
+/// where it sits on the globe. tiles outside every zone are plain, with no
+/// multiplier or power limit.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Terrain {
+    pub zones: Vec<Zone>,
+    pub blocked: Vec<Area>,
+    /// tiles left out of the board entirely.
+    pub missing: Vec<Area>,
+    pub projection: Projection,
+    pub legacy: Vec<LegacyNode>,
+    /// links between legacy nodes, by anchor tile.
+    pub legacy_links: Vec<(BoardPos, BoardPos)>,
+    /// the board code it was generated from, if it was.
+    pub seed: Option<u64>,
+}
+
+impl Default for Terrain {
+    /// two US zones and one in the EU, with a strip of ocean in between.
+    fn default() -> Self {
+        let column = |x, width| Area {
+            x,
+            z: 0,
+            width,
+            depth: GAME_BOARD_SIZE_Z,
+        };
+        Self {
+            zones: vec![
+                Zone {
+                    name: "us-west".to_string(),
+                    region: "us".to_string(),
+                    area: column(0, 10),
+                    tint: [0.05, 0.07, 0.11],
+                    cost_multiplier: 1.0,
+                    power_limit: Some(60),
+                },
+                Zone {
+                    name: "us-east".to_string(),
+                    region: "us".to_string(),
+                    area: column(10, 10),
+                    tint: [0.05, 0.10, 0.09],
+                    cost_multiplier: 1.2,
+                    power_limit: Some(60),
+                },
+                Zone {
+                    name: "eu-west".to_string(),
+                    region: "eu".to_string(),
+                    area: column(22, 10),
+                    tint: [0.10, 0.07, 0.11],
+                    cost_multiplier: 1.1,
+                    power_limit: Some(50),
+                },
+            ],
+            blocked: vec![column(20, 2)],
+            missing: Vec::new(),
+            projection: Projection::default(),
+            legacy: Vec::new(),
+            legacy_links: Vec::new(),
+            seed: None,
+        }
+    }
+}
+
+impl Terrain {
+    /// no zones, nothing blocked and no distance, the board before terrain
+    /// existed.
+    pub fn flat() -> Self {
+        Self {
+            zones: Vec::new(),
+            blocked: Vec::new(),
+            missing: Vec::new(),
+            projection: Projection::Local,
+            legacy: Vec::new(),
+            legacy_links: Vec::new(),
+            seed: None,
+        }
+    }
+
+    pub fn zone_at(&self, pos: BoardPos) -> Option<usize> {
+        self.zones.iter().position(|zone| zone.area.contains(pos))
+    }
+
+    pub fn zone(&self, zone: Option<usize>) -> Option<&Zone> {
+        zone.and_then(|i| self.zones.get(i))
+    }
+
+    pub fn is_blocked(&self, pos: BoardPos) -> bool {
+        self.blocked.iter().any(|area| area.contains(pos))
+    }
+
+    pub fn is_missing(&self, pos: BoardPos) -> bool {
+        self.missing.iter().any(|area| area.contains(pos))
+    }
+
+    fn cost_multiplier(&self, at: BoardPos) -> f64 {
+        self.zone(self.zone_at(at))
+            .map_or(1.0, |zone| zone.cost_multiplier)
+    }
+
+    /// what a node costs with its anchor tile at `at`.
+    pub fn cost(&self, node_type: NodeType, at: BoardPos) -> i64 {
+        (node_type.cost() as f64 * self.cost_multiplier(at)).round() as i64
+    }
+
+    /// what taking a node anchored at `at` up to `tier` costs.
+    pub fn upgrade_cost(&self, node_type: NodeType, tier: u8, at: BoardPos) -> i64 {
+        (node_type.upgrade_cost(tier) as f64 * self.cost_multiplier(at)).round() as i64
+    }
+
+    /// what moving a node at `tier` from `from` to `to` costs: the difference
+    /// in what it would cost built at either place. a negative cost is a
+    /// refund, never more than the `paid` the node cost so far.
+    pub fn move_cost(
+        &self,
+        node_type: NodeType,
+        tier: u8,
+        paid: i64,
+        from: BoardPos,
+        to: BoardPos,
+    ) -> i64 {
+        let built = |at| {
+            self.cost(node_type, at)
+                + (1..=tier)
+                    .map(|tier| self.upgrade_cost(node_type, tier, at))
+                    .sum::<i64>()
+        };
+        (built(to) - built(from)).max(-paid)
+    }
+
+    /// power drawn by the nodes anchored in `zone`.
+    pub fn power_used<'a>(
+        &self,
+        zone: Option<usize>,
+        nodes: impl Iterator<Item = (&'a NodeTag, &'a BoardPos)>,
+    ) -> u32 {
+        nodes
+            .filter(|(_, pos)| self.zone_at(**pos) == zone)
+            .map(|(tag, _)| tag.node_type.tier_power(tag.tier))
+            .sum()
+    }
+
+    /// extra ticks a request spends going between two tiles: the distance
+    /// under the projection, plus a penalty for leaving the zone or region.
+    pub fn link_delay(&self, from: BoardPos, to: BoardPos) -> u64 {
+        let distance = (self.projection.distance_km(from, to) / KM_PER_LATENCY_TICK) as u64;
+        let (from_zone, to_zone) = (self.zone_at(from), self.zone_at(to));
+        let crossing = match (self.zone(from_zone), self.zone(to_zone)) {
+            _ if from_zone == to_zone => 0,
+            (Some(a), Some(b)) if a.region == b.region => ZONE_CROSSING_TICKS,
+            (Some(_), Some(_)) => REGION_CROSSING_TICKS,
+            _ => 0,
+        };
+        distance + crossing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moving_pays_the_zone_difference() {
+        // us-west costs 1.0, us-east 1.2
+        let terrain = Terrain::default();
+        let (west, east) = (BoardPos { x: 2, z: 2 }, BoardPos { x: 12, z: 2 });
+        let price = terrain.cost(NodeType::Compute, west);
+        let upgraded = price + terrain.upgrade_cost(NodeType::Compute, 1, west);
+
+        assert_eq!(
+            terrain.move_cost(NodeType::Compute, 0, price, west, west),
+            0
+        );
+        assert_eq!(
+            terrain.move_cost(NodeType::Compute, 0, price, west, east),
+            terrain.cost(NodeType::Compute, east) - price
+        );
+        assert_eq!(
+            terrain.move_cost(NodeType::Compute, 1, upgraded, west, east),
+            terrain.cost(NodeType::Compute, east)
+                + terrain.upgrade_cost(NodeType::Compute, 1, east)
+                - upgraded
+        );
+        assert!(terrain.move_cost(NodeType::Compute, 0, price, east, west) < 0);
+        // nodes that came free give nothing back
+        assert_eq!(terrain.move_cost(NodeType::Compute, 0, 0, east, west), 0);
+    }
+
+    #[test]
+    fn link_delay_adds_crossings_and_distance() {
+        let terrain = Terrain {
+            projection: Projection::Local,
+            ..Terrain::default()
+        };
+        let at = |x| BoardPos { x, z: 0 };
+        assert_eq!(terrain.link_delay(at(1), at(8)), 0);
+        assert_eq!(terrain.link_delay(at(1), at(12)), ZONE_CROSSING_TICKS);
+        assert_eq!(terrain.link_delay(at(12), at(25)), REGION_CROSSING_TICKS);
+        // the ocean belongs to no zone
+        assert_eq!(terrain.link_delay(at(1), at(20)), 0);
+
+        // about 1250 km a tile around a flat world
+        let flat = Terrain {
+            projection: Projection::Flat,
+            ..Terrain::flat()
+        };
+        let down = |z| BoardPos { x: 0, z };
+        assert_eq!(flat.link_delay(down(0), down(3)), 0);
+        assert_eq!(flat.link_delay(down(0), down(10)), 3);
+        assert_eq!(
+            flat.link_delay(down(0), down(10)),
+            flat.link_delay(down(10), down(0))
+        );
+    }
+}
@@ -1,6 +1,6 @@
-use super::components::TileNodeLink;
+use super::actions::{OpenContextMenu, PlayerAction};
+use super::components::{BoardPos, TileNodeLink, TileProps};
 use super::constants::{SPAWN_FALL_Y, TILE_SPAWN_Y};
-use super::nodes::spawn_node;
 use super::resources::{Game, RenderAssets};
 
 use crate::camera::CamState;
@@ -9,11 +9,16 @@ use crate::game::types::ToolType;
 
 use bevy::prelude::*;
 
-pub fn spawn_tile(commands: &mut Commands, render_assets: &RenderAssets, pos: Vec3) -> Entity {
+pub fn spawn_tile(
+    commands: &mut Commands,
+    render_assets: &RenderAssets,
+    pos: Vec3,
+    props: TileProps,
+) -> Entity {
     let tile_e = commands
         .spawn((
             Mesh3d(render_assets.tile_mesh.clone()),
-            MeshMaterial3d(render_assets.tile_mat.clone()),
+            MeshMaterial3d(render_assets.tile_material(&props)),
             Transform::from_xyz(pos.x, pos.y + SPAWN_FALL_Y, pos.z),
             Pickable::default(),
         ))
@@ -32,6 +37,11 @@ pub fn spawn_tile(commands: &mut Commands, render_assets: &RenderAssets, pos: Ve
             base_y: TILE_SPAWN_Y,
             curr_y: TILE_SPAWN_Y + SPAWN_FALL_Y,
         },
+        BoardPos {
+            x: pos.x as usize,
+            z: pos.z as usize,
+        },
+        props,
     ));
 
     tile_e
@@ -39,14 +49,18 @@ pub fn spawn_tile(commands: &mut Commands, render_assets: &RenderAssets, pos: Ve
 
 fn tile_click_event(
     mut click: On<Pointer<Click>>,
-    mut commands: Commands,
-    game: ResMut<Game>,
-    render_assets: Res<RenderAssets>,
+    mut game: ResMut<Game>,
+    mut actions: MessageWriter<PlayerAction>,
     buttons: Res<ButtonInput<MouseButton>>,
     cam_state: Res<State<CamState>>,
-    mut tile_links: Query<&mut TileNodeLink, With<TileTag>>,
+    tile_links: Query<&TileNodeLink, With<TileTag>>,
+    mut node_tags: Query<&mut NodeTag>,
+    mut menus: MessageWriter<OpenContextMenu>,
 ) {
-    if click.event.button != PointerButton::Primary {
+    if !matches!(
+        click.event.button,
+        PointerButton::Primary | PointerButton::Secondary
+    ) {
         return;
     }
 
@@ -76,33 +90,39 @@ fn tile_click_event(
     }
 
     // Must be a tile (has TileTag + TileNodeLink).
-    let Ok(mut link) = tile_links.get_mut(click.entity) else {
+    let Ok(link) = tile_links.get(click.entity) else {
         return;
     };
+    let at = BoardPos {
+        x: tile_x,
+        z: tile_z,
+    };
 
     // Consume the click once we know it's a valid tile interaction.
     click.propagate(false);
 
+    if click.event.button == PointerButton::Secondary {
+        menus.write(OpenContextMenu {
+            at,
+            node: link.node,
+            cursor: click.pointer_location.position,
+        });
+        return;
+    }
+
     match game.tool_selection {
-        ToolType::Delete => {
-            if let Some(node_e) = link.node {
-                commands.entity(node_e).despawn();
-                link.node = None;
-            }
+        ToolType::Delete if link.node.is_some() => {
+            actions.write(PlayerAction::DeleteNode { at });
         }
 
-        ToolType::Add(node_type) => {
-            if link.node.is_none() {
-                spawn_node(
-                    &mut commands,
-                    &mut link,
-                    &render_assets,
-                    node_type,
-                    tile_x,
-                    tile_z,
-                );
+        ToolType::Select => {
+            for mut tag in &mut node_tags {
+                tag.selected = false;
             }
         }
+
+        ToolType::Link => game.link_source = None,
+
         _ => {}
     }
 }
@@ -110,13 +130,25 @@ fn tile_click_event(
 fn tile_hover_over_event(
     over: On<Pointer<Over>>,
     render_assets: Res<RenderAssets>,
-    mut tiles: Query<(&TileNodeLink, &mut MeshMaterial3d<StandardMaterial>), (With<TileTag>, Without<NodeTag>)>,
-    mut nodes: Query<(&NodeTag, &mut MeshMaterial3d<StandardMaterial>), (With<NodeTag>, Without<TileTag>)>,
+    mut game: ResMut<Game>,
+    mut tiles: Query<
+        (
+            &TileNodeLink,
+            &BoardPos,
+            &mut MeshMaterial3d<StandardMaterial>,
+        ),
+        (With<TileTag>, Without<NodeTag>),
+    >,
+    mut nodes: Query<
+        (&NodeTag, &mut MeshMaterial3d<StandardMaterial>),
+        (With<NodeTag>, Without<TileTag>),
+    >,
 ) {
-    let Ok((link, mut tile_m)) = tiles.get_mut(over.entity) else {
+    let Ok((link, pos, mut tile_m)) = tiles.get_mut(over.entity) else {
         return;
     };
     tile_m.0 = render_assets.tile_vfx.clone();
+    game.hovered_tile = Some(*pos);
 
     let Some(node_e) = link.node else {
         return;
@@ -132,13 +164,28 @@ fn tile_hover_over_event(
 fn tile_hover_out_event(
     out: On<Pointer<Out>>,
     render_assets: Res<RenderAssets>,
-    mut tiles: Query<(&TileNodeLink, &mut MeshMaterial3d<StandardMaterial>), (With<TileTag>, Without<NodeTag>)>,
-    mut nodes: Query<(&NodeTag, &mut MeshMaterial3d<StandardMaterial>), (With<NodeTag>, Without<TileTag>)>,
+    mut game: ResMut<Game>,
+    mut tiles: Query<
+        (
+            &TileNodeLink,
+            &BoardPos,
+            &TileProps,
+            &mut MeshMaterial3d<StandardMaterial>,
+        ),
+        (With<TileTag>, Without<NodeTag>),
+    >,
+    mut nodes: Query<
+        (&NodeTag, &mut MeshMaterial3d<StandardMaterial>),
+        (With<NodeTag>, Without<TileTag>),
+    >,
 ) {
-    let Ok((link, mut tile_m)) = tiles.get_mut(out.entity) else {
+    let Ok((link, pos, props, mut tile_m)) = tiles.get_mut(out.entity) else {
         return;
     };
-    tile_m.0 = render_assets.tile_mat.clone();
+    tile_m.0 = render_assets.tile_material(props);
+    if game.hovered_tile == Some(*pos) {
+        game.hovered_tile = None;
+    }
 
     let Some(node_e) = link.node else {
         return;
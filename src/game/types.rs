@@ -1,4 +1,8 @@
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+use super::components::Footprint;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
 pub enum NodeType {
     #[default]
     Internet,
@@ -12,6 +16,14 @@ pub enum NodeType {
     CDN,
 }
 
+pub const fn tier_name(tier: u8) -> &'static str {
+    match tier {
+        0 => "Small",
+        1 => "Medium",
+        _ => "Large",
+    }
+}
+
 impl NodeType {
     const ALL: [NodeType; 9] = [
         Self::Internet,
@@ -39,6 +51,76 @@ impl NodeType {
         }
     }
 
+    /// tiles covered when placed with the given quarter turns.
+    pub fn footprint(self, rotation: u8) -> Footprint {
+        let (width, depth) = match self {
+            Self::Database => (2, 2),
+            Self::Queue => (1, 3),
+            _ => (1, 1),
+        };
+        Footprint::new(width, depth, rotation)
+    }
+
+    /// what placing one takes out of the budget.
+    pub const fn cost(self) -> i64 {
+        match self {
+            Self::Internet => 0,
+            Self::LoadBalancer => 300,
+            Self::Firewall => 250,
+            Self::Database => 800,
+            Self::Compute => 400,
+            Self::Storage => 500,
+            Self::Queue => 300,
+            Self::Cache => 350,
+            Self::CDN => 600,
+        }
+    }
+
+    /// rack power drawn, in kW.
+    pub const fn power(self) -> u32 {
+        match self {
+            Self::Internet => 0,
+            Self::LoadBalancer => 2,
+            Self::Firewall => 2,
+            Self::Database => 6,
+            Self::Compute => 4,
+            Self::Storage => 5,
+            Self::Queue => 2,
+            Self::Cache => 3,
+            Self::CDN => 3,
+        }
+    }
+
+    /// the traffic source has nothing to scale.
+    pub const fn upgradable(self) -> bool {
+        !matches!(self, Self::Internet)
+    }
+
+    /// what moving up to `tier` costs, before the zone multiplier. each
+    /// tier doubles throughput, so a node at the top tier costs as much as
+    /// four small ones.
+    pub const fn upgrade_cost(self, tier: u8) -> i64 {
+        self.cost() * tier as i64
+    }
+
+    /// power drawn at `tier`, in kW. bigger racks are more efficient than
+    /// the same capacity spread over small ones.
+    pub const fn tier_power(self, tier: u8) -> u32 {
+        let halves = match tier {
+            0 => 2,
+            1 => 3,
+            _ => 5,
+        };
+        self.power() * halves / 2
+    }
+
+    /// the type called `name`, by variant or short name, ignoring case.
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| {
+            format!("{t:?}").eq_ignore_ascii_case(name) || t.name().eq_ignore_ascii_case(name)
+        })
+    }
+
     pub fn all() -> impl Iterator<Item = (NodeType, &'static str)> {
         Self::ALL.into_iter().map(|t| (t, t.name()))
     }
@@ -52,4 +134,6 @@ pub enum ToolType {
     Delete,
     Link,
     Move,
-}
\ No newline at end of file
+    /// build the copied blueprint wherever the player clicks.
+    Stamp,
+}
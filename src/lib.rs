@@ -0,0 +1,4 @@
+pub mod camera;
+pub mod game;
+pub mod sim;
+pub mod ui;
@@ -3,13 +3,27 @@ use bevy::{
     window::{PresentMode, WindowMode, WindowPlugin, WindowPosition, WindowResolution},
 };
 
-mod camera;
-mod game;
-mod ui;
+use server_sim::game::GameLogicPlugin;
+use server_sim::game::spec::SpecImport;
+use std::path::PathBuf;
+use std::process::ExitCode;
 
-use crate::game::GameLogicPlugin;
+/// usage: server_sim [architecture.yaml|architecture.toml]
+///
+/// a spec given on the command line is picked in the setup menu already.
+fn main() -> ExitCode {
+    let mut spec_import = SpecImport::default();
+    if let Some(path) = std::env::args().nth(1) {
+        spec_import.select(Some(PathBuf::from(&path)));
+        if !spec_import.errors.is_empty() {
+            eprintln!("could not import {path}:");
+            for err in &spec_import.errors {
+                eprintln!("  {err}");
+            }
+            return ExitCode::from(2);
+        }
+    }
 
-fn main() {
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -27,5 +41,7 @@ fn main() {
             ..default()
         }))
         .add_plugins(GameLogicPlugin)
+        .insert_resource(spec_import)
         .run();
+    ExitCode::SUCCESS
 }
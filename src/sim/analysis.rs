@@ -0,0 +1,380 @@
+use super::components::{SimNode, StorageNode, TrafficSource};
+use super::constants::{MAX_OBJECT_MB, SIM_TICK_HZ};
+use super::graph::BoardGraph;
+use super::resources::FastForward;
+use super::systems::sim_running;
+
+use crate::game::GameState;
+use crate::game::components::{BoardPos, NodeLinks, NodeTag};
+use crate::game::terrain::Terrain;
+
+use bevy::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, VecDeque};
+
+const UNLIMITED: u64 = u64::MAX / 4;
+
+/// what the board can carry, worked out from capacities and links alone.
+/// cache and CDN hits are left out, so the estimate errs on the low side.
+#[derive(Resource, Default)]
+pub struct Analysis {
+    /// requests per tick that can get from the sources to a serving node.
+    pub max_flow: u64,
+    /// requests per tick the sources currently send.
+    pub offered: u64,
+    /// nodes whose capacity makes up the min cut.
+    pub bottlenecks: Vec<Entity>,
+    /// quickest way from a source to a serving node.
+    pub fastest_path: Vec<Entity>,
+    /// ticks along `fastest_path`: one per hop plus each link's delay.
+    pub path_latency: u64,
+}
+
+impl Analysis {
+    pub fn max_rps(&self) -> f64 {
+        self.max_flow as f64 * SIM_TICK_HZ
+    }
+
+    pub fn offered_rps(&self) -> f64 {
+        self.offered as f64 * SIM_TICK_HZ
+    }
+
+    pub fn min_latency_ticks(&self) -> Option<u64> {
+        (!self.fastest_path.is_empty()).then_some(self.path_latency)
+    }
+}
+
+struct FlowEdge {
+    to: usize,
+    residual: u64,
+}
+
+/// a directed flow network. every edge is stored next to its reverse, so
+/// edge `i` pairs with `i ^ 1`.
+struct FlowNetwork {
+    edges: Vec<FlowEdge>,
+    adj: Vec<Vec<usize>>,
+}
+
+impl FlowNetwork {
+    fn new(vertices: usize) -> Self {
+        Self {
+            edges: Vec::new(),
+            adj: vec![Vec::new(); vertices],
+        }
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, capacity: u64) {
+        self.adj[from].push(self.edges.len());
+        self.edges.push(FlowEdge {
+            to,
+            residual: capacity,
+        });
+        self.adj[to].push(self.edges.len());
+        self.edges.push(FlowEdge {
+            to: from,
+            residual: 0,
+        });
+    }
+
+    /// edmonds-karp: keep pushing flow along the shortest augmenting path.
+    fn max_flow(&mut self, source: usize, sink: usize) -> u64 {
+        let mut total = 0;
+        loop {
+            let mut via: Vec<Option<usize>> = vec![None; self.adj.len()];
+            let mut open = VecDeque::from([source]);
+            while let Some(v) = open.pop_front() {
+                if v == sink {
+                    break;
+                }
+                for &i in &self.adj[v] {
+                    let edge = &self.edges[i];
+                    if edge.residual > 0 && edge.to != source && via[edge.to].is_none() {
+                        via[edge.to] = Some(i);
+                        open.push_back(edge.to);
+                    }
+                }
+            }
+            if via[sink].is_none() {
+                return total;
+            }
+
+            let mut push = UNLIMITED;
+            let mut v = sink;
+            while let Some(i) = via[v] {
+                push = push.min(self.edges[i].residual);
+                v = self.edges[i ^ 1].to;
+            }
+            let mut v = sink;
+            while let Some(i) = via[v] {
+                self.edges[i].residual -= push;
+                self.edges[i ^ 1].residual += push;
+                v = self.edges[i ^ 1].to;
+            }
+            total += push;
+            if total >= UNLIMITED {
+                return total;
+            }
+        }
+    }
+
+    /// vertices still reachable from `source` in the residual network.
+    fn residual_reach(&self, source: usize) -> Vec<bool> {
+        let mut seen = vec![false; self.adj.len()];
+        seen[source] = true;
+        let mut open = VecDeque::from([source]);
+        while let Some(v) = open.pop_front() {
+            for &i in &self.adj[v] {
+                let edge = &self.edges[i];
+                if edge.residual > 0 && !seen[edge.to] {
+                    seen[edge.to] = true;
+                    open.push_back(edge.to);
+                }
+            }
+        }
+        seen
+    }
+}
+
+/// max flow from all sources to all serving nodes. each node is split into
+/// an in and an out vertex joined by its capacity; links themselves are
+/// unlimited, so the min cut always runs through nodes.
+pub fn analyze_board(
+    graph: &BoardGraph,
+    capacity: impl Fn(Entity) -> u64,
+    delay: impl Fn(Entity, Entity) -> u64,
+    offered: u64,
+) -> Analysis {
+    let index: BTreeMap<Entity, usize> = graph
+        .types
+        .keys()
+        .enumerate()
+        .map(|(i, e)| (*e, i))
+        .collect();
+    let source = 2 * index.len();
+    let sink = source + 1;
+    let mut network = FlowNetwork::new(sink + 1);
+
+    for (node_e, &i) in &index {
+        let is_source = graph.sources().any(|s| s == *node_e);
+        // sources generate rather than serve, only a failure stops them
+        let limit = match capacity(*node_e) {
+            0 => 0,
+            _ if is_source => UNLIMITED,
+            limit => limit,
+        };
+        network.add_edge(2 * i, 2 * i + 1, limit);
+        if is_source {
+            network.add_edge(source, 2 * i, UNLIMITED);
+        }
+        if graph.serves(*node_e) {
+            network.add_edge(2 * i + 1, sink, UNLIMITED);
+        }
+        for target in graph.request_targets(*node_e) {
+            network.add_edge(2 * i + 1, 2 * index[&target], UNLIMITED);
+        }
+    }
+
+    let max_flow = network.max_flow(source, sink);
+    let reach = network.residual_reach(source);
+    let bottlenecks = if max_flow == 0 {
+        Vec::new()
+    } else {
+        index
+            .iter()
+            .filter(|(_, i)| reach[2 * **i] && !reach[2 * **i + 1])
+            .map(|(e, _)| *e)
+            .collect()
+    };
+
+    let (fastest_path, path_latency) = fastest_path(graph, |e| capacity(e) > 0, delay);
+    Analysis {
+        max_flow,
+        offered,
+        bottlenecks,
+        fastest_path,
+        path_latency,
+    }
+}
+
+/// dijkstra from every source at once to the nearest serving node, where a
+/// hop takes a tick plus the link's delay.
+fn fastest_path(
+    graph: &BoardGraph,
+    usable: impl Fn(Entity) -> bool,
+    delay: impl Fn(Entity, Entity) -> u64,
+) -> (Vec<Entity>, u64) {
+    let mut best: BTreeMap<Entity, (u64, Option<Entity>)> = BTreeMap::new();
+    let mut open = BinaryHeap::new();
+    for source in graph.sources().filter(|e| usable(*e)) {
+        best.insert(source, (0, None));
+        open.push(Reverse((0, source)));
+    }
+
+    while let Some(Reverse((ticks, node))) = open.pop() {
+        if best.get(&node).is_some_and(|(known, _)| *known < ticks) {
+            continue;
+        }
+        if graph.serves(node) {
+            let mut path = vec![node];
+            let mut at = node;
+            while let Some((_, Some(prev))) = best.get(&at) {
+                path.push(*prev);
+                at = *prev;
+            }
+            path.reverse();
+            return (path, ticks);
+        }
+        for target in graph.request_targets(node) {
+            let arrive = ticks + 1 + delay(node, target);
+            if usable(target) && best.get(&target).is_none_or(|(known, _)| arrive < *known) {
+                best.insert(target, (arrive, Some(node)));
+                open.push(Reverse((arrive, target)));
+            }
+        }
+    }
+    (Vec::new(), 0)
+}
+
+/// requests per tick a node can handle. storage is also held back by its
+/// bandwidth, taken at the average object size.
+fn node_capacity(node: &SimNode, storage: Option<&StorageNode>) -> u64 {
+    if node.failed {
+        return 0;
+    }
+    let mut capacity = node.capacity as u64;
+    if let Some(storage) = storage {
+        let mean_object_mb = (1 + MAX_OBJECT_MB) as f64 / 2.0;
+        capacity = capacity.min((storage.bandwidth_mb as f64 / mean_object_mb).max(1.0) as u64);
+    }
+    capacity
+}
+
+/// re-run whenever the board's shape or terrain changes, and on config
+/// changes while the simulation is stopped (running ticks touch node state
+/// every frame).
+pub fn analyze_board_system(
+    mut analysis: ResMut<Analysis>,
+    terrain: Res<Terrain>,
+    mut removed: RemovedComponents<NodeTag>,
+    state: Res<State<GameState>>,
+    fast_forward: Res<FastForward>,
+    graph_changed: Query<(), Or<(Changed<NodeLinks>, Added<NodeTag>)>>,
+    config_changed: Query<
+        (),
+        Or<(
+            Changed<SimNode>,
+            Changed<StorageNode>,
+            Changed<TrafficSource>,
+        )>,
+    >,
+    nodes: Query<(Entity, &NodeTag, &NodeLinks)>,
+    positions: Query<&BoardPos>,
+    sims: Query<(&SimNode, Option<&StorageNode>, Option<&TrafficSource>)>,
+) {
+    let running = sim_running(state, fast_forward);
+    if removed.read().count() == 0
+        && graph_changed.is_empty()
+        && !terrain.is_changed()
+        && (running || config_changed.is_empty())
+    {
+        return;
+    }
+
+    let graph = BoardGraph::new(nodes.iter());
+    let offered = sims
+        .iter()
+        .filter(|(node, ..)| !node.failed)
+        .filter_map(|(_, _, source)| source.map(|s| s.rate as u64))
+        .sum();
+    *analysis = analyze_board(
+        &graph,
+        |e| {
+            sims.get(e)
+                .map_or(0, |(node, storage, _)| node_capacity(node, storage))
+        },
+        |from, to| match (positions.get(from), positions.get(to)) {
+            (Ok(from), Ok(to)) => terrain.link_delay(*from, *to),
+            _ => 0,
+        },
+        offered,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::NodeType;
+
+    #[test]
+    fn max_flow_matches_the_textbook() {
+        // the network from CLRS figure 26.1, which carries 23
+        let mut network = FlowNetwork::new(6);
+        for (from, to, capacity) in [
+            (0, 1, 16),
+            (0, 2, 13),
+            (2, 1, 4),
+            (1, 3, 12),
+            (3, 2, 9),
+            (2, 4, 14),
+            (4, 3, 7),
+            (3, 5, 20),
+            (4, 5, 4),
+        ] {
+            network.add_edge(from, to, capacity);
+        }
+        assert_eq!(network.max_flow(0, 5), 23);
+        let reach = network.residual_reach(0);
+        assert_eq!(reach, vec![true, true, true, false, true, false]);
+    }
+
+    /// internet -> lb -> two computes -> database.
+    fn board() -> (BoardGraph, [Entity; 5]) {
+        let mut world = World::new();
+        let nodes = [(); 5].map(|_| world.spawn_empty().id());
+        let [internet, lb, fast, slow, db] = nodes;
+        let mut graph = BoardGraph::default();
+        for (node_e, node_type, out) in [
+            (internet, NodeType::Internet, vec![lb]),
+            (lb, NodeType::LoadBalancer, vec![fast, slow]),
+            (fast, NodeType::Compute, vec![db]),
+            (slow, NodeType::Compute, vec![db]),
+            (db, NodeType::Database, vec![]),
+        ] {
+            graph.types.insert(node_e, node_type);
+            graph.links.insert(node_e, out);
+        }
+        (graph, nodes)
+    }
+
+    #[test]
+    fn analysis_finds_the_bottleneck_and_fastest_path() {
+        let (graph, [internet, lb, fast, slow, db]) = board();
+        let capacity = |e: Entity| match e {
+            e if e == fast => 4,
+            e if e == slow => 3,
+            _ => 100,
+        };
+        let delay = |from: Entity, to: Entity| if from == lb && to == slow { 5 } else { 0 };
+
+        let analysis = analyze_board(&graph, capacity, delay, 20);
+        assert_eq!(analysis.max_flow, 7);
+        assert_eq!(analysis.offered, 20);
+        let mut bottlenecks = analysis.bottlenecks.clone();
+        bottlenecks.sort();
+        let mut expected = vec![fast, slow];
+        expected.sort();
+        assert_eq!(bottlenecks, expected);
+        assert_eq!(analysis.fastest_path, vec![internet, lb, fast, db]);
+        assert_eq!(analysis.min_latency_ticks(), Some(3));
+    }
+
+    #[test]
+    fn failed_nodes_carry_nothing() {
+        let (graph, [_, lb, ..]) = board();
+        let analysis = analyze_board(&graph, |e| if e == lb { 0 } else { 100 }, |_, _| 0, 20);
+        assert_eq!(analysis.max_flow, 0);
+        assert!(analysis.bottlenecks.is_empty());
+        assert_eq!(analysis.min_latency_ticks(), None);
+    }
+}
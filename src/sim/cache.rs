@@ -0,0 +1,234 @@
+use super::types::EvictionPolicy;
+
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug)]
+struct CacheEntry {
+    inserted_at: u64,
+    last_used: u64,
+    uses: u64,
+}
+
+/// fixed capacity keyspace with a pluggable eviction policy.
+/// BTreeMap keeps eviction tie-breaks deterministic.
+#[derive(Clone, Debug)]
+pub struct CacheStore {
+    capacity: usize,
+    policy: EvictionPolicy,
+    ttl: u64,
+    entries: BTreeMap<u64, CacheEntry>,
+}
+
+impl CacheStore {
+    pub fn new(capacity: usize, policy: EvictionPolicy, ttl: u64) -> Self {
+        Self {
+            capacity,
+            policy,
+            ttl,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn policy(&self) -> EvictionPolicy {
+        self.policy
+    }
+
+    pub fn ttl(&self) -> u64 {
+        self.ttl
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn set_policy(&mut self, policy: EvictionPolicy) {
+        self.policy = policy;
+    }
+
+    /// shrinking evicts entries until the store fits again.
+    pub fn set_capacity(&mut self, capacity: usize, now: u64) {
+        self.capacity = capacity;
+        while self.entries.len() > self.capacity {
+            self.evict_one(now);
+        }
+    }
+
+    /// look up a key, returns true on hit. expired TTL entries count as a miss.
+    pub fn get(&mut self, key: u64, now: u64) -> bool {
+        if self.policy == EvictionPolicy::Ttl
+            && let Some(entry) = self.entries.get(&key)
+            && now.saturating_sub(entry.inserted_at) >= self.ttl
+        {
+            self.entries.remove(&key);
+            return false;
+        }
+
+        let Some(entry) = self.entries.get_mut(&key) else {
+            return false;
+        };
+        entry.last_used = now;
+        entry.uses += 1;
+        true
+    }
+
+    pub fn insert(&mut self, key: u64, now: u64) {
+        if self.capacity == 0 {
+            return;
+        }
+        if !self.entries.contains_key(&key) {
+            while self.entries.len() >= self.capacity {
+                self.evict_one(now);
+            }
+        }
+        self.entries.insert(
+            key,
+            CacheEntry {
+                inserted_at: now,
+                last_used: now,
+                uses: 1,
+            },
+        );
+    }
+
+    pub fn invalidate(&mut self, key: u64) {
+        self.entries.remove(&key);
+    }
+
+    fn evict_one(&mut self, now: u64) {
+        let victim = match self.policy {
+            EvictionPolicy::Lru => self.min_by_key(|e| e.last_used),
+            EvictionPolicy::Lfu => self.min_by_key(|e| (e.uses, e.last_used)),
+            EvictionPolicy::Fifo => self.min_by_key(|e| e.inserted_at),
+            // prefer anything already expired, otherwise whatever expires first.
+            EvictionPolicy::Ttl => self
+                .entries
+                .iter()
+                .find(|(_, e)| now.saturating_sub(e.inserted_at) >= self.ttl)
+                .map(|(k, _)| *k)
+                .or_else(|| self.min_by_key(|e| e.inserted_at)),
+        };
+
+        if let Some(key) = victim {
+            self.entries.remove(&key);
+        }
+    }
+
+    fn min_by_key<K: Ord>(&self, f: impl Fn(&CacheEntry) -> K) -> Option<u64> {
+        self.entries
+            .iter()
+            .min_by_key(|(_, e)| f(e))
+            .map(|(k, _)| *k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::components::HitHistory;
+
+    /// a full store of keys 1, 2 and 3, inserted at ticks 0, 1 and 2.
+    fn full(policy: EvictionPolicy) -> CacheStore {
+        let mut store = CacheStore::new(3, policy, 10);
+        for key in 1..=3 {
+            store.insert(key, key - 1);
+        }
+        store
+    }
+
+    fn cached(store: &CacheStore) -> Vec<u64> {
+        store.entries.keys().copied().collect()
+    }
+
+    #[test]
+    fn lru_evicts_the_least_recently_used() {
+        let mut store = full(EvictionPolicy::Lru);
+        assert!(store.get(1, 3));
+        store.insert(4, 4);
+        assert_eq!(cached(&store), [1, 3, 4]);
+    }
+
+    #[test]
+    fn lfu_evicts_the_least_used() {
+        let mut store = full(EvictionPolicy::Lfu);
+        assert!(store.get(1, 3));
+        assert!(store.get(1, 4));
+        assert!(store.get(2, 5));
+        store.insert(4, 6);
+        assert_eq!(cached(&store), [1, 2, 4]);
+    }
+
+    #[test]
+    fn fifo_evicts_the_oldest_however_busy() {
+        let mut store = full(EvictionPolicy::Fifo);
+        for now in 3..8 {
+            assert!(store.get(1, now));
+        }
+        store.insert(4, 8);
+        assert_eq!(cached(&store), [2, 3, 4]);
+    }
+
+    #[test]
+    fn ttl_evicts_the_expired_first() {
+        let mut store = CacheStore::new(3, EvictionPolicy::Ttl, 10);
+        store.insert(1, 6);
+        store.insert(2, 0);
+        store.insert(3, 5);
+        // nothing has expired yet, so whatever expires first goes
+        store.insert(4, 8);
+        assert_eq!(cached(&store), [1, 3, 4]);
+        // by 15 key 3 has expired and keys 1 and 4 haven't
+        store.insert(5, 15);
+        assert_eq!(cached(&store), [1, 4, 5]);
+    }
+
+    #[test]
+    fn ttl_entries_expire_on_lookup() {
+        let mut store = CacheStore::new(3, EvictionPolicy::Ttl, 10);
+        store.insert(1, 0);
+        assert!(store.get(1, 9));
+        assert!(!store.get(1, 10));
+        assert!(store.is_empty());
+
+        // other policies keep entries however old
+        let mut store = CacheStore::new(3, EvictionPolicy::Lru, 10);
+        store.insert(1, 0);
+        assert!(store.get(1, 100));
+    }
+
+    #[test]
+    fn shrinking_evicts_down_to_the_new_capacity() {
+        let mut store = full(EvictionPolicy::Fifo);
+        store.set_capacity(1, 3);
+        assert_eq!(cached(&store), [3]);
+        store.set_capacity(0, 3);
+        store.insert(4, 4);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn counts_hits_and_misses() {
+        let mut store = CacheStore::new(2, EvictionPolicy::Lru, 10);
+        let mut history = HitHistory::default();
+        for (now, key) in [1, 2, 1, 3, 2, 1].into_iter().enumerate() {
+            let hit = store.get(key, now as u64);
+            history.record(hit);
+            if !hit {
+                store.insert(key, now as u64);
+            }
+        }
+        // 1 and 2 miss, 1 hits, 3 misses and pushes out 2, which misses and
+        // pushes out 1, which misses
+        assert_eq!((history.hits, history.misses), (1, 5));
+        assert_eq!(history.total(), 6);
+        history.sample_window();
+        assert_eq!(history.samples.back(), Some(&(1.0 / 6.0)));
+    }
+}
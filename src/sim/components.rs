@@ -0,0 +1,429 @@
+use super::cache::CacheStore;
+use super::constants::*;
+use super::traffic::KeySampler;
+use super::types::{
+    DbRole, EvictionPolicy, FirewallRule, Geo, KeyDistribution, Request, RequestClass,
+};
+
+use bevy::prelude::*;
+use std::collections::{BTreeMap, VecDeque};
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct NodeStats {
+    pub received: u64,
+    pub served: u64,
+    pub forwarded: u64,
+    pub dropped: u64,
+}
+
+/// per-node simulation state, attached to every node when it is placed.
+#[derive(Component, Debug)]
+pub struct SimNode {
+    pub capacity: u32,
+    pub queue_limit: usize,
+    pub inbox: VecDeque<Request>,
+    pub rr_cursor: usize,
+    pub failed: bool,
+    pub stats: NodeStats,
+}
+
+impl Default for SimNode {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_NODE_CAPACITY,
+            queue_limit: DEFAULT_QUEUE_LIMIT,
+            inbox: VecDeque::new(),
+            rr_cursor: 0,
+            failed: false,
+            stats: NodeStats::default(),
+        }
+    }
+}
+
+/// request generator, attached to Internet nodes.
+#[derive(Component, Debug)]
+pub struct TrafficSource {
+    pub rate: u32,
+    pub write_ratio: f32,
+    pub static_ratio: f32,
+    pub geo: Geo,
+    pub attack_class: RequestClass,
+    pub attack_ratio: f32,
+    pub sampler: KeySampler,
+}
+
+impl Default for TrafficSource {
+    fn default() -> Self {
+        Self {
+            rate: DEFAULT_REQUEST_RATE,
+            write_ratio: DEFAULT_WRITE_RATIO,
+            static_ratio: DEFAULT_STATIC_RATIO,
+            geo: Geo::default(),
+            attack_class: RequestClass::SqlInjection,
+            attack_ratio: 0.0,
+            sampler: KeySampler::new(KeyDistribution::default(), DEFAULT_KEYSPACE),
+        }
+    }
+}
+
+/// running hit/miss counters plus a rolling history of per-window hit ratios.
+#[derive(Clone, Debug)]
+pub struct HitHistory {
+    pub hits: u64,
+    pub misses: u64,
+    window_hits: u64,
+    window_misses: u64,
+    pub samples: VecDeque<f32>,
+}
+
+impl Default for HitHistory {
+    fn default() -> Self {
+        Self {
+            hits: 0,
+            misses: 0,
+            window_hits: 0,
+            window_misses: 0,
+            samples: VecDeque::with_capacity(HISTORY_LEN),
+        }
+    }
+}
+
+impl HitHistory {
+    pub fn record(&mut self, hit: bool) {
+        if hit {
+            self.hits += 1;
+            self.window_hits += 1;
+        } else {
+            self.misses += 1;
+            self.window_misses += 1;
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.hits + self.misses
+    }
+
+    pub fn ratio(&self) -> f32 {
+        if self.total() == 0 {
+            return 0.0;
+        }
+        self.hits as f32 / self.total() as f32
+    }
+
+    /// close the current sample window and push its hit ratio into the history.
+    pub fn sample_window(&mut self) {
+        let total = self.window_hits + self.window_misses;
+        let ratio = if total == 0 {
+            0.0
+        } else {
+            self.window_hits as f32 / total as f32
+        };
+        if self.samples.len() == HISTORY_LEN {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(ratio);
+        self.window_hits = 0;
+        self.window_misses = 0;
+    }
+}
+
+/// keyspace cache, attached to Cache nodes.
+#[derive(Component, Debug)]
+pub struct CacheNode {
+    pub store: CacheStore,
+    pub history: HitHistory,
+}
+
+impl Default for CacheNode {
+    fn default() -> Self {
+        Self {
+            store: CacheStore::new(
+                DEFAULT_CACHE_CAPACITY,
+                EvictionPolicy::default(),
+                DEFAULT_CACHE_TTL,
+            ),
+            history: HitHistory::default(),
+        }
+    }
+}
+
+/// edge cache for static content, attached to CDN nodes.
+/// `warmth` scales the hit chance and recovers gradually after a purge.
+#[derive(Component, Debug)]
+pub struct CdnNode {
+    pub hit_fraction: f32,
+    pub warmth: f32,
+    pub history: HitHistory,
+    pub origin_requests: u64,
+    pub purges: u64,
+}
+
+impl Default for CdnNode {
+    fn default() -> Self {
+        Self {
+            hit_fraction: DEFAULT_CDN_HIT_FRACTION,
+            warmth: 1.0,
+            history: HitHistory::default(),
+            origin_requests: 0,
+            purges: 0,
+        }
+    }
+}
+
+impl CdnNode {
+    pub fn purge(&mut self) {
+        self.warmth = 0.0;
+        self.purges += 1;
+    }
+
+    /// share of everything the edge received that never reached the origin.
+    pub fn offload(&self) -> f32 {
+        let total = self.history.hits + self.origin_requests;
+        if total == 0 {
+            return 0.0;
+        }
+        self.history.hits as f32 / total as f32
+    }
+}
+
+/// primary/replica database, attached to Database nodes.
+/// a primary replicates to the Database nodes it links to.
+#[derive(Component, Debug)]
+pub struct DatabaseNode {
+    pub role: DbRole,
+    pub replication_lag: u64,
+    pub reads: u64,
+    pub writes: u64,
+    pub stale_reads: u64,
+    pub breaches: u64,
+    /// key -> tick at which the replicated write lands on this replica.
+    pub pending: BTreeMap<u64, u64>,
+}
+
+impl Default for DatabaseNode {
+    fn default() -> Self {
+        Self {
+            role: DbRole::default(),
+            replication_lag: DEFAULT_REPLICATION_LAG,
+            reads: 0,
+            writes: 0,
+            stale_reads: 0,
+            breaches: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+}
+
+impl DatabaseNode {
+    /// true if a write to `key` has not reached this replica yet.
+    pub fn is_stale(&self, key: u64, now: u64) -> bool {
+        self.pending
+            .get(&key)
+            .is_some_and(|apply_at| *apply_at > now)
+    }
+}
+
+/// consumer settings for Compute nodes reading from a queue.
+#[derive(Component, Debug)]
+pub struct ComputeNode {
+    pub concurrency: u32,
+}
+
+impl Default for ComputeNode {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_CONSUMER_CONCURRENCY,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct QueuedMessage {
+    pub request: Request,
+    pub attempts: u32,
+}
+
+/// a message handed to a consumer and not yet acknowledged.
+#[derive(Clone, Debug)]
+pub struct Lease {
+    pub id: u64,
+    pub consumer: Entity,
+    pub deadline: u64,
+    pub message: QueuedMessage,
+}
+
+/// bounded at-least-once message queue, attached to Queue nodes.
+#[derive(Component, Debug)]
+pub struct QueueNode {
+    pub max_depth: usize,
+    pub buffer: VecDeque<QueuedMessage>,
+    pub leases: Vec<Lease>,
+    pub dead_letters: VecDeque<Request>,
+    pub next_lease_id: u64,
+    pub enqueued: u64,
+    pub acked: u64,
+    pub redelivered: u64,
+    pub dead_lettered: u64,
+    pub full: bool,
+}
+
+impl Default for QueueNode {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_QUEUE_DEPTH,
+            buffer: VecDeque::new(),
+            leases: Vec::new(),
+            dead_letters: VecDeque::new(),
+            next_lease_id: 0,
+            enqueued: 0,
+            acked: 0,
+            redelivered: 0,
+            dead_lettered: 0,
+            full: false,
+        }
+    }
+}
+
+impl QueueNode {
+    pub fn depth(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn leases_for(&self, consumer: Entity) -> u32 {
+        self.leases
+            .iter()
+            .filter(|l| l.consumer == consumer)
+            .count() as u32
+    }
+
+    pub fn dead_letter(&mut self, request: Request) {
+        if self.dead_letters.len() == DEAD_LETTER_LIMIT {
+            self.dead_letters.pop_front();
+        }
+        self.dead_letters.push_back(request);
+        self.dead_lettered += 1;
+    }
+
+    /// move dead letters back into the buffer for another round of attempts.
+    pub fn redrive(&mut self) {
+        while self.buffer.len() < self.max_depth {
+            let Some(request) = self.dead_letters.pop_front() else {
+                break;
+            };
+            self.buffer.push_back(QueuedMessage {
+                request,
+                attempts: 0,
+            });
+        }
+    }
+}
+
+/// ordered rule list, attached to Firewall nodes.
+#[derive(Component, Debug)]
+pub struct FirewallNode {
+    pub rules: Vec<FirewallRule>,
+    pub allowed: u64,
+    pub blocked: u64,
+    pub false_positives: u64,
+    /// requests seen per source in the current tick, for rate limits.
+    pub source_counts: Vec<(Entity, u32)>,
+}
+
+impl Default for FirewallNode {
+    fn default() -> Self {
+        Self {
+            rules: vec![FirewallRule::Deny(RequestClass::SqlInjection)],
+            allowed: 0,
+            blocked: 0,
+            false_positives: 0,
+            source_counts: Vec::new(),
+        }
+    }
+}
+
+impl FirewallNode {
+    /// `seen_class` is what the firewall detected, not necessarily the true class.
+    pub fn allows(&mut self, request: &Request, seen_class: RequestClass) -> bool {
+        let source_count = match request.source {
+            Some(source) => match self.source_counts.iter_mut().find(|(e, _)| *e == source) {
+                Some((_, count)) => {
+                    *count += 1;
+                    *count
+                }
+                None => {
+                    self.source_counts.push((source, 1));
+                    1
+                }
+            },
+            None => 0,
+        };
+
+        for rule in &self.rules {
+            match *rule {
+                FirewallRule::Allow(class) if class == seen_class => return true,
+                FirewallRule::Deny(class) if class == seen_class => return false,
+                FirewallRule::RateLimit(limit) if source_count > limit => return false,
+                FirewallRule::GeoBlock(geo) if geo == request.geo => return false,
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+/// object store, attached to Storage nodes. objects are keyed by request key,
+/// with a size derived from the key so the same object always weighs the same.
+#[derive(Component, Debug)]
+pub struct StorageNode {
+    pub bandwidth_mb: u32,
+    pub replication_factor: u32,
+    pub objects: BTreeMap<u64, u64>,
+    pub stored_mb: u64,
+    pub reads: u64,
+    pub writes: u64,
+    pub not_found: u64,
+    pub under_replicated: u64,
+    pub bandwidth_used_mb: u32,
+    /// storage bill not yet charged to the budget.
+    pub accrued_cost: f64,
+}
+
+impl Default for StorageNode {
+    fn default() -> Self {
+        Self {
+            bandwidth_mb: DEFAULT_STORAGE_BANDWIDTH_MB,
+            replication_factor: 1,
+            objects: BTreeMap::new(),
+            stored_mb: 0,
+            reads: 0,
+            writes: 0,
+            not_found: 0,
+            under_replicated: 0,
+            bandwidth_used_mb: 0,
+            accrued_cost: 0.0,
+        }
+    }
+}
+
+impl StorageNode {
+    pub fn object_size_mb(key: u64) -> u64 {
+        1 + (key.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32) % MAX_OBJECT_MB
+    }
+
+    pub fn stored_gb(&self) -> f64 {
+        self.stored_mb as f64 / 1024.0
+    }
+
+    pub fn put(&mut self, key: u64) {
+        let size = Self::object_size_mb(key);
+        if self.objects.insert(key, size).is_none() {
+            self.stored_mb += size;
+        }
+    }
+
+    /// the disk is gone: drop every object and return what was on it.
+    pub fn wipe(&mut self) -> BTreeMap<u64, u64> {
+        self.stored_mb = 0;
+        std::mem::take(&mut self.objects)
+    }
+}
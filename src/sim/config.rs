@@ -0,0 +1,533 @@
+use super::components::{
+    CacheNode, CdnNode, ComputeNode, DatabaseNode, FirewallNode, QueueNode, StorageNode,
+    TrafficSource,
+};
+use super::constants::*;
+use super::traffic::KeySampler;
+use super::types::{DbRole, EvictionPolicy, FirewallRule, Geo, KeyDistribution, RequestClass};
+
+use bevy::ecs::query::QueryData;
+use serde::{Deserialize, Serialize};
+
+/// a player change to one node's settings. applied at a tick boundary so runs
+/// can be replayed from the action log.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum NodeConfig {
+    CachePolicy,
+    CacheCapacityMinus,
+    CacheCapacityPlus,
+    KeyDistribution,
+    RateMinus,
+    RatePlus,
+    WriteRatioMinus,
+    WriteRatioPlus,
+    Geo,
+    AttackClass,
+    AttackRatioMinus,
+    AttackRatioPlus,
+    StaticRatioMinus,
+    StaticRatioPlus,
+    CdnHitMinus,
+    CdnHitPlus,
+    CdnPurge,
+    ReplicationMinus,
+    ReplicationPlus,
+    BandwidthMinus,
+    BandwidthPlus,
+    DbRole,
+    LagMinus,
+    LagPlus,
+    QueueDepthMinus,
+    QueueDepthPlus,
+    Redrive,
+    ConcurrencyMinus,
+    ConcurrencyPlus,
+    RuleSwap(usize, usize),
+    RuleDelete(usize),
+    RuleInsert(usize, FirewallRule),
+    /// the settings below are given outright rather than a press at a time,
+    /// for copies and specs. values past what the buttons allow are clamped.
+    SetCachePolicy(EvictionPolicy),
+    SetCacheCapacity(usize),
+    SetKeyDistribution(KeyDistribution),
+    SetRate(u32),
+    SetWriteRatio(f32),
+    SetGeo(Geo),
+    /// `Normal` is not an attack and is ignored.
+    SetAttackClass(RequestClass),
+    SetAttackRatio(f32),
+    SetStaticRatio(f32),
+    SetCdnHit(f32),
+    SetReplication(u32),
+    SetBandwidth(u32),
+    SetDbRole(DbRole),
+    SetLag(u64),
+    SetQueueDepth(usize),
+    SetConcurrency(u32),
+}
+
+/// every per-type component a config change can touch.
+#[derive(QueryData)]
+#[query_data(mutable)]
+pub struct ConfigTarget {
+    pub cache: Option<&'static mut CacheNode>,
+    pub source: Option<&'static mut TrafficSource>,
+    pub db: Option<&'static mut DatabaseNode>,
+    pub queue: Option<&'static mut QueueNode>,
+    pub compute: Option<&'static mut ComputeNode>,
+    pub firewall: Option<&'static mut FirewallNode>,
+    pub cdn: Option<&'static mut CdnNode>,
+    pub storage: Option<&'static mut StorageNode>,
+}
+
+impl ConfigTargetItem<'_, '_> {
+    /// changes that do not fit the node's type are ignored.
+    pub fn apply(&mut self, config: NodeConfig, now: u64) {
+        match config {
+            NodeConfig::CachePolicy => {
+                if let Some(cache) = &mut self.cache {
+                    let policy = cache.store.policy().next();
+                    cache.store.set_policy(policy);
+                }
+            }
+            NodeConfig::CacheCapacityMinus => {
+                if let Some(cache) = &mut self.cache {
+                    let capacity = cache.store.capacity().saturating_sub(CACHE_CAPACITY_STEP);
+                    cache.store.set_capacity(capacity, now);
+                }
+            }
+            NodeConfig::CacheCapacityPlus => {
+                if let Some(cache) = &mut self.cache {
+                    let capacity = cache.store.capacity() + CACHE_CAPACITY_STEP;
+                    cache.store.set_capacity(capacity, now);
+                }
+            }
+            NodeConfig::KeyDistribution => {
+                if let Some(source) = &mut self.source {
+                    let dist = source.sampler.dist().next();
+                    source.sampler = KeySampler::new(dist, source.sampler.keyspace());
+                }
+            }
+            NodeConfig::RateMinus => {
+                if let Some(source) = &mut self.source {
+                    source.rate = source.rate.saturating_sub(REQUEST_RATE_STEP);
+                }
+            }
+            NodeConfig::RatePlus => {
+                if let Some(source) = &mut self.source {
+                    source.rate += REQUEST_RATE_STEP;
+                }
+            }
+            NodeConfig::WriteRatioMinus => {
+                if let Some(source) = &mut self.source {
+                    source.write_ratio = (source.write_ratio - WRITE_RATIO_STEP).max(0.0);
+                }
+            }
+            NodeConfig::WriteRatioPlus => {
+                if let Some(source) = &mut self.source {
+                    source.write_ratio = (source.write_ratio + WRITE_RATIO_STEP).min(1.0);
+                }
+            }
+            NodeConfig::Geo => {
+                if let Some(source) = &mut self.source {
+                    source.geo = source.geo.next();
+                }
+            }
+            NodeConfig::AttackClass => {
+                if let Some(source) = &mut self.source {
+                    source.attack_class = source.attack_class.next_attack();
+                }
+            }
+            NodeConfig::AttackRatioMinus => {
+                if let Some(source) = &mut self.source {
+                    source.attack_ratio = (source.attack_ratio - ATTACK_RATIO_STEP).max(0.0);
+                }
+            }
+            NodeConfig::AttackRatioPlus => {
+                if let Some(source) = &mut self.source {
+                    source.attack_ratio = (source.attack_ratio + ATTACK_RATIO_STEP).min(1.0);
+                }
+            }
+            NodeConfig::StaticRatioMinus => {
+                if let Some(source) = &mut self.source {
+                    source.static_ratio = (source.static_ratio - STATIC_RATIO_STEP).max(0.0);
+                }
+            }
+            NodeConfig::StaticRatioPlus => {
+                if let Some(source) = &mut self.source {
+                    source.static_ratio = (source.static_ratio + STATIC_RATIO_STEP).min(1.0);
+                }
+            }
+            NodeConfig::CdnHitMinus => {
+                if let Some(cdn) = &mut self.cdn {
+                    cdn.hit_fraction = (cdn.hit_fraction - CDN_HIT_FRACTION_STEP).max(0.0);
+                }
+            }
+            NodeConfig::CdnHitPlus => {
+                if let Some(cdn) = &mut self.cdn {
+                    cdn.hit_fraction = (cdn.hit_fraction + CDN_HIT_FRACTION_STEP).min(1.0);
+                }
+            }
+            NodeConfig::CdnPurge => {
+                if let Some(cdn) = &mut self.cdn {
+                    cdn.purge();
+                }
+            }
+            NodeConfig::ReplicationMinus => {
+                if let Some(storage) = &mut self.storage {
+                    storage.replication_factor = (storage.replication_factor - 1).max(1);
+                }
+            }
+            NodeConfig::ReplicationPlus => {
+                if let Some(storage) = &mut self.storage {
+                    storage.replication_factor =
+                        (storage.replication_factor + 1).min(MAX_REPLICATION_FACTOR);
+                }
+            }
+            NodeConfig::BandwidthMinus => {
+                if let Some(storage) = &mut self.storage {
+                    storage.bandwidth_mb = storage
+                        .bandwidth_mb
+                        .saturating_sub(STORAGE_BANDWIDTH_STEP_MB)
+                        .max(STORAGE_BANDWIDTH_STEP_MB);
+                }
+            }
+            NodeConfig::BandwidthPlus => {
+                if let Some(storage) = &mut self.storage {
+                    storage.bandwidth_mb += STORAGE_BANDWIDTH_STEP_MB;
+                }
+            }
+            NodeConfig::DbRole => {
+                if let Some(db) = &mut self.db {
+                    db.role = db.role.toggled();
+                    db.pending.clear();
+                }
+            }
+            NodeConfig::LagMinus => {
+                if let Some(db) = &mut self.db {
+                    db.replication_lag = db.replication_lag.saturating_sub(REPLICATION_LAG_STEP);
+                }
+            }
+            NodeConfig::LagPlus => {
+                if let Some(db) = &mut self.db {
+                    db.replication_lag += REPLICATION_LAG_STEP;
+                }
+            }
+            NodeConfig::QueueDepthMinus => {
+                if let Some(queue) = &mut self.queue {
+                    queue.max_depth = queue.max_depth.saturating_sub(QUEUE_DEPTH_STEP).max(1);
+                }
+            }
+            NodeConfig::QueueDepthPlus => {
+                if let Some(queue) = &mut self.queue {
+                    queue.max_depth += QUEUE_DEPTH_STEP;
+                }
+            }
+            NodeConfig::Redrive => {
+                if let Some(queue) = &mut self.queue {
+                    queue.redrive();
+                }
+            }
+            NodeConfig::ConcurrencyMinus => {
+                if let Some(compute) = &mut self.compute {
+                    compute.concurrency = compute.concurrency.saturating_sub(1).max(1);
+                }
+            }
+            NodeConfig::ConcurrencyPlus => {
+                if let Some(compute) = &mut self.compute {
+                    compute.concurrency += 1;
+                }
+            }
+            NodeConfig::RuleSwap(a, b) => {
+                if let Some(firewall) = &mut self.firewall
+                    && a < firewall.rules.len()
+                    && b < firewall.rules.len()
+                {
+                    firewall.rules.swap(a, b);
+                }
+            }
+            NodeConfig::RuleDelete(at) => {
+                if let Some(firewall) = &mut self.firewall
+                    && at < firewall.rules.len()
+                {
+                    firewall.rules.remove(at);
+                }
+            }
+            NodeConfig::RuleInsert(at, rule) => {
+                if let Some(firewall) = &mut self.firewall {
+                    let at = at.min(firewall.rules.len());
+                    firewall.rules.insert(at, rule);
+                }
+            }
+            NodeConfig::SetCachePolicy(policy) => {
+                if let Some(cache) = &mut self.cache {
+                    cache.store.set_policy(policy);
+                }
+            }
+            NodeConfig::SetCacheCapacity(capacity) => {
+                if let Some(cache) = &mut self.cache {
+                    cache.store.set_capacity(capacity, now);
+                }
+            }
+            NodeConfig::SetKeyDistribution(dist) => {
+                if let Some(source) = &mut self.source {
+                    source.sampler = KeySampler::new(dist, source.sampler.keyspace());
+                }
+            }
+            NodeConfig::SetRate(rate) => {
+                if let Some(source) = &mut self.source {
+                    source.rate = rate;
+                }
+            }
+            NodeConfig::SetWriteRatio(ratio) => {
+                if let Some(source) = &mut self.source {
+                    source.write_ratio = ratio.clamp(0.0, 1.0);
+                }
+            }
+            NodeConfig::SetGeo(geo) => {
+                if let Some(source) = &mut self.source {
+                    source.geo = geo;
+                }
+            }
+            NodeConfig::SetAttackClass(class) => {
+                if let Some(source) = &mut self.source
+                    && class.is_malicious()
+                {
+                    source.attack_class = class;
+                }
+            }
+            NodeConfig::SetAttackRatio(ratio) => {
+                if let Some(source) = &mut self.source {
+                    source.attack_ratio = ratio.clamp(0.0, 1.0);
+                }
+            }
+            NodeConfig::SetStaticRatio(ratio) => {
+                if let Some(source) = &mut self.source {
+                    source.static_ratio = ratio.clamp(0.0, 1.0);
+                }
+            }
+            NodeConfig::SetCdnHit(fraction) => {
+                if let Some(cdn) = &mut self.cdn {
+                    cdn.hit_fraction = fraction.clamp(0.0, 1.0);
+                }
+            }
+            NodeConfig::SetReplication(factor) => {
+                if let Some(storage) = &mut self.storage {
+                    storage.replication_factor = factor.clamp(1, MAX_REPLICATION_FACTOR);
+                }
+            }
+            NodeConfig::SetBandwidth(bandwidth) => {
+                if let Some(storage) = &mut self.storage {
+                    storage.bandwidth_mb = bandwidth.max(STORAGE_BANDWIDTH_STEP_MB);
+                }
+            }
+            NodeConfig::SetDbRole(role) => {
+                if let Some(db) = &mut self.db
+                    && db.role != role
+                {
+                    db.role = role;
+                    db.pending.clear();
+                }
+            }
+            NodeConfig::SetLag(lag) => {
+                if let Some(db) = &mut self.db {
+                    db.replication_lag = lag;
+                }
+            }
+            NodeConfig::SetQueueDepth(depth) => {
+                if let Some(queue) = &mut self.queue {
+                    queue.max_depth = depth.max(1);
+                }
+            }
+            NodeConfig::SetConcurrency(concurrency) => {
+                if let Some(compute) = &mut self.compute {
+                    compute.concurrency = concurrency.max(1);
+                }
+            }
+        }
+    }
+}
+
+impl ConfigTargetReadOnlyItem<'_, '_> {
+    /// the config changes that give a freshly placed node these settings,
+    /// one for each setting that differs from a new node's.
+    pub fn settings(&self) -> Vec<NodeConfig> {
+        let mut settings = Vec::new();
+        let mut set = |changed: bool, config: NodeConfig| {
+            if changed {
+                settings.push(config);
+            }
+        };
+        if let Some(cache) = self.cache {
+            let default = CacheNode::default();
+            let (policy, capacity) = (cache.store.policy(), cache.store.capacity());
+            set(
+                policy != default.store.policy(),
+                NodeConfig::SetCachePolicy(policy),
+            );
+            set(
+                capacity != default.store.capacity(),
+                NodeConfig::SetCacheCapacity(capacity),
+            );
+        }
+        if let Some(source) = self.source {
+            let default = TrafficSource::default();
+            set(
+                source.rate != default.rate,
+                NodeConfig::SetRate(source.rate),
+            );
+            set(
+                source.write_ratio != default.write_ratio,
+                NodeConfig::SetWriteRatio(source.write_ratio),
+            );
+            set(
+                source.static_ratio != default.static_ratio,
+                NodeConfig::SetStaticRatio(source.static_ratio),
+            );
+            set(
+                source.attack_ratio != default.attack_ratio,
+                NodeConfig::SetAttackRatio(source.attack_ratio),
+            );
+            set(
+                source.sampler.dist() != default.sampler.dist(),
+                NodeConfig::SetKeyDistribution(source.sampler.dist()),
+            );
+            set(source.geo != default.geo, NodeConfig::SetGeo(source.geo));
+            set(
+                source.attack_class != default.attack_class,
+                NodeConfig::SetAttackClass(source.attack_class),
+            );
+        }
+        if let Some(db) = self.db {
+            let default = DatabaseNode::default();
+            set(db.role != default.role, NodeConfig::SetDbRole(db.role));
+            set(
+                db.replication_lag != default.replication_lag,
+                NodeConfig::SetLag(db.replication_lag),
+            );
+        }
+        if let Some(queue) = self.queue {
+            set(
+                queue.max_depth != QueueNode::default().max_depth,
+                NodeConfig::SetQueueDepth(queue.max_depth),
+            );
+        }
+        if let Some(compute) = self.compute {
+            set(
+                compute.concurrency != ComputeNode::default().concurrency,
+                NodeConfig::SetConcurrency(compute.concurrency),
+            );
+        }
+        if let Some(cdn) = self.cdn {
+            set(
+                cdn.hit_fraction != CdnNode::default().hit_fraction,
+                NodeConfig::SetCdnHit(cdn.hit_fraction),
+            );
+        }
+        if let Some(storage) = self.storage {
+            let default = StorageNode::default();
+            set(
+                storage.replication_factor != default.replication_factor,
+                NodeConfig::SetReplication(storage.replication_factor),
+            );
+            set(
+                storage.bandwidth_mb != default.bandwidth_mb,
+                NodeConfig::SetBandwidth(storage.bandwidth_mb),
+            );
+        }
+        if let Some(firewall) = self.firewall {
+            let default = FirewallNode::default().rules;
+            if firewall.rules != default {
+                settings.extend(default.iter().map(|_| NodeConfig::RuleDelete(0)));
+                settings.extend(
+                    firewall
+                        .rules
+                        .iter()
+                        .enumerate()
+                        .map(|(i, rule)| NodeConfig::RuleInsert(i, *rule)),
+                );
+            }
+        }
+        settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::prelude::*;
+
+    fn node(world: &mut World) -> Entity {
+        world
+            .spawn((
+                TrafficSource::default(),
+                CacheNode::default(),
+                DatabaseNode::default(),
+                StorageNode::default(),
+            ))
+            .id()
+    }
+
+    #[test]
+    fn settings_copy_values_the_buttons_cannot_reach() {
+        let mut world = World::new();
+        let original = node(&mut world);
+        let copy = node(&mut world);
+
+        let mut targets = world.query::<ConfigTarget>();
+        let mut target = targets.get_mut(&mut world, original).unwrap();
+        for config in [
+            NodeConfig::SetRate(13),
+            NodeConfig::SetWriteRatio(0.37),
+            NodeConfig::SetGeo(Geo::Asia),
+            NodeConfig::SetKeyDistribution(KeyDistribution::Uniform),
+            NodeConfig::SetCachePolicy(EvictionPolicy::Fifo),
+            NodeConfig::SetCacheCapacity(333),
+            NodeConfig::SetDbRole(DbRole::Replica),
+            NodeConfig::SetReplication(MAX_REPLICATION_FACTOR + 4),
+        ] {
+            target.apply(config, 0);
+        }
+
+        let settings = world
+            .query::<ConfigTargetReadOnly>()
+            .get(&world, original)
+            .unwrap()
+            .settings();
+        assert_eq!(settings.len(), 8);
+        let mut target = targets.get_mut(&mut world, copy).unwrap();
+        for config in settings {
+            target.apply(config, 0);
+        }
+
+        let source = world.get::<TrafficSource>(copy).unwrap();
+        assert_eq!(
+            (source.rate, source.write_ratio, source.geo),
+            (13, 0.37, Geo::Asia)
+        );
+        assert_eq!(source.sampler.dist(), KeyDistribution::Uniform);
+        let cache = &world.get::<CacheNode>(copy).unwrap().store;
+        assert_eq!(
+            (cache.policy(), cache.capacity()),
+            (EvictionPolicy::Fifo, 333)
+        );
+        assert_eq!(
+            world.get::<DatabaseNode>(copy).unwrap().role,
+            DbRole::Replica
+        );
+        let storage = world.get::<StorageNode>(copy).unwrap();
+        assert_eq!(storage.replication_factor, MAX_REPLICATION_FACTOR);
+    }
+
+    #[test]
+    fn a_fresh_node_has_nothing_to_copy() {
+        let mut world = World::new();
+        let fresh = node(&mut world);
+        let settings = world
+            .query::<ConfigTargetReadOnly>()
+            .get(&world, fresh)
+            .unwrap()
+            .settings();
+        assert!(settings.is_empty());
+    }
+}
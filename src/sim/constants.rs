@@ -0,0 +1,67 @@
+// Simulation clock
+pub const SIM_TICK_HZ: f64 = 20.0;
+pub const FAST_SPEED: f32 = 4.0;
+pub const SIM_DEFAULT_SEED: u64 = 0x5EED;
+
+// Node defaults
+pub const DEFAULT_NODE_CAPACITY: u32 = 20; // requests serviced per tick
+pub const DEFAULT_QUEUE_LIMIT: usize = 200;
+pub const MAX_HOPS: u8 = 32;
+
+// Traffic defaults (Internet nodes)
+pub const DEFAULT_REQUEST_RATE: u32 = 10; // requests generated per tick
+pub const REQUEST_RATE_STEP: u32 = 5;
+pub const DEFAULT_KEYSPACE: u64 = 1000;
+pub const ZIPF_EXPONENTS: [f64; 2] = [0.8, 1.2];
+pub const DEFAULT_WRITE_RATIO: f32 = 0.1;
+pub const WRITE_RATIO_STEP: f32 = 0.05;
+pub const ATTACK_RATIO_STEP: f32 = 0.05;
+pub const DEFAULT_STATIC_RATIO: f32 = 0.3;
+pub const STATIC_RATIO_STEP: f32 = 0.05;
+
+// Cache
+pub const DEFAULT_CACHE_CAPACITY: usize = 100;
+pub const CACHE_CAPACITY_STEP: usize = 25;
+pub const DEFAULT_CACHE_TTL: u64 = 200; // ticks
+
+// CDN
+pub const CDN_CAPACITY: u32 = 200; // edges are sized well above origins
+pub const DEFAULT_CDN_HIT_FRACTION: f32 = 0.85;
+pub const CDN_HIT_FRACTION_STEP: f32 = 0.05;
+pub const CDN_WARMUP_PER_TICK: f32 = 0.01;
+
+// Database replication
+pub const DEFAULT_REPLICATION_LAG: u64 = 20; // ticks
+pub const REPLICATION_LAG_STEP: u64 = 5;
+
+// Message queues
+pub const DEFAULT_QUEUE_DEPTH: usize = 500;
+pub const QUEUE_DEPTH_STEP: usize = 100;
+pub const VISIBILITY_TIMEOUT: u64 = 40; // ticks before an unacked message is redelivered
+pub const MAX_RECEIVES: u32 = 3; // deliveries before a message is dead-lettered
+pub const DEAD_LETTER_LIMIT: usize = 1000;
+pub const DEFAULT_CONSUMER_CONCURRENCY: u32 = 4;
+
+// Object storage
+pub const MAX_OBJECT_MB: u64 = 64;
+pub const DEFAULT_STORAGE_BANDWIDTH_MB: u32 = 400; // per tick
+pub const STORAGE_BANDWIDTH_STEP_MB: u32 = 100;
+pub const MAX_REPLICATION_FACTOR: u32 = 3;
+pub const STORAGE_COST_PER_GB_TICK: f64 = 0.02;
+pub const DATA_LOSS_PENALTY_PER_GB: f64 = 200.0;
+
+// Security
+pub const BREACH_PENALTY: i64 = 500;
+
+// SLA
+pub const SLA_WINDOW_TICKS: u64 = 20;
+pub const SLA_MAX_LATENCY_TICKS: f32 = 20.0;
+pub const SLA_MAX_DROP_RATE: f32 = 0.05;
+
+// Metrics export
+pub const METRICS_LOG_MAX_TICKS: usize = 72_000;
+pub const APP_DATA_DIR: &str = "server_sim";
+
+// Stat history
+pub const HISTORY_LEN: usize = 60;
+pub const HISTORY_SAMPLE_TICKS: u64 = 10;
@@ -0,0 +1,167 @@
+use super::constants::APP_DATA_DIR;
+use crate::game::replay::ActionLog;
+
+use super::recorder::{GlobalSample, LinkSample, MetricsLog, NodeSample, SlaEvent};
+
+use bevy::prelude::*;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// where the last export went, or why it failed.
+#[derive(Resource, Default)]
+pub struct LastExport(pub Option<Result<PathBuf, String>>);
+
+#[derive(Serialize)]
+struct ExportJson<'a> {
+    globals: &'a [GlobalSample],
+    nodes: &'a [NodeSample],
+    links: &'a [LinkSample],
+    sla_events: &'a [SlaEvent],
+}
+
+/// per-user data directory, following the platform conventions.
+pub fn user_data_dir() -> PathBuf {
+    let base = if let Some(dir) = std::env::var_os("XDG_DATA_HOME") {
+        PathBuf::from(dir)
+    } else if cfg!(windows)
+        && let Some(dir) = std::env::var_os("APPDATA")
+    {
+        PathBuf::from(dir)
+    } else if let Some(home) = std::env::var_os("HOME") {
+        if cfg!(target_os = "macos") {
+            PathBuf::from(home).join("Library/Application Support")
+        } else {
+            PathBuf::from(home).join(".local/share")
+        }
+    } else {
+        PathBuf::from(".")
+    };
+    base.join(APP_DATA_DIR)
+}
+
+/// a new directory under `parent` named after the current time. exports in
+/// the same millisecond get a numbered one rather than sharing it.
+fn fresh_run_dir(parent: &Path) -> io::Result<PathBuf> {
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    fs::create_dir_all(parent)?;
+    for n in 1.. {
+        let name = match n {
+            1 => format!("run-{stamp}"),
+            _ => format!("run-{stamp}-{n}"),
+        };
+        let dir = parent.join(name);
+        match fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    unreachable!()
+}
+
+/// write the metrics log as CSV tables plus one JSON document into a fresh
+/// run directory, next to the run's replay file, and return its path.
+pub fn export_run(log: &mut MetricsLog, actions: &ActionLog) -> io::Result<PathBuf> {
+    let dir = fresh_run_dir(&user_data_dir().join("exports"))?;
+
+    log.globals.make_contiguous();
+    log.nodes.make_contiguous();
+    log.links.make_contiguous();
+    let globals = log.globals.as_slices().0;
+    let nodes = log.nodes.as_slices().0;
+    let links = log.links.as_slices().0;
+
+    let mut csv = String::from(
+        "tick,generated,completed,dropped,throttled,in_flight,avg_latency_ticks,budget\n",
+    );
+    for s in globals {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{:.3},{}",
+            s.tick,
+            s.generated,
+            s.completed,
+            s.dropped,
+            s.throttled,
+            s.in_flight,
+            s.avg_latency_ticks,
+            s.budget
+        );
+    }
+    fs::write(dir.join("global.csv"), csv)?;
+
+    let mut csv =
+        String::from("tick,node,node_type,queue,received,served,forwarded,dropped,failed\n");
+    for s in nodes {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{}",
+            s.tick,
+            s.node,
+            s.node_type,
+            s.queue,
+            s.received,
+            s.served,
+            s.forwarded,
+            s.dropped,
+            s.failed
+        );
+    }
+    fs::write(dir.join("nodes.csv"), csv)?;
+
+    let mut csv = String::from("tick,from,to,requests,utilization\n");
+    for s in links {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{:.3}",
+            s.tick, s.from, s.to, s.requests, s.utilization
+        );
+    }
+    fs::write(dir.join("links.csv"), csv)?;
+
+    let mut csv = String::from("tick,metric,value,threshold,breached\n");
+    for e in &log.sla_events {
+        let _ = writeln!(
+            csv,
+            "{},{},{:.3},{:.3},{}",
+            e.tick,
+            e.metric.name(),
+            e.value,
+            e.threshold,
+            e.breached
+        );
+    }
+    fs::write(dir.join("sla_events.csv"), csv)?;
+
+    let json = ExportJson {
+        globals,
+        nodes,
+        links,
+        sla_events: &log.sla_events,
+    };
+    let json = serde_json::to_string_pretty(&json).map_err(io::Error::other)?;
+    fs::write(dir.join("metrics.json"), json)?;
+    actions.save(&dir.join("replay.json"))?;
+
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_to_back_exports_get_their_own_directories() {
+        let parent = std::env::temp_dir().join(format!("export-test-{}", std::process::id()));
+        let first = fresh_run_dir(&parent).unwrap();
+        let second = fresh_run_dir(&parent).unwrap();
+        fs::remove_dir_all(&parent).unwrap();
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::game::NodeType;
+use crate::game::components::{NodeLinks, NodeTag};
+
+use bevy::prelude::*;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// the board's nodes and links as a plain graph, for checks that run before
+/// (or without) the simulation.
+#[derive(Default)]
+pub struct BoardGraph {
+    pub types: BTreeMap<Entity, NodeType>,
+    pub links: BTreeMap<Entity, Vec<Entity>>,
+}
+
+impl BoardGraph {
+    pub fn new<'a>(nodes: impl Iterator<Item = (Entity, &'a NodeTag, &'a NodeLinks)>) -> Self {
+        let mut graph = BoardGraph::default();
+        let nodes: Vec<_> = nodes.collect();
+        for (node_e, tag, _) in &nodes {
+            graph.types.insert(*node_e, tag.node_type);
+        }
+        // links to nodes deleted this frame are not pruned yet
+        for (node_e, _, links) in nodes {
+            let out = links
+                .out
+                .iter()
+                .copied()
+                .filter(|e| graph.types.contains_key(e))
+                .collect();
+            graph.links.insert(node_e, out);
+        }
+        graph
+    }
+
+    pub fn sources(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.types
+            .iter()
+            .filter(|(_, t)| **t == NodeType::Internet)
+            .map(|(e, _)| *e)
+    }
+
+    fn links_of(&self, node: Entity) -> &[Entity] {
+        self.links.get(&node).map_or(&[], |out| out.as_slice())
+    }
+
+    fn has_type(&self, node: Entity, types: &[NodeType]) -> bool {
+        self.types.get(&node).is_some_and(|t| types.contains(t))
+    }
+
+    /// the links a node sends requests over, as its service system picks them.
+    /// database and storage links only carry replication.
+    pub fn request_targets(&self, node: Entity) -> Vec<Entity> {
+        let out = self.links_of(node);
+        let preferred = |types: &[NodeType]| -> Vec<Entity> {
+            let picked: Vec<Entity> = out
+                .iter()
+                .copied()
+                .filter(|e| self.has_type(*e, types))
+                .collect();
+            if picked.is_empty() {
+                out.to_vec()
+            } else {
+                picked
+            }
+        };
+
+        match self.types.get(&node) {
+            Some(NodeType::Database | NodeType::Storage) | None => Vec::new(),
+            Some(NodeType::Queue) => out
+                .iter()
+                .copied()
+                .filter(|e| self.has_type(*e, &[NodeType::Compute]))
+                .collect(),
+            Some(NodeType::Cache) => preferred(&[NodeType::Database]),
+            Some(NodeType::CDN) => preferred(&[NodeType::Storage, NodeType::Compute]),
+            Some(_) => out.to_vec(),
+        }
+    }
+
+    /// true if requests can finish on this node instead of being passed on.
+    pub fn serves(&self, node: Entity) -> bool {
+        match self.types.get(&node) {
+            Some(NodeType::Database | NodeType::Storage) => true,
+            Some(NodeType::LoadBalancer | NodeType::Compute | NodeType::Firewall) => {
+                self.links_of(node).is_empty()
+            }
+            _ => false,
+        }
+    }
+
+    /// nodes reachable from `roots` over request links, never entering a
+    /// blocked node. roots count as reached unless blocked themselves.
+    pub fn reach(
+        &self,
+        roots: impl IntoIterator<Item = Entity>,
+        blocked: impl Fn(Entity) -> bool,
+    ) -> BTreeSet<Entity> {
+        self.walk(roots, blocked, |node| self.request_targets(node))
+    }
+
+    /// like `reach`, but following every link including replication.
+    pub fn reach_any_link(&self, roots: impl IntoIterator<Item = Entity>) -> BTreeSet<Entity> {
+        self.walk(roots, |_| false, |node| self.links_of(node).to_vec())
+    }
+
+    fn walk(
+        &self,
+        roots: impl IntoIterator<Item = Entity>,
+        blocked: impl Fn(Entity) -> bool,
+        next: impl Fn(Entity) -> Vec<Entity>,
+    ) -> BTreeSet<Entity> {
+        let mut seen = BTreeSet::new();
+        let mut open: VecDeque<Entity> = roots.into_iter().filter(|e| !blocked(*e)).collect();
+        while let Some(node) = open.pop_front() {
+            if !seen.insert(node) {
+                continue;
+            }
+            open.extend(next(node).into_iter().filter(|e| !blocked(*e)));
+        }
+        seen
+    }
+}
@@ -0,0 +1,267 @@
+use super::graph::BoardGraph;
+
+use crate::game::NodeType;
+use crate::game::components::{NodeLinks, NodeTag};
+
+use bevy::prelude::*;
+use std::collections::BTreeSet;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LintKind {
+    Unreachable,
+    ExposedDatabase,
+    SinglePointOfFailure,
+    Cycle,
+    QueueWithoutConsumer,
+}
+
+/// one problem found on the board and the nodes it is about.
+#[derive(Clone, Debug)]
+pub struct LintIssue {
+    pub kind: LintKind,
+    pub nodes: Vec<Entity>,
+    pub message: String,
+}
+
+/// the board's current problems, refreshed whenever nodes or links change.
+#[derive(Resource, Default)]
+pub struct LintReport {
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    pub fn flags(&self, node: Entity) -> bool {
+        self.issues.iter().any(|issue| issue.nodes.contains(&node))
+    }
+}
+
+fn label(graph: &BoardGraph, node: Entity) -> String {
+    let name = graph.types.get(&node).map_or("?", |t| t.name());
+    format!("{} #{}", name, node.index())
+}
+
+pub fn lint_board(graph: &BoardGraph) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let sources: Vec<Entity> = graph.sources().collect();
+
+    // replicas only fed by replication still count as part of the system
+    let reached = graph.reach_any_link(sources.iter().copied());
+    let unreachable: Vec<Entity> = graph
+        .types
+        .keys()
+        .copied()
+        .filter(|e| !reached.contains(e))
+        .collect();
+    if !unreachable.is_empty() {
+        issues.push(LintIssue {
+            kind: LintKind::Unreachable,
+            message: format!(
+                "{} node(s) not reachable from any Internet source",
+                unreachable.len()
+            ),
+            nodes: unreachable,
+        });
+    }
+
+    let unfiltered = graph.reach(sources.iter().copied(), |e| {
+        graph.types.get(&e) == Some(&NodeType::Firewall)
+    });
+    for db in unfiltered
+        .iter()
+        .filter(|e| graph.types.get(e) == Some(&NodeType::Database))
+    {
+        issues.push(LintIssue {
+            kind: LintKind::ExposedDatabase,
+            nodes: vec![*db],
+            message: format!("{} is reachable without a Firewall", label(graph, *db)),
+        });
+    }
+
+    // a node whose loss leaves some source with nowhere to finish requests
+    let mut spofs = BTreeSet::new();
+    for source in &sources {
+        let reach = graph.reach([*source], |_| false);
+        if !reach.iter().any(|e| graph.serves(*e)) {
+            continue;
+        }
+        for candidate in reach.iter().filter(|e| *e != source) {
+            if spofs.contains(candidate) {
+                continue;
+            }
+            let without = graph.reach([*source], |e| e == *candidate);
+            if !without.iter().any(|e| graph.serves(*e)) {
+                spofs.insert(*candidate);
+            }
+        }
+    }
+    for node in spofs {
+        issues.push(LintIssue {
+            kind: LintKind::SinglePointOfFailure,
+            nodes: vec![node],
+            message: format!("{} is a single point of failure", label(graph, node)),
+        });
+    }
+
+    // group nodes that can reach each other into request loops
+    let downstream: Vec<(Entity, BTreeSet<Entity>)> = graph
+        .types
+        .keys()
+        .map(|e| (*e, graph.reach(graph.request_targets(*e), |_| false)))
+        .collect();
+    let mut in_loop = BTreeSet::new();
+    for (node, reach) in &downstream {
+        if !reach.contains(node) || in_loop.contains(node) {
+            continue;
+        }
+        let members: Vec<Entity> = downstream
+            .iter()
+            .filter(|(other, other_reach)| reach.contains(other) && other_reach.contains(node))
+            .map(|(other, _)| *other)
+            .collect();
+        in_loop.extend(members.iter().copied());
+        issues.push(LintIssue {
+            kind: LintKind::Cycle,
+            message: format!("Request loop through {} node(s)", members.len()),
+            nodes: members,
+        });
+    }
+
+    for (queue, _) in graph.types.iter().filter(|(_, t)| **t == NodeType::Queue) {
+        if graph.request_targets(*queue).is_empty() {
+            issues.push(LintIssue {
+                kind: LintKind::QueueWithoutConsumer,
+                nodes: vec![*queue],
+                message: format!("{} has no Compute consumer", label(graph, *queue)),
+            });
+        }
+    }
+
+    issues
+}
+
+pub fn lint_board_system(
+    mut report: ResMut<LintReport>,
+    mut removed: RemovedComponents<NodeTag>,
+    changed: Query<(), Or<(Changed<NodeLinks>, Added<NodeTag>)>>,
+    nodes: Query<(Entity, &NodeTag, &NodeLinks)>,
+) {
+    if removed.read().count() == 0 && changed.is_empty() {
+        return;
+    }
+    report.issues = lint_board(&BoardGraph::new(nodes.iter()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// nodes by type with their links, as indices into the list.
+    fn board(nodes: &[(NodeType, &[usize])]) -> (BoardGraph, Vec<Entity>) {
+        let mut world = World::new();
+        let entities: Vec<Entity> = nodes.iter().map(|_| world.spawn_empty().id()).collect();
+        let mut graph = BoardGraph::default();
+        for ((node_type, out), node_e) in nodes.iter().zip(&entities) {
+            graph.types.insert(*node_e, *node_type);
+            graph
+                .links
+                .insert(*node_e, out.iter().map(|i| entities[*i]).collect());
+        }
+        (graph, entities)
+    }
+
+    /// the nodes of each issue of `kind`, all sorted.
+    fn flagged(graph: &BoardGraph, kind: LintKind) -> Vec<Vec<Entity>> {
+        let mut flagged: Vec<Vec<Entity>> = lint_board(graph)
+            .into_iter()
+            .filter(|issue| issue.kind == kind)
+            .map(|issue| {
+                let mut nodes = issue.nodes;
+                nodes.sort();
+                nodes
+            })
+            .collect();
+        flagged.sort();
+        flagged
+    }
+
+    #[test]
+    fn a_redundant_firewalled_board_is_clean() {
+        let (graph, _) = board(&[
+            (NodeType::Internet, &[1, 2]),
+            (NodeType::Firewall, &[3]),
+            (NodeType::Firewall, &[4]),
+            (NodeType::Compute, &[5]),
+            (NodeType::Compute, &[6]),
+            (NodeType::Database, &[]),
+            (NodeType::Database, &[]),
+        ]);
+        assert!(lint_board(&graph).is_empty());
+    }
+
+    #[test]
+    fn databases_need_a_firewall_in_front() {
+        let (graph, nodes) = board(&[
+            (NodeType::Internet, &[1, 2]),
+            (NodeType::Compute, &[3]),
+            (NodeType::Firewall, &[4]),
+            (NodeType::Database, &[]),
+            (NodeType::Compute, &[5]),
+            (NodeType::Database, &[]),
+        ]);
+        assert_eq!(flagged(&graph, LintKind::ExposedDatabase), [[nodes[3]]]);
+    }
+
+    #[test]
+    fn finds_single_points_of_failure() {
+        let (graph, nodes) = board(&[
+            (NodeType::Internet, &[1]),
+            (NodeType::LoadBalancer, &[2, 3]),
+            (NodeType::Compute, &[4]),
+            (NodeType::Compute, &[4]),
+            (NodeType::Database, &[]),
+        ]);
+        let mut spofs = vec![vec![nodes[1]], vec![nodes[4]]];
+        spofs.sort();
+        assert_eq!(flagged(&graph, LintKind::SinglePointOfFailure), spofs);
+    }
+
+    #[test]
+    fn finds_request_loops() {
+        let (graph, nodes) = board(&[
+            (NodeType::Internet, &[1]),
+            (NodeType::LoadBalancer, &[2]),
+            (NodeType::LoadBalancer, &[1, 3]),
+            (NodeType::Compute, &[]),
+        ]);
+        let mut members = vec![nodes[1], nodes[2]];
+        members.sort();
+        assert_eq!(flagged(&graph, LintKind::Cycle), [members]);
+    }
+
+    #[test]
+    fn queues_need_a_compute_consumer() {
+        let (graph, nodes) = board(&[
+            (NodeType::Internet, &[1, 2]),
+            (NodeType::Queue, &[3]),
+            (NodeType::Queue, &[4]),
+            (NodeType::Database, &[]),
+            (NodeType::Compute, &[]),
+        ]);
+        assert_eq!(
+            flagged(&graph, LintKind::QueueWithoutConsumer),
+            [[nodes[1]]]
+        );
+    }
+
+    #[test]
+    fn replicas_count_as_reached() {
+        let (graph, nodes) = board(&[
+            (NodeType::Internet, &[1]),
+            (NodeType::Compute, &[2]),
+            (NodeType::Database, &[3]),
+            (NodeType::Database, &[]),
+            (NodeType::Compute, &[]),
+        ]);
+        assert_eq!(flagged(&graph, LintKind::Unreachable), [[nodes[4]]]);
+    }
+}
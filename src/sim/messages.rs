@@ -0,0 +1,18 @@
+use bevy::prelude::*;
+
+/// take a node down or bring it back.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct SetNodeFailed {
+    pub node: Entity,
+    pub failed: bool,
+}
+
+/// promote a healthy replica of `primary` and point its clients at it.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct Failover {
+    pub primary: Entity,
+}
+
+/// write the recorded metrics to the user data directory.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct ExportMetrics;
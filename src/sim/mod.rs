@@ -0,0 +1,17 @@
+pub mod analysis;
+pub mod cache;
+pub mod components;
+pub mod config;
+pub mod constants;
+pub mod export;
+pub mod graph;
+pub mod lint;
+pub mod messages;
+pub mod plugin;
+pub mod recorder;
+pub mod resources;
+pub mod systems;
+pub mod traffic;
+pub mod types;
+
+pub use plugin::SimPlugin;
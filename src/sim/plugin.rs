@@ -0,0 +1,65 @@
+use super::analysis::{Analysis, analyze_board_system};
+use super::constants::SIM_TICK_HZ;
+use super::export::LastExport;
+use super::lint::{LintReport, lint_board_system};
+use super::messages::{ExportMetrics, Failover, SetNodeFailed};
+use super::recorder::{MetricsLog, record_metrics_system};
+use super::resources::{
+    Backpressure, FastForward, QueueAcks, SimClock, SimRng, SimStats, Trace, Transit,
+};
+use super::systems::{
+    advance_clock_system, attach_sim_components, cache_service_system, cdn_service_system,
+    database_service_system, deliver_transit_system, fast_speed_system, firewall_service_system,
+    forward_service_system, generate_traffic_system, normal_speed_system, queue_service_system,
+    sim_running, storage_service_system, trace_request_system,
+};
+
+use crate::game::GameState;
+
+use bevy::prelude::*;
+
+pub struct SimPlugin;
+
+impl Plugin for SimPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(SIM_TICK_HZ))
+            .init_resource::<SimClock>()
+            .init_resource::<SimRng>()
+            .init_resource::<SimStats>()
+            .init_resource::<Trace>()
+            .init_resource::<Transit>()
+            .init_resource::<Backpressure>()
+            .init_resource::<QueueAcks>()
+            .init_resource::<FastForward>()
+            .init_resource::<MetricsLog>()
+            .init_resource::<LastExport>()
+            .init_resource::<LintReport>()
+            .init_resource::<Analysis>()
+            .add_message::<SetNodeFailed>()
+            .add_message::<Failover>()
+            .add_message::<ExportMetrics>()
+            .add_observer(attach_sim_components)
+            .add_systems(OnEnter(GameState::Playing), normal_speed_system)
+            .add_systems(OnEnter(GameState::Fast), fast_speed_system)
+            .add_systems(
+                FixedUpdate,
+                (
+                    advance_clock_system,
+                    deliver_transit_system,
+                    generate_traffic_system,
+                    firewall_service_system,
+                    cdn_service_system,
+                    cache_service_system,
+                    database_service_system,
+                    queue_service_system,
+                    storage_service_system,
+                    forward_service_system,
+                    trace_request_system,
+                    record_metrics_system,
+                )
+                    .chain()
+                    .run_if(sim_running),
+            )
+            .add_systems(Update, (lint_board_system, analyze_board_system));
+    }
+}
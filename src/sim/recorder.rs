@@ -0,0 +1,223 @@
+use super::components::SimNode;
+use super::constants::*;
+use super::resources::{SimClock, SimStats, Transit};
+
+use crate::game::components::NodeTag;
+use crate::game::resources::Economy;
+
+use bevy::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GlobalSample {
+    pub tick: u64,
+    pub generated: u64,
+    pub completed: u64,
+    pub dropped: u64,
+    pub throttled: u64,
+    pub in_flight: u64,
+    pub avg_latency_ticks: f32,
+    pub budget: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NodeSample {
+    pub tick: u64,
+    pub node: u32,
+    pub node_type: &'static str,
+    pub queue: usize,
+    pub received: u64,
+    pub served: u64,
+    pub forwarded: u64,
+    pub dropped: u64,
+    pub failed: bool,
+}
+
+/// requests sent over one link in one tick, relative to the target's capacity.
+#[derive(Clone, Debug, Serialize)]
+pub struct LinkSample {
+    pub tick: u64,
+    pub from: u32,
+    pub to: u32,
+    pub requests: u32,
+    pub utilization: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+pub enum SlaMetric {
+    Latency,
+    DropRate,
+}
+
+impl SlaMetric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SlaMetric::Latency => "latency",
+            SlaMetric::DropRate => "drop_rate",
+        }
+    }
+}
+
+/// an SLA target crossed in either direction over one window.
+#[derive(Clone, Debug, Serialize)]
+pub struct SlaEvent {
+    pub tick: u64,
+    pub metric: SlaMetric,
+    pub value: f32,
+    pub threshold: f32,
+    pub breached: bool,
+}
+
+/// counters at the start of the current SLA window.
+#[derive(Default)]
+struct SlaWindow {
+    completed: u64,
+    dropped: u64,
+    latency_ticks: u64,
+    latency_breached: bool,
+    drops_breached: bool,
+}
+
+/// per-tick time series of the whole run, kept for export.
+#[derive(Resource, Default)]
+pub struct MetricsLog {
+    pub globals: VecDeque<GlobalSample>,
+    pub nodes: VecDeque<NodeSample>,
+    pub links: VecDeque<LinkSample>,
+    pub sla_events: Vec<SlaEvent>,
+    window: SlaWindow,
+}
+
+impl MetricsLog {
+    pub fn sla_breached(&self) -> bool {
+        self.window.latency_breached || self.window.drops_breached
+    }
+
+    /// drop the oldest ticks once the log is over its limit.
+    fn trim(&mut self) {
+        while self.globals.len() > METRICS_LOG_MAX_TICKS {
+            let Some(oldest) = self.globals.pop_front() else {
+                break;
+            };
+            while self.nodes.front().is_some_and(|s| s.tick <= oldest.tick) {
+                self.nodes.pop_front();
+            }
+            while self.links.front().is_some_and(|s| s.tick <= oldest.tick) {
+                self.links.pop_front();
+            }
+        }
+    }
+
+    fn check_sla(&mut self, tick: u64, stats: &SimStats) {
+        let completed = stats.completed - self.window.completed;
+        let dropped = stats.dropped - self.window.dropped;
+        let latency_ticks = stats.total_latency_ticks - self.window.latency_ticks;
+
+        let latency = if completed == 0 {
+            0.0
+        } else {
+            latency_ticks as f32 / completed as f32
+        };
+        let finished = completed + dropped;
+        let drop_rate = if finished == 0 {
+            0.0
+        } else {
+            dropped as f32 / finished as f32
+        };
+
+        let latency_breached = latency > SLA_MAX_LATENCY_TICKS;
+        if latency_breached != self.window.latency_breached {
+            self.sla_events.push(SlaEvent {
+                tick,
+                metric: SlaMetric::Latency,
+                value: latency,
+                threshold: SLA_MAX_LATENCY_TICKS,
+                breached: latency_breached,
+            });
+        }
+        let drops_breached = drop_rate > SLA_MAX_DROP_RATE;
+        if drops_breached != self.window.drops_breached {
+            self.sla_events.push(SlaEvent {
+                tick,
+                metric: SlaMetric::DropRate,
+                value: drop_rate,
+                threshold: SLA_MAX_DROP_RATE,
+                breached: drops_breached,
+            });
+        }
+
+        self.window = SlaWindow {
+            completed: stats.completed,
+            dropped: stats.dropped,
+            latency_ticks: stats.total_latency_ticks,
+            latency_breached,
+            drops_breached,
+        };
+    }
+}
+
+/// sample global, node and link metrics at the end of every tick.
+/// everything sent this tick is still in transit, which gives link usage.
+pub fn record_metrics_system(
+    clock: Res<SimClock>,
+    stats: Res<SimStats>,
+    economy: Res<Economy>,
+    transit: Res<Transit>,
+    mut log: ResMut<MetricsLog>,
+    nodes: Query<(Entity, &NodeTag, &SimNode)>,
+) {
+    let tick = clock.tick;
+
+    let mut in_flight = transit.hops.len() as u64;
+    for (node_e, tag, node) in &nodes {
+        in_flight += node.inbox.len() as u64;
+        log.nodes.push_back(NodeSample {
+            tick,
+            node: node_e.index(),
+            node_type: tag.node_type.name(),
+            queue: node.inbox.len(),
+            received: node.stats.received,
+            served: node.stats.served,
+            forwarded: node.stats.forwarded,
+            dropped: node.stats.dropped,
+            failed: node.failed,
+        });
+    }
+
+    log.globals.push_back(GlobalSample {
+        tick,
+        generated: stats.generated,
+        completed: stats.completed,
+        dropped: stats.dropped,
+        throttled: stats.throttled,
+        in_flight,
+        avg_latency_ticks: stats.avg_latency_ticks(),
+        budget: economy.budget,
+    });
+
+    let mut link_counts: BTreeMap<(Entity, Entity), u32> = BTreeMap::new();
+    // held hops were already counted on the tick they were sent
+    for hop in transit.hops.iter().filter(|hop| hop.waited == 0) {
+        *link_counts.entry((hop.from, hop.to)).or_default() += 1;
+    }
+    for ((from, to), requests) in link_counts {
+        let capacity = nodes.get(to).map_or(0, |(_, _, node)| node.capacity);
+        log.links.push_back(LinkSample {
+            tick,
+            from: from.index(),
+            to: to.index(),
+            requests,
+            utilization: if capacity == 0 {
+                0.0
+            } else {
+                requests as f32 / capacity as f32
+            },
+        });
+    }
+
+    if tick.is_multiple_of(SLA_WINDOW_TICKS) {
+        log.check_sla(tick, &stats);
+    }
+    log.trim();
+}
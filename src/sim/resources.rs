@@ -0,0 +1,210 @@
+use super::constants::SIM_DEFAULT_SEED;
+use super::types::{ContentKind, Delivery, Geo, Request, RequestClass, RequestKind};
+
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+#[derive(Resource, Default)]
+pub struct SimClock {
+    pub tick: u64,
+}
+
+impl SimClock {
+    pub fn new_request(&mut self, key: u64, kind: RequestKind) -> Request {
+        Request {
+            key,
+            kind,
+            content: ContentKind::Dynamic,
+            class: RequestClass::Normal,
+            source: None,
+            geo: Geo::default(),
+            delivery: None,
+            created_at: self.tick,
+            enqueued_at: self.tick,
+            hops: 0,
+            traced: false,
+        }
+    }
+}
+
+/// the only randomness source the simulation is allowed to use.
+#[derive(Resource)]
+pub struct SimRng(pub ChaCha8Rng);
+
+impl Default for SimRng {
+    fn default() -> Self {
+        Self::seeded(SIM_DEFAULT_SEED)
+    }
+}
+
+impl SimRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+/// run sim ticks regardless of the game state, used to seek through replays.
+#[derive(Resource, Default, PartialEq)]
+pub struct FastForward(pub bool);
+
+pub struct Hop {
+    pub from: Entity,
+    pub to: Entity,
+    pub request: Request,
+    /// ticks held back so far for crossing zones.
+    pub waited: u64,
+}
+
+/// requests sent this tick, delivered to their target inbox on the next tick,
+/// or later when the link crosses zones.
+#[derive(Resource, Default)]
+pub struct Transit {
+    pub hops: Vec<Hop>,
+}
+
+impl Transit {
+    pub fn send(&mut self, from: Entity, to: Entity, mut request: Request) {
+        request.hops = request.hops.saturating_add(1);
+        self.hops.push(Hop {
+            from,
+            to,
+            request,
+            waited: 0,
+        });
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SimStats {
+    pub generated: u64,
+    pub completed: u64,
+    pub dropped: u64,
+    pub throttled: u64,
+    pub blocked_attacks: u64,
+    pub breaches: u64,
+    pub storage_spend: i64,
+    pub data_loss_events: u64,
+    pub lost_objects: u64,
+    pub total_latency_ticks: u64,
+    /// tick at which the traced request completed, picked up by the tracer.
+    pub traced_completed_at: Option<u64>,
+}
+
+impl SimStats {
+    pub fn complete(&mut self, request: &Request, now: u64) {
+        self.completed += 1;
+        self.total_latency_ticks += now.saturating_sub(request.created_at);
+        if request.traced {
+            self.traced_completed_at = Some(now);
+        }
+    }
+
+    pub fn avg_latency_ticks(&self) -> f32 {
+        if self.completed == 0 {
+            return 0.0;
+        }
+        self.total_latency_ticks as f32 / self.completed as f32
+    }
+}
+
+/// nodes that refuse new requests this tick (full queues).
+#[derive(Resource, Default)]
+pub struct Backpressure {
+    pub blocked: Vec<Entity>,
+}
+
+impl Backpressure {
+    pub fn is_blocked(&self, node: Entity) -> bool {
+        self.blocked.contains(&node)
+    }
+}
+
+/// consumer acknowledgements, picked up by the owning queue on the next tick.
+#[derive(Resource, Default)]
+pub struct QueueAcks {
+    pub acks: Vec<Delivery>,
+}
+
+/// time a traced request spent at one node. it waits from `arrived` until
+/// `left`; the tick it leaves on is the one it was serviced in.
+#[derive(Clone, Copy, Debug)]
+pub struct TraceSpan {
+    pub node: Entity,
+    pub arrived: u64,
+    pub left: Option<u64>,
+}
+
+impl TraceSpan {
+    pub fn queued_ticks(&self, now: u64) -> u64 {
+        match self.left {
+            Some(left) => left - self.arrived,
+            None => now - self.arrived,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TraceOutcome {
+    InFlight,
+    Completed,
+    Dropped,
+}
+
+impl TraceOutcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TraceOutcome::InFlight => "in flight",
+            TraceOutcome::Completed => "completed",
+            TraceOutcome::Dropped => "dropped",
+        }
+    }
+}
+
+/// a single sampled request followed hop by hop, like a distributed trace.
+#[derive(Resource, Default)]
+pub struct Trace {
+    /// source that tags its next generated request.
+    pub armed: Option<Entity>,
+    pub started_at: u64,
+    pub spans: Vec<TraceSpan>,
+    pub outcome: Option<TraceOutcome>,
+}
+
+impl Trace {
+    pub fn arm(&mut self, source: Entity) {
+        *self = Trace {
+            armed: Some(source),
+            ..default()
+        };
+    }
+
+    pub fn clear(&mut self) {
+        *self = Trace::default();
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.outcome == Some(TraceOutcome::InFlight)
+    }
+
+    pub fn arrive(&mut self, node: Entity, now: u64) {
+        self.spans.push(TraceSpan {
+            node,
+            arrived: now,
+            left: None,
+        });
+    }
+
+    /// close the span the request is currently sitting in.
+    pub fn leave(&mut self, now: u64) {
+        if let Some(span) = self.spans.last_mut()
+            && span.left.is_none()
+        {
+            span.left = Some(now);
+        }
+    }
+
+    pub fn path(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.spans.iter().map(|span| span.node)
+    }
+}
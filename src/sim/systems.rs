@@ -0,0 +1,893 @@
+use super::components::{
+    CacheNode, CdnNode, ComputeNode, DatabaseNode, FirewallNode, Lease, QueueNode, QueuedMessage,
+    SimNode, StorageNode, TrafficSource,
+};
+use super::constants::*;
+use super::messages::{Failover, SetNodeFailed};
+use super::resources::{
+    Backpressure, FastForward, QueueAcks, SimClock, SimRng, SimStats, Trace, TraceOutcome,
+    Transit,
+};
+use super::types::{ContentKind, DbRole, Delivery, RequestClass, RequestKind, Route};
+
+use crate::game::{GameState, NodeType};
+use crate::game::components::{BoardPos, NodeLinks, NodeTag};
+use crate::game::resources::Economy;
+use crate::game::terrain::Terrain;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+/// give freshly placed nodes their simulation state.
+pub fn attach_sim_components(add: On<Add, NodeTag>, mut commands: Commands, tags: Query<&NodeTag>) {
+    let Ok(tag) = tags.get(add.entity) else {
+        return;
+    };
+
+    let mut node = commands.entity(add.entity);
+    node.insert(SimNode::default());
+    match tag.node_type {
+        NodeType::Internet => {
+            node.insert(TrafficSource::default());
+        }
+        NodeType::Cache => {
+            node.insert(CacheNode::default());
+        }
+        NodeType::Database => {
+            node.insert(DatabaseNode::default());
+        }
+        NodeType::Compute => {
+            node.insert(ComputeNode::default());
+        }
+        NodeType::Queue => {
+            node.insert(QueueNode::default());
+        }
+        NodeType::Firewall => {
+            node.insert(FirewallNode::default());
+        }
+        NodeType::Storage => {
+            node.insert(StorageNode::default());
+        }
+        NodeType::CDN => {
+            node.insert((
+                SimNode {
+                    capacity: CDN_CAPACITY,
+                    ..default()
+                },
+                CdnNode::default(),
+            ));
+        }
+        _ => {}
+    }
+}
+
+/// ticks run while playing, or when a replay seek drives them directly.
+pub fn sim_running(state: Res<State<GameState>>, fast_forward: Res<FastForward>) -> bool {
+    matches!(state.get(), GameState::Playing | GameState::Fast) || fast_forward.0
+}
+
+pub fn normal_speed_system(mut time: ResMut<Time<Virtual>>) {
+    time.set_relative_speed(1.0);
+}
+
+pub fn fast_speed_system(mut time: ResMut<Time<Virtual>>) {
+    time.set_relative_speed(FAST_SPEED);
+}
+
+/// round-robin over a node's outgoing links.
+pub fn next_target(targets: &[Entity], cursor: &mut usize) -> Option<Entity> {
+    if targets.is_empty() {
+        return None;
+    }
+    let target = targets[*cursor % targets.len()];
+    *cursor = cursor.wrapping_add(1);
+    Some(target)
+}
+
+/// round-robin over a node's outgoing links, skipping nodes under backpressure.
+pub fn next_route(targets: &[Entity], cursor: &mut usize, backpressure: &Backpressure) -> Route {
+    if targets.is_empty() {
+        return Route::Serve;
+    }
+    for _ in 0..targets.len() {
+        let target = targets[*cursor % targets.len()];
+        *cursor = cursor.wrapping_add(1);
+        if !backpressure.is_blocked(target) {
+            return Route::Send(target);
+        }
+    }
+    Route::Hold
+}
+
+pub fn advance_clock_system(mut clock: ResMut<SimClock>) {
+    clock.tick += 1;
+}
+
+pub fn deliver_transit_system(
+    clock: Res<SimClock>,
+    mut transit: ResMut<Transit>,
+    mut stats: ResMut<SimStats>,
+    mut trace: ResMut<Trace>,
+    terrain: Res<Terrain>,
+    positions: Query<&BoardPos>,
+    mut nodes: Query<&mut SimNode>,
+) {
+    let mut held = Vec::new();
+    for mut hop in transit.hops.drain(..) {
+        let delay = match (positions.get(hop.from), positions.get(hop.to)) {
+            (Ok(from), Ok(to)) => terrain.link_delay(*from, *to),
+            _ => 0,
+        };
+        if hop.waited < delay {
+            hop.waited += 1;
+            held.push(hop);
+            continue;
+        }
+        if hop.request.traced {
+            trace.arrive(hop.to, clock.tick);
+        }
+
+        // target deleted while the request was in flight
+        let Ok(mut node) = nodes.get_mut(hop.to) else {
+            stats.dropped += 1;
+            continue;
+        };
+
+        node.stats.received += 1;
+        if node.failed || node.inbox.len() >= node.queue_limit || hop.request.hops > MAX_HOPS {
+            node.stats.dropped += 1;
+            stats.dropped += 1;
+            continue;
+        }
+
+        let mut request = hop.request;
+        request.enqueued_at = clock.tick;
+        node.inbox.push_back(request);
+    }
+    transit.hops = held;
+}
+
+pub fn generate_traffic_system(
+    mut clock: ResMut<SimClock>,
+    mut rng: ResMut<SimRng>,
+    mut transit: ResMut<Transit>,
+    mut stats: ResMut<SimStats>,
+    mut trace: ResMut<Trace>,
+    backpressure: Res<Backpressure>,
+    mut sources: Query<(Entity, &TrafficSource, &mut SimNode, &NodeLinks)>,
+) {
+    for (source_e, source, mut node, links) in &mut sources {
+        if node.failed {
+            continue;
+        }
+        for sent in 0..source.rate {
+            let target = match next_route(&links.out, &mut node.rr_cursor, &backpressure) {
+                Route::Send(target) => target,
+                Route::Serve => break,
+                Route::Hold => {
+                    stats.throttled += (source.rate - sent) as u64;
+                    break;
+                }
+            };
+            let key = source.sampler.sample(&mut rng.0);
+            let kind = if rng.0.random::<f32>() < source.write_ratio {
+                RequestKind::Write
+            } else {
+                RequestKind::Read
+            };
+            let mut request = clock.new_request(key, kind);
+            request.source = Some(source_e);
+            if kind == RequestKind::Read && rng.0.random::<f32>() < source.static_ratio {
+                request.content = ContentKind::Static;
+            }
+            request.geo = source.geo;
+            if source.attack_ratio > 0.0 && rng.0.random::<f32>() < source.attack_ratio {
+                request.class = source.attack_class;
+            }
+            if trace.armed == Some(source_e) {
+                request.traced = true;
+                trace.armed = None;
+                trace.started_at = clock.tick;
+                trace.outcome = Some(TraceOutcome::InFlight);
+                trace.arrive(source_e, clock.tick);
+                trace.leave(clock.tick);
+            }
+            stats.generated += 1;
+            node.stats.forwarded += 1;
+            transit.send(source_e, target, request);
+        }
+    }
+}
+
+/// serve hits locally, forward misses to a linked Database and fill the cache.
+/// writes invalidate the key and go straight through.
+pub fn cache_service_system(
+    clock: Res<SimClock>,
+    mut transit: ResMut<Transit>,
+    mut stats: ResMut<SimStats>,
+    mut caches: Query<(Entity, &mut SimNode, &mut CacheNode, &NodeLinks)>,
+    tags: Query<&NodeTag>,
+) {
+    let now = clock.tick;
+
+    for (cache_e, mut node, mut cache, links) in &mut caches {
+        if node.failed {
+            continue;
+        }
+        let databases: Vec<Entity> = links
+            .out
+            .iter()
+            .copied()
+            .filter(|e| {
+                tags.get(*e)
+                    .is_ok_and(|t| t.node_type == NodeType::Database)
+            })
+            .collect();
+        let origins = if databases.is_empty() {
+            &links.out
+        } else {
+            &databases
+        };
+
+        let node = &mut *node;
+        let batch = (node.capacity as usize).min(node.inbox.len());
+        for request in node.inbox.drain(..batch) {
+            if request.kind == RequestKind::Write {
+                cache.store.invalidate(request.key);
+            } else if cache.store.get(request.key, now) {
+                cache.history.record(true);
+                node.stats.served += 1;
+                stats.complete(&request, now);
+                continue;
+            } else {
+                cache.history.record(false);
+                cache.store.insert(request.key, now);
+            }
+
+            match next_target(origins, &mut node.rr_cursor) {
+                Some(target) => {
+                    node.stats.forwarded += 1;
+                    transit.send(cache_e, target, request);
+                }
+                // nothing behind the cache can answer the miss
+                None => {
+                    node.stats.dropped += 1;
+                    stats.dropped += 1;
+                }
+            }
+        }
+
+        if now.is_multiple_of(HISTORY_SAMPLE_TICKS) {
+            cache.history.sample_window();
+        }
+    }
+}
+
+/// default behaviour for node types without a dedicated model:
+/// pass requests on round-robin, or serve them if the node has no links.
+/// requests handed out by a queue are acknowledged once picked up.
+pub fn forward_service_system(
+    clock: Res<SimClock>,
+    mut transit: ResMut<Transit>,
+    mut stats: ResMut<SimStats>,
+    mut acks: ResMut<QueueAcks>,
+    backpressure: Res<Backpressure>,
+    mut nodes: Query<(Entity, &NodeTag, &mut SimNode, &NodeLinks)>,
+) {
+    let now = clock.tick;
+
+    for (node_e, tag, mut node, links) in &mut nodes {
+        if node.failed
+            || matches!(
+                tag.node_type,
+                NodeType::Cache
+                    | NodeType::Database
+                    | NodeType::Queue
+                    | NodeType::Firewall
+                    | NodeType::CDN
+                    | NodeType::Storage
+            )
+        {
+            continue;
+        }
+
+        let node = &mut *node;
+        for _ in 0..node.capacity {
+            if node.inbox.is_empty() {
+                break;
+            }
+            let route = next_route(&links.out, &mut node.rr_cursor, &backpressure);
+            // head of line waits until a downstream queue drains
+            if route == Route::Hold {
+                break;
+            }
+            let Some(mut request) = node.inbox.pop_front() else {
+                break;
+            };
+            if let Some(delivery) = request.delivery.take() {
+                acks.acks.push(delivery);
+            }
+
+            match route {
+                Route::Send(target) => {
+                    node.stats.forwarded += 1;
+                    transit.send(node_e, target, request);
+                }
+                _ => {
+                    node.stats.served += 1;
+                    stats.complete(&request, now);
+                }
+            }
+        }
+    }
+}
+
+/// primaries take writes and replicate them to their linked replicas,
+/// replicas serve reads (possibly stale) and pass writes up to their primary.
+pub fn database_service_system(
+    clock: Res<SimClock>,
+    mut transit: ResMut<Transit>,
+    mut stats: ResMut<SimStats>,
+    mut economy: ResMut<Economy>,
+    mut dbs: Query<(Entity, &mut SimNode, &mut DatabaseNode, &NodeLinks)>,
+) {
+    let now = clock.tick;
+
+    // replication topology: primary -> linked replicas
+    let mut replicas: Vec<(Entity, Entity)> = Vec::new();
+    for (primary_e, _, db, links) in &dbs {
+        if db.role != DbRole::Primary {
+            continue;
+        }
+        for target in &links.out {
+            if dbs
+                .get(*target)
+                .is_ok_and(|(_, _, r, _)| r.role == DbRole::Replica)
+            {
+                replicas.push((primary_e, *target));
+            }
+        }
+    }
+
+    let mut replicated: Vec<(Entity, u64)> = Vec::new();
+    for (db_e, mut node, mut db, _) in &mut dbs {
+        if node.failed {
+            continue;
+        }
+        db.pending.retain(|_, apply_at| *apply_at > now);
+
+        let node = &mut *node;
+        let batch = (node.capacity as usize).min(node.inbox.len());
+        for request in node.inbox.drain(..batch) {
+            // malicious traffic that got this far reaches the data
+            if request.class.is_breach() {
+                db.breaches += 1;
+                stats.breaches += 1;
+                economy.budget -= BREACH_PENALTY;
+            }
+
+            match (db.role, request.kind) {
+                (role, RequestKind::Read) => {
+                    db.reads += 1;
+                    if role == DbRole::Replica && db.is_stale(request.key, now) {
+                        db.stale_reads += 1;
+                    }
+                }
+                (DbRole::Primary, RequestKind::Write) => {
+                    db.writes += 1;
+                    replicated.extend(
+                        replicas
+                            .iter()
+                            .filter(|(p, _)| *p == db_e)
+                            .map(|(_, r)| (*r, request.key)),
+                    );
+                }
+                (DbRole::Replica, RequestKind::Write) => {
+                    match replicas.iter().find(|(_, r)| *r == db_e) {
+                        Some((primary, _)) => {
+                            node.stats.forwarded += 1;
+                            transit.send(db_e, *primary, request);
+                        }
+                        // read-only replica with no primary to take the write
+                        None => {
+                            node.stats.dropped += 1;
+                            stats.dropped += 1;
+                        }
+                    }
+                    continue;
+                }
+            }
+
+            node.stats.served += 1;
+            stats.complete(&request, now);
+        }
+    }
+
+    for (replica_e, key) in replicated {
+        if let Ok((_, _, mut replica, _)) = dbs.get_mut(replica_e) {
+            let apply_at = now + replica.replication_lag;
+            replica.pending.insert(key, apply_at);
+        }
+    }
+}
+
+pub fn set_node_failed_system(
+    mut reader: MessageReader<SetNodeFailed>,
+    mut stats: ResMut<SimStats>,
+    mut nodes: Query<&mut SimNode>,
+) {
+    for msg in reader.read() {
+        let Ok(mut node) = nodes.get_mut(msg.node) else {
+            continue;
+        };
+        node.failed = msg.failed;
+        if msg.failed {
+            // whatever was queued on the node is lost
+            let lost = node.inbox.len() as u64;
+            node.inbox.clear();
+            node.stats.dropped += lost;
+            stats.dropped += lost;
+        }
+    }
+}
+
+pub fn failover_system(
+    mut reader: MessageReader<Failover>,
+    mut dbs: Query<(&SimNode, &mut DatabaseNode)>,
+    mut links: Query<&mut NodeLinks>,
+) {
+    for msg in reader.read() {
+        let primary = msg.primary;
+        if !dbs
+            .get(primary)
+            .is_ok_and(|(_, db)| db.role == DbRole::Primary)
+        {
+            continue;
+        }
+        let Ok(primary_links) = links.get(primary) else {
+            continue;
+        };
+        let old_replicas: Vec<Entity> = primary_links
+            .out
+            .iter()
+            .copied()
+            .filter(|e| dbs.get(*e).is_ok_and(|(_, db)| db.role == DbRole::Replica))
+            .collect();
+        let Some(promoted) = old_replicas
+            .iter()
+            .copied()
+            .find(|e| dbs.get(*e).is_ok_and(|(node, _)| !node.failed))
+        else {
+            continue;
+        };
+
+        // clients follow the primary endpoint
+        for mut node_links in &mut links {
+            if !node_links.out.contains(&primary) {
+                continue;
+            }
+            for target in node_links.out.iter_mut() {
+                if *target == primary {
+                    *target = promoted;
+                }
+            }
+            let mut seen = Vec::new();
+            node_links.out.retain(|e| {
+                let first = !seen.contains(e);
+                seen.push(*e);
+                first
+            });
+        }
+
+        // replication now flows from the promoted replica, including to the old primary
+        if let Ok(mut old) = links.get_mut(primary) {
+            old.out
+                .retain(|e| !old_replicas.contains(e) && *e != promoted);
+        }
+        if let Ok(mut new) = links.get_mut(promoted) {
+            new.out.retain(|e| *e != promoted);
+            for replica in old_replicas.iter().chain([&primary]) {
+                if *replica != promoted && !new.out.contains(replica) {
+                    new.out.push(*replica);
+                }
+            }
+        }
+
+        if let Ok((_, mut db)) = dbs.get_mut(primary) {
+            db.role = DbRole::Replica;
+        }
+        if let Ok((_, mut db)) = dbs.get_mut(promoted) {
+            db.role = DbRole::Primary;
+        }
+    }
+}
+
+/// buffer arrivals, hand messages to linked Compute consumers under a lease,
+/// redeliver expired leases and dead-letter messages that keep failing.
+pub fn queue_service_system(
+    clock: Res<SimClock>,
+    mut transit: ResMut<Transit>,
+    mut acks: ResMut<QueueAcks>,
+    mut backpressure: ResMut<Backpressure>,
+    mut queues: Query<(Entity, &mut SimNode, &mut QueueNode, &NodeLinks)>,
+    consumers: Query<&ComputeNode, Without<QueueNode>>,
+) {
+    let now = clock.tick;
+    let acked: Vec<Delivery> = std::mem::take(&mut acks.acks);
+    backpressure.blocked.clear();
+
+    for (queue_e, mut node, mut queue, links) in &mut queues {
+        let node = &mut *node;
+        let queue = &mut *queue;
+
+        for delivery in acked.iter().filter(|d| d.queue == queue_e) {
+            if let Some(i) = queue.leases.iter().position(|l| l.id == delivery.id) {
+                queue.leases.swap_remove(i);
+                queue.acked += 1;
+            }
+        }
+
+        if node.failed {
+            continue;
+        }
+
+        // intake, anything that doesn't fit waits in the inbox
+        while queue.buffer.len() < queue.max_depth {
+            let Some(request) = node.inbox.pop_front() else {
+                break;
+            };
+            queue.buffer.push_back(QueuedMessage {
+                request,
+                attempts: 0,
+            });
+            queue.enqueued += 1;
+        }
+
+        // expired leases go back to the head of the queue
+        let (expired, live): (Vec<Lease>, Vec<Lease>) = std::mem::take(&mut queue.leases)
+            .into_iter()
+            .partition(|l| l.deadline <= now);
+        queue.leases = live;
+        for lease in expired.into_iter().rev() {
+            if lease.message.attempts >= MAX_RECEIVES {
+                queue.dead_letter(lease.message.request);
+            } else {
+                queue.redelivered += 1;
+                queue.buffer.push_front(lease.message);
+            }
+        }
+
+        let consumer_links: Vec<Entity> = links
+            .out
+            .iter()
+            .copied()
+            .filter(|e| consumers.contains(*e))
+            .collect();
+        let mut dispatched = 0;
+        let mut idle = 0;
+        while dispatched < node.capacity && idle < consumer_links.len() {
+            let Some(consumer) = next_target(&consumer_links, &mut node.rr_cursor) else {
+                break;
+            };
+            let concurrency = consumers.get(consumer).map_or(0, |c| c.concurrency);
+            if queue.leases_for(consumer) >= concurrency {
+                idle += 1;
+                continue;
+            }
+            let Some(mut message) = queue.buffer.pop_front() else {
+                break;
+            };
+            idle = 0;
+            dispatched += 1;
+
+            message.attempts += 1;
+            let id = queue.next_lease_id;
+            queue.next_lease_id += 1;
+            let mut request = message.request.clone();
+            request.delivery = Some(Delivery { queue: queue_e, id });
+            queue.leases.push(Lease {
+                id,
+                consumer,
+                deadline: now + VISIBILITY_TIMEOUT,
+                message,
+            });
+            node.stats.forwarded += 1;
+            transit.send(queue_e, consumer, request);
+        }
+
+        queue.full = queue.buffer.len() >= queue.max_depth;
+        if queue.full {
+            backpressure.blocked.push(queue_e);
+        }
+    }
+}
+
+/// evaluate each request against the rule list. attacks are only recognised
+/// with their class detection rate, the rest look like Normal traffic.
+pub fn firewall_service_system(
+    clock: Res<SimClock>,
+    mut rng: ResMut<SimRng>,
+    mut transit: ResMut<Transit>,
+    mut stats: ResMut<SimStats>,
+    backpressure: Res<Backpressure>,
+    mut firewalls: Query<(Entity, &mut SimNode, &mut FirewallNode, &NodeLinks)>,
+) {
+    let now = clock.tick;
+
+    for (firewall_e, mut node, mut firewall, links) in &mut firewalls {
+        firewall.source_counts.clear();
+        if node.failed {
+            continue;
+        }
+
+        let node = &mut *node;
+        for _ in 0..node.capacity {
+            if node.inbox.is_empty() {
+                break;
+            }
+            let route = next_route(&links.out, &mut node.rr_cursor, &backpressure);
+            if route == Route::Hold {
+                break;
+            }
+            let Some(request) = node.inbox.pop_front() else {
+                break;
+            };
+
+            let detected = request.class.is_malicious()
+                && rng.0.random::<f32>() < request.class.detection_rate();
+            let seen_class = if detected {
+                request.class
+            } else {
+                RequestClass::Normal
+            };
+
+            if !firewall.allows(&request, seen_class) {
+                firewall.blocked += 1;
+                if request.class.is_malicious() {
+                    stats.blocked_attacks += 1;
+                } else {
+                    // a legitimate user got turned away
+                    firewall.false_positives += 1;
+                    node.stats.dropped += 1;
+                    stats.dropped += 1;
+                }
+                continue;
+            }
+
+            firewall.allowed += 1;
+            match route {
+                Route::Send(target) => {
+                    node.stats.forwarded += 1;
+                    transit.send(firewall_e, target, request);
+                }
+                _ => {
+                    node.stats.served += 1;
+                    stats.complete(&request, now);
+                }
+            }
+        }
+    }
+}
+
+/// answer a share of static reads at the edge, send everything else to an
+/// origin (Storage or Compute if linked).
+pub fn cdn_service_system(
+    clock: Res<SimClock>,
+    mut rng: ResMut<SimRng>,
+    mut transit: ResMut<Transit>,
+    mut stats: ResMut<SimStats>,
+    mut cdns: Query<(Entity, &mut SimNode, &mut CdnNode, &NodeLinks)>,
+    tags: Query<&NodeTag>,
+) {
+    let now = clock.tick;
+
+    for (cdn_e, mut node, mut cdn, links) in &mut cdns {
+        cdn.warmth = (cdn.warmth + CDN_WARMUP_PER_TICK).min(1.0);
+        if node.failed {
+            continue;
+        }
+
+        let origins: Vec<Entity> = links
+            .out
+            .iter()
+            .copied()
+            .filter(|e| {
+                tags.get(*e)
+                    .is_ok_and(|t| matches!(t.node_type, NodeType::Storage | NodeType::Compute))
+            })
+            .collect();
+        let origins = if origins.is_empty() {
+            &links.out
+        } else {
+            &origins
+        };
+
+        let node = &mut *node;
+        let batch = (node.capacity as usize).min(node.inbox.len());
+        for request in node.inbox.drain(..batch) {
+            if request.content == ContentKind::Static {
+                let hit = rng.0.random::<f32>() < cdn.hit_fraction * cdn.warmth;
+                cdn.history.record(hit);
+                if hit {
+                    node.stats.served += 1;
+                    stats.complete(&request, now);
+                    continue;
+                }
+            }
+
+            match next_target(origins, &mut node.rr_cursor) {
+                Some(target) => {
+                    cdn.origin_requests += 1;
+                    node.stats.forwarded += 1;
+                    transit.send(cdn_e, target, request);
+                }
+                None => {
+                    node.stats.dropped += 1;
+                    stats.dropped += 1;
+                }
+            }
+        }
+
+        if now.is_multiple_of(HISTORY_SAMPLE_TICKS) {
+            cdn.history.sample_window();
+        }
+    }
+}
+
+/// serve object reads and writes within the node's bandwidth, replicate writes
+/// to linked Storage peers and bill the stored volume.
+pub fn storage_service_system(
+    clock: Res<SimClock>,
+    mut stats: ResMut<SimStats>,
+    mut economy: ResMut<Economy>,
+    mut stores: Query<(Entity, &mut SimNode, &mut StorageNode, &NodeLinks)>,
+) {
+    let now = clock.tick;
+    let mut replicated: Vec<(Entity, u64)> = Vec::new();
+
+    let healthy: Vec<Entity> = stores
+        .iter()
+        .filter(|(_, node, ..)| !node.failed)
+        .map(|(e, ..)| e)
+        .collect();
+
+    for (_, mut node, mut store, links) in &mut stores {
+        store.bandwidth_used_mb = 0;
+        if node.failed {
+            continue;
+        }
+
+        let peers: Vec<Entity> = links
+            .out
+            .iter()
+            .copied()
+            .filter(|e| healthy.contains(e))
+            .take(store.replication_factor.saturating_sub(1) as usize)
+            .collect();
+
+        let node = &mut *node;
+        for _ in 0..node.capacity {
+            let Some(request) = node.inbox.front() else {
+                break;
+            };
+            let size = StorageNode::object_size_mb(request.key) as u32;
+            // always move at least one object per tick, even an oversized one
+            if store.bandwidth_used_mb > 0 && store.bandwidth_used_mb + size > store.bandwidth_mb {
+                break;
+            }
+            let Some(request) = node.inbox.pop_front() else {
+                break;
+            };
+            store.bandwidth_used_mb += size;
+
+            match request.kind {
+                RequestKind::Read => {
+                    store.reads += 1;
+                    if !store.objects.contains_key(&request.key) {
+                        store.not_found += 1;
+                    }
+                }
+                RequestKind::Write => {
+                    store.writes += 1;
+                    store.put(request.key);
+                    if (peers.len() as u32) + 1 < store.replication_factor {
+                        store.under_replicated += 1;
+                    }
+                    replicated.extend(peers.iter().map(|peer| (*peer, request.key)));
+                }
+            }
+            node.stats.served += 1;
+            stats.complete(&request, now);
+        }
+    }
+
+    for (peer, key) in replicated {
+        if let Ok((_, _, mut store, _)) = stores.get_mut(peer) {
+            store.put(key);
+        }
+    }
+
+    for (_, _, mut store, _) in &mut stores {
+        store.accrued_cost += store.stored_gb() * STORAGE_COST_PER_GB_TICK;
+        let bill = store.accrued_cost.floor();
+        if bill >= 1.0 {
+            store.accrued_cost -= bill;
+            economy.budget -= bill as i64;
+            stats.storage_spend += bill as i64;
+        }
+    }
+}
+
+/// a failed Storage node loses its disk. objects with no healthy copy left
+/// anywhere else are gone for good.
+pub fn storage_failure_system(
+    mut reader: MessageReader<SetNodeFailed>,
+    mut stats: ResMut<SimStats>,
+    mut economy: ResMut<Economy>,
+    mut stores: Query<(Entity, &SimNode, &mut StorageNode)>,
+) {
+    for msg in reader.read() {
+        if !msg.failed {
+            continue;
+        }
+        let Ok((_, _, mut store)) = stores.get_mut(msg.node) else {
+            continue;
+        };
+        let objects = store.wipe();
+
+        let mut lost_mb = 0;
+        let mut lost = 0;
+        for (key, size) in objects {
+            let survives = stores.iter().any(|(e, node, other)| {
+                e != msg.node && !node.failed && other.objects.contains_key(&key)
+            });
+            if !survives {
+                lost += 1;
+                lost_mb += size;
+            }
+        }
+
+        if lost > 0 {
+            stats.data_loss_events += 1;
+            stats.lost_objects += lost;
+            economy.budget -= (lost_mb as f64 / 1024.0 * DATA_LOSS_PENALTY_PER_GB).ceil() as i64;
+        }
+    }
+}
+
+/// close the traced request's span once it is back in transit and detect
+/// when it completes or disappears.
+pub fn trace_request_system(
+    clock: Res<SimClock>,
+    transit: Res<Transit>,
+    mut stats: ResMut<SimStats>,
+    mut trace: ResMut<Trace>,
+    nodes: Query<&SimNode>,
+    queues: Query<&QueueNode>,
+) {
+    if !trace.is_active() {
+        return;
+    }
+    let now = clock.tick;
+
+    if let Some(done) = stats.traced_completed_at.take() {
+        trace.leave(done);
+        trace.outcome = Some(TraceOutcome::Completed);
+        return;
+    }
+
+    if transit.hops.iter().any(|hop| hop.request.traced) {
+        trace.leave(now);
+        return;
+    }
+
+    let waiting = nodes.iter().any(|node| node.inbox.iter().any(|r| r.traced))
+        || queues.iter().any(|queue| {
+            queue.buffer.iter().any(|m| m.request.traced)
+                || queue.leases.iter().any(|l| l.message.request.traced)
+        });
+    if !waiting {
+        trace.leave(now);
+        trace.outcome = Some(TraceOutcome::Dropped);
+    }
+}
@@ -0,0 +1,111 @@
+use super::types::KeyDistribution;
+
+use rand::Rng;
+
+/// draws request keys from a keyspace of `keyspace` keys.
+/// Zipf sampling uses a precomputed CDF and a binary search.
+#[derive(Clone, Debug)]
+pub struct KeySampler {
+    dist: KeyDistribution,
+    keyspace: u64,
+    cdf: Vec<f64>,
+}
+
+impl KeySampler {
+    pub fn new(dist: KeyDistribution, keyspace: u64) -> Self {
+        let cdf = match dist {
+            KeyDistribution::Uniform => Vec::new(),
+            KeyDistribution::Zipf { s } => {
+                let mut acc = 0.0;
+                let mut cdf: Vec<f64> = (1..=keyspace.max(1))
+                    .map(|rank| {
+                        acc += 1.0 / (rank as f64).powf(s);
+                        acc
+                    })
+                    .collect();
+                for c in &mut cdf {
+                    *c /= acc;
+                }
+                cdf
+            }
+        };
+
+        Self {
+            dist,
+            keyspace: keyspace.max(1),
+            cdf,
+        }
+    }
+
+    pub fn dist(&self) -> KeyDistribution {
+        self.dist
+    }
+
+    pub fn keyspace(&self) -> u64 {
+        self.keyspace
+    }
+
+    pub fn sample(&self, rng: &mut impl Rng) -> u64 {
+        match self.dist {
+            KeyDistribution::Uniform => rng.random_range(0..self.keyspace),
+            KeyDistribution::Zipf { .. } => {
+                let u: f64 = rng.random();
+                (self.cdf.partition_point(|c| *c < u) as u64).min(self.keyspace - 1)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::constants::ZIPF_EXPONENTS;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    const SAMPLES: usize = 10_000;
+
+    fn draw(dist: KeyDistribution, seed: u64) -> Vec<u64> {
+        let sampler = KeySampler::new(dist, 1000);
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        (0..SAMPLES).map(|_| sampler.sample(&mut rng)).collect()
+    }
+
+    /// the share of keys below 10.
+    fn low_share(keys: &[u64]) -> f64 {
+        keys.iter().filter(|key| **key < 10).count() as f64 / keys.len() as f64
+    }
+
+    #[test]
+    fn zipf_skews_toward_low_keys() {
+        let zipf = draw(
+            KeyDistribution::Zipf {
+                s: ZIPF_EXPONENTS[1],
+            },
+            1,
+        );
+        let uniform = draw(KeyDistribution::Uniform, 1);
+        assert!(zipf.iter().chain(&uniform).all(|key| *key < 1000));
+        assert!(low_share(&zipf) > 0.4, "{}", low_share(&zipf));
+        assert!(low_share(&uniform) < 0.05, "{}", low_share(&uniform));
+
+        let count = |key| zipf.iter().filter(|k| **k == key).count();
+        assert!(count(0) > count(1));
+        assert!(count(1) > count(10));
+    }
+
+    #[test]
+    fn the_same_seed_draws_the_same_keys() {
+        let dist = KeyDistribution::default();
+        assert_eq!(draw(dist, 7), draw(dist, 7));
+        assert_ne!(draw(dist, 7), draw(dist, 8));
+    }
+
+    #[test]
+    fn a_one_key_keyspace_always_draws_it() {
+        let sampler = KeySampler::new(KeyDistribution::default(), 0);
+        let mut rng = ChaCha8Rng::seed_from_u64(3);
+        assert_eq!(sampler.keyspace(), 1);
+        assert!((0..100).all(|_| sampler.sample(&mut rng) == 0));
+    }
+}
@@ -0,0 +1,261 @@
+use super::constants::ZIPF_EXPONENTS;
+
+use bevy::prelude::Entity;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum RequestKind {
+    #[default]
+    Read,
+    Write,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum ContentKind {
+    #[default]
+    Dynamic,
+    Static,
+}
+
+/// traffic class tagged on requests by the Internet node that produced them.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum RequestClass {
+    #[default]
+    Normal,
+    DdosFlood,
+    CredentialStuffing,
+    SqlInjection,
+}
+
+impl RequestClass {
+    pub const ATTACKS: [RequestClass; 3] = [
+        Self::DdosFlood,
+        Self::CredentialStuffing,
+        Self::SqlInjection,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::DdosFlood => "DDoS",
+            Self::CredentialStuffing => "CredStuff",
+            Self::SqlInjection => "SQLi",
+        }
+    }
+
+    pub const fn is_malicious(self) -> bool {
+        !matches!(self, Self::Normal)
+    }
+
+    /// classes that count as a data breach when they reach a Database.
+    pub const fn is_breach(self) -> bool {
+        matches!(self, Self::CredentialStuffing | Self::SqlInjection)
+    }
+
+    /// chance a firewall recognises the class, undetected requests look Normal.
+    pub const fn detection_rate(self) -> f32 {
+        match self {
+            Self::Normal => 1.0,
+            Self::DdosFlood => 0.95,
+            Self::CredentialStuffing => 0.6,
+            Self::SqlInjection => 0.85,
+        }
+    }
+
+    /// cycle through the attack classes.
+    pub fn next_attack(self) -> Self {
+        let i = Self::ATTACKS
+            .iter()
+            .position(|c| *c == self)
+            .map_or(0, |i| i + 1);
+        Self::ATTACKS[i % Self::ATTACKS.len()]
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum Geo {
+    #[default]
+    NorthAmerica,
+    Europe,
+    Asia,
+    Other,
+}
+
+impl Geo {
+    pub const ALL: [Geo; 4] = [Self::NorthAmerica, Self::Europe, Self::Asia, Self::Other];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::NorthAmerica => "NA",
+            Self::Europe => "EU",
+            Self::Asia => "APAC",
+            Self::Other => "Other",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|g| *g == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+/// a firewall rule, evaluated in order, first match wins.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum FirewallRule {
+    Allow(RequestClass),
+    Deny(RequestClass),
+    /// deny once a single source exceeds this many requests in a tick.
+    RateLimit(u32),
+    GeoBlock(Geo),
+}
+
+impl FirewallRule {
+    /// rule templates offered by the inspector editor.
+    pub const CATALOG: [FirewallRule; 11] = [
+        Self::Deny(RequestClass::SqlInjection),
+        Self::Deny(RequestClass::CredentialStuffing),
+        Self::Deny(RequestClass::DdosFlood),
+        Self::Allow(RequestClass::Normal),
+        Self::RateLimit(5),
+        Self::RateLimit(10),
+        Self::RateLimit(25),
+        Self::GeoBlock(Geo::NorthAmerica),
+        Self::GeoBlock(Geo::Europe),
+        Self::GeoBlock(Geo::Asia),
+        Self::GeoBlock(Geo::Other),
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            Self::Allow(class) => format!("Allow {}", class.name()),
+            Self::Deny(class) => format!("Deny {}", class.name()),
+            Self::RateLimit(limit) => format!("Rate limit {limit}/src"),
+            Self::GeoBlock(geo) => format!("Geo-block {}", geo.name()),
+        }
+    }
+}
+
+/// identifies the queue lease a request was handed out under.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Delivery {
+    pub queue: Entity,
+    pub id: u64,
+}
+
+/// a single request travelling through the node graph.
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub key: u64,
+    pub kind: RequestKind,
+    pub content: ContentKind,
+    pub class: RequestClass,
+    pub source: Option<Entity>,
+    pub geo: Geo,
+    pub delivery: Option<Delivery>,
+    pub created_at: u64,
+    pub enqueued_at: u64,
+    pub hops: u8,
+    pub traced: bool,
+}
+
+/// where a node should send its next request.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Route {
+    Send(Entity),
+    /// no outgoing links, the node answers the request itself.
+    Serve,
+    /// every link is signalling backpressure, keep the request queued.
+    Hold,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum KeyDistribution {
+    Uniform,
+    Zipf { s: f64 },
+}
+
+impl Default for KeyDistribution {
+    fn default() -> Self {
+        Self::Zipf {
+            s: ZIPF_EXPONENTS[0],
+        }
+    }
+}
+
+impl KeyDistribution {
+    /// cycle Uniform -> Zipf(s0) -> Zipf(s1) -> ... -> Uniform
+    pub fn next(self) -> Self {
+        match self {
+            Self::Uniform => Self::Zipf {
+                s: ZIPF_EXPONENTS[0],
+            },
+            Self::Zipf { s } => match ZIPF_EXPONENTS.iter().position(|e| *e == s) {
+                Some(i) if i + 1 < ZIPF_EXPONENTS.len() => Self::Zipf {
+                    s: ZIPF_EXPONENTS[i + 1],
+                },
+                _ => Self::Uniform,
+            },
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Self::Uniform => "Uniform".into(),
+            Self::Zipf { s } => format!("Zipf s={s:.1}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum EvictionPolicy {
+    #[default]
+    Lru,
+    Lfu,
+    Fifo,
+    Ttl,
+}
+
+impl EvictionPolicy {
+    pub const ALL: [EvictionPolicy; 4] = [Self::Lru, Self::Lfu, Self::Fifo, Self::Ttl];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Lru => "LRU",
+            Self::Lfu => "LFU",
+            Self::Fifo => "FIFO",
+            Self::Ttl => "TTL",
+        }
+    }
+
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Lru => Self::Lfu,
+            Self::Lfu => Self::Fifo,
+            Self::Fifo => Self::Ttl,
+            Self::Ttl => Self::Lru,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum DbRole {
+    #[default]
+    Primary,
+    Replica,
+}
+
+impl DbRole {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Primary => "Primary",
+            Self::Replica => "Replica",
+        }
+    }
+
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Primary => Self::Replica,
+            Self::Replica => Self::Primary,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use super::styles::*;
+
+use crate::game::components::NodeTag;
+use crate::sim::analysis::Analysis;
+use crate::sim::constants::SIM_TICK_HZ;
+
+use bevy::ecs::prelude::ChildSpawnerCommands;
+use bevy::prelude::*;
+
+const BOTTLENECK_COLOR: Color = Color::srgb(0.95, 0.75, 0.2);
+const BOTTLENECK_RING_Y: f32 = 0.5;
+const BOTTLENECK_RING_RADIUS: f32 = 0.32;
+
+#[derive(Component)]
+pub struct AnalysisPanel;
+
+#[derive(Component)]
+pub struct AnalysisText;
+
+pub fn spawn_analysis_panel(parent: &mut ChildSpawnerCommands) {
+    parent
+        .spawn((
+            Node {
+                width: Val::Px(260.0),
+                position_type: PositionType::Absolute,
+                top: Val::Px(64.0),
+                left: Val::Px(244.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(PANEL_BG),
+            Visibility::Hidden,
+            AnalysisPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(""),
+                text_style(13.0).0,
+                text_style(13.0).1,
+                AnalysisText,
+            ));
+        });
+}
+
+fn node_list(nodes: &[Entity], tags: &Query<&NodeTag>, separator: &str) -> String {
+    nodes
+        .iter()
+        .map(|e| {
+            let name = tags.get(*e).map_or("?", |tag| tag.node_type.name());
+            format!("{} #{}", name, e.index())
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+pub fn update_analysis_panel(
+    analysis: Res<Analysis>,
+    tags: Query<&NodeTag>,
+    text: Option<Single<&mut Text, With<AnalysisText>>>,
+) {
+    let Some(mut text) = text else {
+        return;
+    };
+    if !analysis.is_changed() && !text.0.is_empty() {
+        return;
+    }
+
+    let Some(latency) = analysis.min_latency_ticks() else {
+        text.0 = "Analysis\nNo path from the Internet to a serving node".to_string();
+        return;
+    };
+
+    let load = if analysis.max_flow == 0 {
+        0.0
+    } else {
+        analysis.offered as f64 / analysis.max_flow as f64 * 100.0
+    };
+    let bottlenecks = if analysis.bottlenecks.is_empty() {
+        "none".to_string()
+    } else {
+        node_list(&analysis.bottlenecks, &tags, ", ")
+    };
+    text.0 = format!(
+        "Analysis\nMax throughput {:.0} rps ({}/tick)\nOffered {:.0} rps ({:.0}% of max)\nBottleneck: {}\nFastest path {} ticks ({:.0} ms)\n{}",
+        analysis.max_rps(),
+        analysis.max_flow,
+        analysis.offered_rps(),
+        load,
+        bottlenecks,
+        latency,
+        latency as f64 * 1000.0 / SIM_TICK_HZ,
+        node_list(&analysis.fastest_path, &tags, " > "),
+    );
+}
+
+/// ring the min-cut nodes while the panel is open.
+pub fn draw_bottleneck_markers(
+    mut gizmos: Gizmos,
+    analysis: Res<Analysis>,
+    panel: Option<Single<&Visibility, With<AnalysisPanel>>>,
+    nodes: Query<&Transform, With<NodeTag>>,
+) {
+    if panel.is_none_or(|panel| **panel == Visibility::Hidden) {
+        return;
+    }
+    for node_e in &analysis.bottlenecks {
+        let Ok(tf) = nodes.get(*node_e) else {
+            continue;
+        };
+        let center = Vec3::new(tf.translation.x, BOTTLENECK_RING_Y, tf.translation.z);
+        gizmos.circle(
+            Isometry3d::new(center, Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+            BOTTLENECK_RING_RADIUS,
+            BOTTLENECK_COLOR,
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use super::styles::*;
+use super::text_entry::{EntryEnd, type_text};
+
+use crate::game::blueprint::{Blueprints, SaveBlueprint};
+use crate::game::resources::Game;
+use crate::game::types::ToolType;
+
+use bevy::ecs::prelude::ChildSpawnerCommands;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+
+const MAX_BLUEPRINT_NAME_LEN: usize = 32;
+
+#[derive(Component)]
+pub struct BlueprintPanel;
+
+#[derive(Component)]
+pub struct BlueprintStatus;
+
+/// one row per library blueprint, rebuilt whenever the library changes.
+#[derive(Component)]
+pub struct BlueprintRows;
+
+/// a library entry, clicking it picks the blueprint up for stamping.
+#[derive(Component)]
+pub struct BlueprintRow(pub usize);
+
+#[derive(Component)]
+pub struct BlueprintSaveButton;
+
+/// the name the copied group is about to be saved under, while typing it.
+#[derive(Resource, Default)]
+pub struct BlueprintNameEntry {
+    pub active: bool,
+    pub text: String,
+}
+
+pub fn spawn_blueprint_panel(parent: &mut ChildSpawnerCommands) {
+    parent
+        .spawn((
+            Node {
+                width: Val::Px(260.0),
+                position_type: PositionType::Absolute,
+                top: Val::Px(64.0),
+                left: Val::Px(516.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(PANEL_BG),
+            Visibility::Hidden,
+            BlueprintPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(""),
+                text_style(13.0).0,
+                text_style(13.0).1,
+                BlueprintStatus,
+            ));
+            panel
+                .spawn((
+                    Button,
+                    Node {
+                        height: Val::Px(24.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BackgroundColor(BTN_IDLE),
+                    BlueprintSaveButton,
+                ))
+                .with_children(|btn| {
+                    btn.spawn((
+                        Text::new("Save copy to library"),
+                        text_style(12.0).0,
+                        text_style(12.0).1,
+                    ));
+                });
+            panel.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(3.0),
+                    ..default()
+                },
+                BlueprintRows,
+            ));
+        });
+}
+
+pub fn update_blueprint_panel(
+    mut commands: Commands,
+    blueprints: Res<Blueprints>,
+    entry: Res<BlueprintNameEntry>,
+    status: Option<Single<&mut Text, With<BlueprintStatus>>>,
+    rows: Option<Single<(Entity, Option<&Children>), With<BlueprintRows>>>,
+) {
+    let (Some(mut status), Some(rows)) = (status, rows) else {
+        return;
+    };
+    let (rows_e, children) = *rows;
+
+    let mut content = if entry.active {
+        format!("Name: {}_", entry.text)
+    } else if let Some(copied) = &blueprints.clipboard {
+        format!(
+            "Copied: {} nodes, {} links\nCtrl+V to stamp, Esc to stop",
+            copied.nodes.len(),
+            copied.links.len()
+        )
+    } else {
+        "Shift-click nodes, Ctrl+C to copy".to_string()
+    };
+    content.push('\n');
+    content.push_str(
+        blueprints
+            .imported
+            .as_deref()
+            .unwrap_or("Drop a .dot or .json file here to import it"),
+    );
+    if status.0 != content {
+        status.0 = content;
+    }
+
+    // the HUD can spawn after the library last changed
+    if !blueprints.is_changed() && children.is_some() {
+        return;
+    }
+    commands.entity(rows_e).despawn_children();
+    commands.entity(rows_e).with_children(|list| {
+        for (i, blueprint) in blueprints.library.iter().enumerate() {
+            list.spawn((
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(4.0), Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(BTN_IDLE),
+                BlueprintRow(i),
+            ))
+            .with_children(|row| {
+                row.spawn((
+                    Text::new(format!(
+                        "{}  {} nodes  ${}",
+                        blueprint.name,
+                        blueprint.nodes.len(),
+                        blueprint.base_cost()
+                    )),
+                    text_style(12.0).0,
+                    text_style(12.0).1,
+                ));
+            });
+        }
+    });
+}
+
+pub fn blueprint_panel_buttons(
+    mut game: ResMut<Game>,
+    mut blueprints: ResMut<Blueprints>,
+    mut entry: ResMut<BlueprintNameEntry>,
+    mut rows: Query<
+        (&Interaction, &BlueprintRow, &mut BackgroundColor),
+        (Changed<Interaction>, Without<BlueprintSaveButton>),
+    >,
+    mut save: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<BlueprintSaveButton>),
+    >,
+) {
+    for (interaction, row, mut bg) in &mut rows {
+        *bg = match *interaction {
+            Interaction::Hovered => BTN_HOVER.into(),
+            Interaction::Pressed => BTN_ACTIVE.into(),
+            Interaction::None => BTN_IDLE.into(),
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(blueprint) = blueprints.library.get(row.0).cloned() {
+            blueprints.clipboard = Some(blueprint);
+            game.tool_selection = ToolType::Stamp;
+        }
+    }
+
+    for (interaction, mut bg) in &mut save {
+        *bg = match *interaction {
+            Interaction::Hovered => BTN_HOVER.into(),
+            Interaction::Pressed => BTN_ACTIVE.into(),
+            Interaction::None => BTN_IDLE.into(),
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(copied) = &blueprints.clipboard {
+            entry.active = true;
+            entry.text = copied.name.clone();
+        }
+    }
+}
+
+/// type the blueprint's name, Enter saves it and Escape gives up.
+pub fn blueprint_name_entry(
+    mut keys: MessageReader<KeyboardInput>,
+    mut entry: ResMut<BlueprintNameEntry>,
+    mut save: MessageWriter<SaveBlueprint>,
+) {
+    if !entry.active {
+        keys.clear();
+        return;
+    }
+
+    match type_text(&mut entry.text, MAX_BLUEPRINT_NAME_LEN, keys.read()) {
+        Some(EntryEnd::Submit) => {
+            save.write(SaveBlueprint {
+                name: std::mem::take(&mut entry.text),
+            });
+            entry.active = false;
+        }
+        Some(EntryEnd::Cancel) => entry.active = false,
+        None => {}
+    }
+}
@@ -0,0 +1,392 @@
+use super::styles::*;
+use super::text_entry::{EntryEnd, type_text};
+
+use crate::game::NodeType;
+use crate::game::actions::{
+    Occupancy, OpenContextMenu, PlayerAction, check_placement, check_upgrade,
+};
+use crate::game::blueprint::{Blueprint, BlueprintNode};
+use crate::game::components::{BoardPos, Footprint, NodeName, NodeTag, TileNodeLink, TileTag};
+use crate::game::resources::{Economy, Game};
+use crate::game::terrain::Terrain;
+use crate::game::types::{ToolType, tier_name};
+use crate::sim::components::SimNode;
+use crate::sim::config::ConfigTargetReadOnly;
+
+use bevy::input::keyboard::KeyboardInput;
+use bevy::prelude::*;
+
+/// how far from the original a duplicate may land, in tiles.
+const DUPLICATE_RANGE: usize = 4;
+const MAX_NAME_LEN: usize = 24;
+
+#[derive(Component)]
+pub struct ContextMenuRoot {
+    at: BoardPos,
+    node: Option<Entity>,
+    cursor: Vec2,
+}
+
+#[derive(Component, Clone, Copy)]
+pub enum ContextAction {
+    Inspect,
+    Rename,
+    Duplicate,
+    Delete,
+    LinkFrom,
+    ToggleFailed,
+    Upgrade,
+    Place(NodeType),
+}
+
+/// the node being renamed and what has been typed so far.
+#[derive(Resource, Default)]
+pub struct RenameEntry {
+    pub target: Option<BoardPos>,
+    pub text: String,
+}
+
+#[derive(Component)]
+pub struct RenamePrompt;
+
+fn menu_panel(cursor: Vec2) -> Node {
+    Node {
+        position_type: PositionType::Absolute,
+        left: Val::Px(cursor.x),
+        top: Val::Px(cursor.y),
+        padding: UiRect::all(Val::Px(4.0)),
+        flex_direction: FlexDirection::Column,
+        row_gap: Val::Px(2.0),
+        ..default()
+    }
+}
+
+/// nodes get the actions that make sense for their type and state, empty
+/// tiles list what could be built there.
+pub fn open_context_menu(
+    mut commands: Commands,
+    mut reader: MessageReader<OpenContextMenu>,
+    game: Res<Game>,
+    economy: Res<Economy>,
+    terrain: Res<Terrain>,
+    open: Query<Entity, With<ContextMenuRoot>>,
+    nodes: Query<(&NodeTag, &BoardPos, &SimNode)>,
+    tiles: Query<(&BoardPos, &TileNodeLink), With<TileTag>>,
+) {
+    let Some(request) = reader.read().last() else {
+        return;
+    };
+    for menu_e in &open {
+        commands.entity(menu_e).despawn();
+    }
+
+    let node = request.node.and_then(|e| nodes.get(e).ok());
+    let power_used = |at: BoardPos| {
+        terrain.power_used(
+            terrain.zone_at(at),
+            nodes.iter().map(|(tag, pos, _)| (tag, pos)),
+        )
+    };
+    let entries: Vec<(String, ContextAction)> = match node {
+        Some((tag, at, sim)) => {
+            let mut entries = vec![
+                ("Inspect".into(), ContextAction::Inspect),
+                ("Rename".into(), ContextAction::Rename),
+                ("Duplicate".into(), ContextAction::Duplicate),
+                ("Delete".into(), ContextAction::Delete),
+                ("Link from here".into(), ContextAction::LinkFrom),
+                (
+                    if sim.failed { "Repair" } else { "Fail" }.into(),
+                    ContextAction::ToggleFailed,
+                ),
+            ];
+            if check_upgrade(
+                tag.node_type,
+                tag.tier,
+                *at,
+                economy.budget,
+                &terrain,
+                power_used(*at),
+            )
+            .is_ok()
+            {
+                entries.push((
+                    format!(
+                        "Upgrade to {}  ${}",
+                        tier_name(tag.tier + 1),
+                        terrain.upgrade_cost(tag.node_type, tag.tier + 1, *at)
+                    ),
+                    ContextAction::Upgrade,
+                ));
+            }
+            entries
+        }
+        None => {
+            let power_used = power_used(request.at);
+            let occupancy = Occupancy::of(tiles.iter());
+            NodeType::all()
+                .filter(|(node_type, _)| {
+                    check_placement(
+                        *node_type,
+                        game.placement_rotation,
+                        request.at,
+                        economy.budget,
+                        &terrain,
+                        power_used,
+                        |pos| occupancy.taken(pos),
+                    )
+                    .is_ok()
+                })
+                .map(|(node_type, label)| {
+                    (
+                        format!("Place {label}  ${}", terrain.cost(node_type, request.at)),
+                        ContextAction::Place(node_type),
+                    )
+                })
+                .collect()
+        }
+    };
+    if entries.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn((
+            menu_panel(request.cursor),
+            BackgroundColor(PANEL_BG),
+            GlobalZIndex(10),
+            Interaction::default(),
+            ContextMenuRoot {
+                at: node.map_or(request.at, |(_, at, _)| *at),
+                node: request.node,
+                cursor: request.cursor,
+            },
+        ))
+        .with_children(|menu| {
+            for (label, action) in entries {
+                menu.spawn((
+                    Button,
+                    Node {
+                        min_width: Val::Px(150.0),
+                        height: Val::Px(24.0),
+                        padding: UiRect::horizontal(Val::Px(8.0)),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BackgroundColor(BTN_IDLE),
+                    action,
+                ))
+                .with_children(|b| {
+                    b.spawn((Text::new(label), text_style(14.0).0, text_style(14.0).1));
+                });
+            }
+        });
+}
+
+/// the nearest spot around `at` where `copy` fits, tier and all.
+/// `power_used` gives the power already drawn in a zone.
+fn duplicate_spot(
+    copy: &Blueprint,
+    at: BoardPos,
+    budget: i64,
+    terrain: &Terrain,
+    occupancy: &Occupancy,
+    power_used: impl Fn(Option<usize>) -> u32,
+) -> Option<BoardPos> {
+    let mut spots: Vec<BoardPos> = (at.z.saturating_sub(DUPLICATE_RANGE)..=at.z + DUPLICATE_RANGE)
+        .flat_map(|z| {
+            (at.x.saturating_sub(DUPLICATE_RANGE)..=at.x + DUPLICATE_RANGE)
+                .map(move |x| BoardPos { x, z })
+        })
+        .collect();
+    spots.sort_by_key(|pos| pos.x.abs_diff(at.x).pow(2) + pos.z.abs_diff(at.z).pow(2));
+
+    spots.into_iter().find(|spot| {
+        copy.check(*spot, budget, terrain, &power_used, |pos| {
+            occupancy.taken(pos)
+        })
+        .is_ok()
+    })
+}
+
+pub fn context_menu_buttons(
+    mut commands: Commands,
+    mut game: ResMut<Game>,
+    mut rename: ResMut<RenameEntry>,
+    economy: Res<Economy>,
+    terrain: Res<Terrain>,
+    menu: Option<Single<(Entity, &ContextMenuRoot)>>,
+    mut q: Query<
+        (&Interaction, &ContextAction, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+    mut tags: Query<(
+        Entity,
+        &mut NodeTag,
+        &BoardPos,
+        &Footprint,
+        &SimNode,
+        Option<&NodeName>,
+        ConfigTargetReadOnly,
+    )>,
+    tiles: Query<(&BoardPos, &TileNodeLink), With<TileTag>>,
+    mut actions: MessageWriter<PlayerAction>,
+) {
+    let Some(menu) = menu else {
+        return;
+    };
+    let (menu_e, target) = *menu;
+
+    for (interaction, action, mut bg) in &mut q {
+        *bg = match *interaction {
+            Interaction::Hovered => BTN_HOVER.into(),
+            Interaction::Pressed => BTN_ACTIVE.into(),
+            Interaction::None => BTN_IDLE.into(),
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        commands.entity(menu_e).despawn();
+
+        let at = target.at;
+        let node = target.node.and_then(|e| tags.get(e).ok()).map(
+            |(node_e, tag, _, footprint, sim, name, config)| {
+                (
+                    node_e,
+                    BlueprintNode {
+                        offset: BoardPos { x: 0, z: 0 },
+                        node_type: tag.node_type,
+                        rotation: footprint.rotation,
+                        tier: tag.tier,
+                        name: None,
+                        config: config.settings(),
+                    },
+                    sim.failed,
+                    name.map(|n| n.0.clone()),
+                )
+            },
+        );
+
+        match (*action, node) {
+            (ContextAction::Inspect, Some((node_e, ..))) => {
+                for (e, mut tag, ..) in &mut tags {
+                    tag.selected = e == node_e;
+                }
+            }
+            (ContextAction::Rename, Some((.., name))) => {
+                rename.target = Some(at);
+                rename.text = name.unwrap_or_default();
+                commands.spawn((
+                    menu_panel(target.cursor),
+                    BackgroundColor(PANEL_BG),
+                    GlobalZIndex(10),
+                    Text::new(""),
+                    text_style(14.0).0,
+                    text_style(14.0).1,
+                    RenamePrompt,
+                ));
+            }
+            (ContextAction::Duplicate, Some((_, copy, ..))) => {
+                let copy = Blueprint {
+                    name: String::new(),
+                    nodes: vec![copy],
+                    links: Vec::new(),
+                };
+                if let Some(spot) = duplicate_spot(
+                    &copy,
+                    at,
+                    economy.budget,
+                    &terrain,
+                    &Occupancy::of(tiles.iter()),
+                    |zone| {
+                        terrain.power_used(zone, tags.iter().map(|(_, tag, pos, ..)| (tag, pos)))
+                    },
+                ) {
+                    actions.write_batch(copy.actions(spot));
+                }
+            }
+            (ContextAction::Delete, Some(_)) => {
+                actions.write(PlayerAction::DeleteNode { at });
+            }
+            (ContextAction::LinkFrom, Some((node_e, ..))) => {
+                game.tool_selection = ToolType::Link;
+                game.link_source = Some(node_e);
+            }
+            (ContextAction::ToggleFailed, Some((_, _, failed, _))) => {
+                actions.write(PlayerAction::SetFailed {
+                    at,
+                    failed: !failed,
+                });
+            }
+            (ContextAction::Upgrade, Some(_)) => {
+                actions.write(PlayerAction::Upgrade { at });
+            }
+            (ContextAction::Place(node_type), None) => {
+                actions.write(PlayerAction::PlaceNode {
+                    at,
+                    node_type,
+                    rotation: game.placement_rotation,
+                });
+            }
+            // the node went away while the menu was open
+            _ => {}
+        }
+        return;
+    }
+}
+
+/// clicking anywhere off the menu, or Escape, closes it.
+pub fn close_context_menu(
+    mut commands: Commands,
+    buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    menu: Option<Single<(Entity, &Interaction), With<ContextMenuRoot>>>,
+    entries: Query<&Interaction, With<ContextAction>>,
+) {
+    let Some(menu) = menu else {
+        return;
+    };
+    let (menu_e, interaction) = *menu;
+    let clicked_off = buttons.any_just_pressed([MouseButton::Left, MouseButton::Right])
+        && *interaction == Interaction::None
+        && entries.iter().all(|i| *i == Interaction::None);
+    if clicked_off || keys.just_pressed(KeyCode::Escape) {
+        commands.entity(menu_e).despawn();
+    }
+}
+
+/// type the new name, Enter keeps it and Escape gives up.
+pub fn rename_entry(
+    mut commands: Commands,
+    mut keys: MessageReader<KeyboardInput>,
+    mut rename: ResMut<RenameEntry>,
+    mut prompt: Query<(Entity, &mut Text), With<RenamePrompt>>,
+    mut actions: MessageWriter<PlayerAction>,
+) {
+    let Some(at) = rename.target else {
+        keys.clear();
+        for (prompt_e, _) in &prompt {
+            commands.entity(prompt_e).despawn();
+        }
+        return;
+    };
+
+    match type_text(&mut rename.text, MAX_NAME_LEN, keys.read()) {
+        Some(EntryEnd::Submit) => {
+            actions.write(PlayerAction::Rename {
+                at,
+                name: std::mem::take(&mut rename.text),
+            });
+            rename.target = None;
+        }
+        Some(EntryEnd::Cancel) => rename.target = None,
+        None => {}
+    }
+
+    let content = format!("Name: {}_", rename.text);
+    for (_, mut text) in &mut prompt {
+        if text.0 != content {
+            text.0 = content.clone();
+        }
+    }
+}
@@ -1,4 +1,11 @@
+use super::analysis::spawn_analysis_panel;
+use super::blueprints::spawn_blueprint_panel;
+use super::inspector::spawn_inspector;
+use super::lint::spawn_lint_panel;
+use super::metrics::spawn_metrics_panel;
+use super::replay::spawn_replay_bar;
 use super::styles::*;
+use super::trace::spawn_trace_panel;
 use bevy::prelude::*;
 
 #[derive(Component)]
@@ -19,6 +26,12 @@ pub enum TopBarButton {
     Play,
     Fast,
     Reset,
+    Replay,
+    Analysis,
+    Blueprints,
+    /// lay the selection, or the whole board, out again.
+    Layout,
+    Export,
     Save,
     Quit,
 }
@@ -75,6 +88,11 @@ pub fn ensure_hud_spawned(mut commands: Commands, existing: Query<Entity, With<H
             })
             .with_children(|right| {
                 spawn_small_button(right, "Reset", TopBarButton::Reset);
+                spawn_small_button(right, "Replay", TopBarButton::Replay);
+                spawn_small_button(right, "Analysis", TopBarButton::Analysis);
+                spawn_small_button(right, "Blueprints", TopBarButton::Blueprints);
+                spawn_small_button(right, "Layout", TopBarButton::Layout);
+                spawn_small_button(right, "Export", TopBarButton::Export);
                 spawn_small_button(right, "Save", TopBarButton::Save);
                 spawn_small_button(right, "Quit", TopBarButton::Quit);
             });
@@ -123,9 +141,21 @@ pub fn ensure_hud_spawned(mut commands: Commands, existing: Query<Entity, With<H
         ))
         .with_children(|bar| {
             for (node_type, label) in crate::game::NodeType::all() {
-                spawn_node_slot(bar, label, NodeButton(node_type));
+                let label = format!("{label}\n${}", node_type.cost());
+                spawn_node_slot(bar, &label, NodeButton(node_type));
             }
         });
+
+        // Global metrics
+        spawn_metrics_panel(root);
+
+        // Selected node inspector
+        spawn_inspector(root);
+        spawn_trace_panel(root);
+        spawn_replay_bar(root);
+        spawn_lint_panel(root);
+        spawn_analysis_panel(root);
+        spawn_blueprint_panel(root);
     });
 }
 
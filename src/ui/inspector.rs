@@ -0,0 +1,713 @@
+use super::styles::*;
+
+use crate::game::NodeType;
+use crate::game::actions::PlayerAction;
+use crate::game::components::{BoardPos, NodeName, NodeTag};
+use crate::game::constants::MAX_TIER;
+use crate::game::terrain::Terrain;
+use crate::game::types::tier_name;
+use crate::sim::components::{
+    CacheNode, CdnNode, ComputeNode, DatabaseNode, FirewallNode, HitHistory, QueueNode, SimNode,
+    StorageNode, TrafficSource,
+};
+use crate::sim::config::NodeConfig;
+use crate::sim::constants::HISTORY_LEN;
+use crate::sim::resources::Trace;
+use crate::sim::types::{DbRole, EvictionPolicy, FirewallRule};
+
+use bevy::ecs::prelude::ChildSpawnerCommands;
+use bevy::prelude::*;
+
+const CHART_HEIGHT: f32 = 48.0;
+const CHART_BAR_WIDTH: f32 = 3.0;
+const CHART_BAR_COLOR: Color = Color::srgb(0.35, 0.75, 0.45);
+
+#[derive(Component)]
+pub struct InspectorPanel;
+
+#[derive(Component)]
+pub struct InspectorTitle;
+
+#[derive(Component)]
+pub struct InspectorStats;
+
+/// container for the per-node-type controls, rebuilt when the selection changes.
+#[derive(Component)]
+pub struct InspectorControls;
+
+#[derive(Component)]
+pub struct ChartBar(pub usize);
+
+/// firewall rule editing state: the highlighted rule and the template to add.
+#[derive(Resource, Default)]
+pub struct RuleEditor {
+    pub cursor: usize,
+    pub draft: usize,
+}
+
+#[derive(Component, Clone, Copy)]
+pub enum InspectorButton {
+    ToggleFailed,
+    Upgrade,
+    Trace,
+    Failover,
+    Config(NodeConfig),
+    RulePrev,
+    RuleNext,
+    RuleUp,
+    RuleDown,
+    RuleDelete,
+    DraftPrev,
+    DraftNext,
+    RuleAdd,
+}
+
+pub fn spawn_inspector(parent: &mut ChildSpawnerCommands) {
+    parent
+        .spawn((
+            Node {
+                width: Val::Px(260.0),
+                position_type: PositionType::Absolute,
+                top: Val::Px(64.0),
+                right: Val::Px(12.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(PANEL_BG),
+            Visibility::Hidden,
+            InspectorPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(""),
+                text_style(18.0).0,
+                text_style(18.0).1,
+                InspectorTitle,
+            ));
+            panel.spawn((
+                Text::new(""),
+                text_style(13.0).0,
+                text_style(13.0).1,
+                InspectorStats,
+            ));
+            panel.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(6.0),
+                    ..default()
+                },
+                InspectorControls,
+            ));
+        });
+}
+
+fn spawn_control_row(parent: &mut ChildSpawnerCommands, buttons: &[(&str, InspectorButton)]) {
+    parent
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            column_gap: Val::Px(6.0),
+            ..default()
+        })
+        .with_children(|row| {
+            for (label, action) in buttons {
+                row.spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(74.0),
+                        height: Val::Px(28.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    BackgroundColor(BTN_IDLE),
+                    *action,
+                ))
+                .with_children(|btn| {
+                    btn.spawn((Text::new(*label), text_style(12.0).0, text_style(12.0).1));
+                });
+            }
+        });
+}
+
+fn spawn_history_chart(parent: &mut ChildSpawnerCommands) {
+    parent
+        .spawn((
+            Node {
+                width: Val::Px(HISTORY_LEN as f32 * CHART_BAR_WIDTH),
+                height: Val::Px(CHART_HEIGHT),
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            BackgroundColor(BTN_IDLE),
+        ))
+        .with_children(|chart| {
+            for i in 0..HISTORY_LEN {
+                chart.spawn((
+                    Node {
+                        width: Val::Px(CHART_BAR_WIDTH),
+                        height: Val::Percent(0.0),
+                        ..default()
+                    },
+                    BackgroundColor(CHART_BAR_COLOR),
+                    ChartBar(i),
+                ));
+            }
+        });
+}
+
+fn selected_node<'a>(
+    nodes: impl IntoIterator<Item = (Entity, &'a NodeTag)>,
+) -> Option<(Entity, NodeType)> {
+    nodes
+        .into_iter()
+        .find(|(_, tag)| tag.selected)
+        .map(|(e, tag)| (e, tag.node_type))
+}
+
+pub fn rebuild_inspector_controls(
+    mut commands: Commands,
+    nodes: Query<(Entity, &NodeTag)>,
+    controls: Option<Single<Entity, With<InspectorControls>>>,
+    mut editor: ResMut<RuleEditor>,
+    mut last: Local<Option<Entity>>,
+) {
+    let Some(controls) = controls else {
+        return;
+    };
+    let selected = selected_node(nodes.iter());
+    if selected.map(|(e, _)| e) == *last {
+        return;
+    }
+    *last = selected.map(|(e, _)| e);
+    editor.cursor = 0;
+
+    let mut controls = commands.entity(*controls);
+    controls.despawn_children();
+    let Some((_, node_type)) = selected else {
+        return;
+    };
+
+    controls.with_children(|parent| {
+        if node_type.upgradable() {
+            spawn_control_row(
+                parent,
+                &[
+                    ("Fail/Fix", InspectorButton::ToggleFailed),
+                    ("Upgrade", InspectorButton::Upgrade),
+                ],
+            );
+        } else {
+            spawn_control_row(parent, &[("Fail/Fix", InspectorButton::ToggleFailed)]);
+        }
+        spawn_type_controls(parent, node_type);
+    });
+}
+
+fn spawn_type_controls(parent: &mut ChildSpawnerCommands, node_type: NodeType) {
+    match node_type {
+        NodeType::Internet => {
+            spawn_control_row(
+                parent,
+                &[
+                    ("Keys", InspectorButton::Config(NodeConfig::KeyDistribution)),
+                    ("Rate -", InspectorButton::Config(NodeConfig::RateMinus)),
+                    ("Rate +", InspectorButton::Config(NodeConfig::RatePlus)),
+                ],
+            );
+            spawn_control_row(
+                parent,
+                &[
+                    (
+                        "Writes -",
+                        InspectorButton::Config(NodeConfig::WriteRatioMinus),
+                    ),
+                    (
+                        "Writes +",
+                        InspectorButton::Config(NodeConfig::WriteRatioPlus),
+                    ),
+                    ("Geo", InspectorButton::Config(NodeConfig::Geo)),
+                ],
+            );
+            spawn_control_row(
+                parent,
+                &[
+                    ("Attack", InspectorButton::Config(NodeConfig::AttackClass)),
+                    (
+                        "Atk -",
+                        InspectorButton::Config(NodeConfig::AttackRatioMinus),
+                    ),
+                    (
+                        "Atk +",
+                        InspectorButton::Config(NodeConfig::AttackRatioPlus),
+                    ),
+                ],
+            );
+            spawn_control_row(
+                parent,
+                &[
+                    (
+                        "Static -",
+                        InspectorButton::Config(NodeConfig::StaticRatioMinus),
+                    ),
+                    (
+                        "Static +",
+                        InspectorButton::Config(NodeConfig::StaticRatioPlus),
+                    ),
+                    ("Trace", InspectorButton::Trace),
+                ],
+            );
+        }
+        NodeType::CDN => {
+            spawn_history_chart(parent);
+            spawn_control_row(
+                parent,
+                &[
+                    ("Hit -", InspectorButton::Config(NodeConfig::CdnHitMinus)),
+                    ("Hit +", InspectorButton::Config(NodeConfig::CdnHitPlus)),
+                    ("Purge", InspectorButton::Config(NodeConfig::CdnPurge)),
+                ],
+            );
+        }
+        NodeType::Storage => {
+            spawn_control_row(
+                parent,
+                &[
+                    (
+                        "Repl -",
+                        InspectorButton::Config(NodeConfig::ReplicationMinus),
+                    ),
+                    (
+                        "Repl +",
+                        InspectorButton::Config(NodeConfig::ReplicationPlus),
+                    ),
+                ],
+            );
+            spawn_control_row(
+                parent,
+                &[
+                    ("BW -", InspectorButton::Config(NodeConfig::BandwidthMinus)),
+                    ("BW +", InspectorButton::Config(NodeConfig::BandwidthPlus)),
+                ],
+            );
+        }
+        NodeType::Firewall => {
+            spawn_control_row(
+                parent,
+                &[
+                    ("Prev", InspectorButton::RulePrev),
+                    ("Next", InspectorButton::RuleNext),
+                    ("Delete", InspectorButton::RuleDelete),
+                ],
+            );
+            spawn_control_row(
+                parent,
+                &[
+                    ("Move up", InspectorButton::RuleUp),
+                    ("Move dn", InspectorButton::RuleDown),
+                ],
+            );
+            spawn_control_row(
+                parent,
+                &[
+                    ("< Draft", InspectorButton::DraftPrev),
+                    ("Draft >", InspectorButton::DraftNext),
+                    ("Add", InspectorButton::RuleAdd),
+                ],
+            );
+        }
+        NodeType::Cache => {
+            spawn_history_chart(parent);
+            spawn_control_row(
+                parent,
+                &[
+                    ("Policy", InspectorButton::Config(NodeConfig::CachePolicy)),
+                    (
+                        "Size -",
+                        InspectorButton::Config(NodeConfig::CacheCapacityMinus),
+                    ),
+                    (
+                        "Size +",
+                        InspectorButton::Config(NodeConfig::CacheCapacityPlus),
+                    ),
+                ],
+            );
+        }
+        NodeType::Database => {
+            spawn_control_row(
+                parent,
+                &[
+                    ("Role", InspectorButton::Config(NodeConfig::DbRole)),
+                    ("Lag -", InspectorButton::Config(NodeConfig::LagMinus)),
+                    ("Lag +", InspectorButton::Config(NodeConfig::LagPlus)),
+                ],
+            );
+            spawn_control_row(parent, &[("Failover", InspectorButton::Failover)]);
+        }
+        NodeType::Queue => {
+            spawn_control_row(
+                parent,
+                &[
+                    (
+                        "Depth -",
+                        InspectorButton::Config(NodeConfig::QueueDepthMinus),
+                    ),
+                    (
+                        "Depth +",
+                        InspectorButton::Config(NodeConfig::QueueDepthPlus),
+                    ),
+                    ("Redrive", InspectorButton::Config(NodeConfig::Redrive)),
+                ],
+            );
+        }
+        NodeType::Compute => {
+            spawn_control_row(
+                parent,
+                &[
+                    (
+                        "Conc -",
+                        InspectorButton::Config(NodeConfig::ConcurrencyMinus),
+                    ),
+                    (
+                        "Conc +",
+                        InspectorButton::Config(NodeConfig::ConcurrencyPlus),
+                    ),
+                ],
+            );
+        }
+        _ => {}
+    }
+}
+
+pub fn update_inspector(
+    nodes: Query<(
+        Entity,
+        &NodeTag,
+        &SimNode,
+        Option<&CacheNode>,
+        Option<&TrafficSource>,
+        Option<&DatabaseNode>,
+        Option<&QueueNode>,
+        Option<&ComputeNode>,
+        Option<&FirewallNode>,
+        Option<&CdnNode>,
+        Option<&StorageNode>,
+        Option<&NodeName>,
+        &BoardPos,
+    )>,
+    editor: Res<RuleEditor>,
+    terrain: Res<Terrain>,
+    panel: Option<Single<&mut Visibility, With<InspectorPanel>>>,
+    title: Option<Single<&mut Text, (With<InspectorTitle>, Without<InspectorStats>)>>,
+    stats: Option<Single<&mut Text, (With<InspectorStats>, Without<InspectorTitle>)>>,
+    mut bars: Query<(&ChartBar, &mut Node)>,
+) {
+    let (Some(mut panel), Some(mut title), Some(mut stats)) = (panel, title, stats) else {
+        return;
+    };
+
+    let Some((
+        node_e,
+        tag,
+        sim,
+        cache,
+        source,
+        db,
+        queue,
+        compute,
+        firewall,
+        cdn,
+        storage,
+        name,
+        at,
+    )) = nodes.iter().find(|(_, tag, ..)| tag.selected)
+    else {
+        **panel = Visibility::Hidden;
+        return;
+    };
+    **panel = Visibility::Visible;
+
+    title.0 = match name {
+        Some(name) => format!("{} ({})", name.0, tag.node_type.name()),
+        None => format!("{} #{}", tag.node_type.name(), node_e.index()),
+    };
+
+    let mut body = String::new();
+    if sim.failed {
+        body.push_str("FAILED\n");
+    }
+    if tag.node_type.upgradable() {
+        body.push_str(&format!("Tier {}", tier_name(tag.tier)));
+        if tag.tier < MAX_TIER {
+            body.push_str(&format!(
+                "  next ${}",
+                terrain.upgrade_cost(tag.node_type, tag.tier + 1, *at)
+            ));
+        }
+        body.push('\n');
+    }
+    body.push_str(&format!(
+        "Queue {}/{}  Cap {}/tick\nRecv {}  Served {}\nFwd {}  Drop {}",
+        sim.inbox.len(),
+        sim.queue_limit,
+        sim.capacity,
+        sim.stats.received,
+        sim.stats.served,
+        sim.stats.forwarded,
+        sim.stats.dropped,
+    ));
+
+    if let Some(source) = source {
+        body.push_str(&format!(
+            "\nRate {}/tick  Writes {:.0}%\nKeys {} over {}",
+            source.rate,
+            source.write_ratio * 100.0,
+            source.sampler.dist().label(),
+            source.sampler.keyspace(),
+        ));
+        body.push_str(&format!(
+            "\nStatic {:.0}%  Geo {}\nAttack {} {:.0}%",
+            source.static_ratio * 100.0,
+            source.geo.name(),
+            source.attack_class.name(),
+            source.attack_ratio * 100.0,
+        ));
+    }
+
+    if let Some(cache) = cache {
+        body.push_str(&format!(
+            "\nCache {}/{}  {}\nHit ratio {:.1}%  ({} / {})",
+            cache.store.len(),
+            cache.store.capacity(),
+            cache.store.policy().name(),
+            cache.history.ratio() * 100.0,
+            cache.history.hits,
+            cache.history.total(),
+        ));
+        if cache.store.policy() == EvictionPolicy::Ttl {
+            body.push_str(&format!("\nTTL {} ticks", cache.store.ttl()));
+        }
+        update_history_chart(&cache.history, &mut bars);
+    }
+
+    if let Some(cdn) = cdn {
+        body.push_str(&format!(
+            "\nEdge hit target {:.0}%  Warm {:.0}%\nStatic hit ratio {:.1}%\nOrigin offload {:.1}%\nPurges {}",
+            cdn.hit_fraction * 100.0,
+            cdn.warmth * 100.0,
+            cdn.history.ratio() * 100.0,
+            cdn.offload() * 100.0,
+            cdn.purges,
+        ));
+        update_history_chart(&cdn.history, &mut bars);
+    }
+
+    if let Some(storage) = storage {
+        body.push_str(&format!(
+            "\nObjects {}  {:.2} GB\nBW {}/{} MB/tick\nReplication x{}  Under {}\nReads {}  Writes {}  404 {}",
+            storage.objects.len(),
+            storage.stored_gb(),
+            storage.bandwidth_used_mb,
+            storage.bandwidth_mb,
+            storage.replication_factor,
+            storage.under_replicated,
+            storage.reads,
+            storage.writes,
+            storage.not_found,
+        ));
+    }
+
+    if let Some(db) = db {
+        let stale_pct = if db.reads == 0 {
+            0.0
+        } else {
+            db.stale_reads as f32 / db.reads as f32 * 100.0
+        };
+        body.push_str(&format!(
+            "\n{}  Lag {} ticks\nReads {}  Writes {}\nStale reads {} ({:.1}%)\nBreaches {}",
+            db.role.name(),
+            db.replication_lag,
+            db.reads,
+            db.writes,
+            db.stale_reads,
+            stale_pct,
+            db.breaches,
+        ));
+    }
+
+    if let Some(queue) = queue {
+        body.push_str(&format!(
+            "\nDepth {}/{}  In flight {}\nAcked {}  Redelivered {}\nDead letters {}",
+            queue.depth(),
+            queue.max_depth,
+            queue.leases.len(),
+            queue.acked,
+            queue.redelivered,
+            queue.dead_lettered,
+        ));
+        if queue.full {
+            body.push_str("\nBACKPRESSURE");
+        }
+    }
+
+    if let Some(compute) = compute {
+        body.push_str(&format!("\nConsumer concurrency {}", compute.concurrency));
+    }
+
+    if let Some(firewall) = firewall {
+        body.push_str(&format!(
+            "\nAllowed {}  Blocked {}\nFalse positives {}\nRules:",
+            firewall.allowed, firewall.blocked, firewall.false_positives,
+        ));
+        for (i, rule) in firewall.rules.iter().enumerate() {
+            let marker = if i == editor.cursor { ">" } else { " " };
+            body.push_str(&format!("\n{marker} {}. {}", i + 1, rule.label()));
+        }
+        if firewall.rules.is_empty() {
+            body.push_str("\n  (allow all)");
+        }
+        body.push_str(&format!(
+            "\nDraft: {}",
+            FirewallRule::CATALOG[editor.draft % FirewallRule::CATALOG.len()].label()
+        ));
+    }
+
+    stats.0 = body;
+}
+
+fn update_history_chart(history: &HitHistory, bars: &mut Query<(&ChartBar, &mut Node)>) {
+    // right-align the history so the newest sample is the last bar
+    let offset = HISTORY_LEN - history.samples.len();
+    for (bar, mut node) in bars {
+        let ratio = bar
+            .0
+            .checked_sub(offset)
+            .and_then(|i| history.samples.get(i))
+            .copied()
+            .unwrap_or(0.0);
+        node.height = Val::Percent(ratio * 100.0);
+    }
+}
+
+pub fn inspector_buttons(
+    mut q: Query<
+        (&Interaction, &InspectorButton, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+    nodes: Query<(
+        Entity,
+        &NodeTag,
+        &BoardPos,
+        &SimNode,
+        Option<&DatabaseNode>,
+        Option<&FirewallNode>,
+        Option<&TrafficSource>,
+    )>,
+    mut editor: ResMut<RuleEditor>,
+    mut trace: ResMut<Trace>,
+    mut actions: MessageWriter<PlayerAction>,
+) {
+    for (interaction, action, mut bg) in &mut q {
+        *bg = match *interaction {
+            Interaction::Hovered => BTN_HOVER.into(),
+            Interaction::Pressed => BTN_ACTIVE.into(),
+            Interaction::None => BTN_IDLE.into(),
+        };
+
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let Some((node_e, _, at, sim, db, firewall, source)) =
+            nodes.iter().find(|(_, tag, ..)| tag.selected)
+        else {
+            continue;
+        };
+        let at = *at;
+
+        match *action {
+            InspectorButton::ToggleFailed => {
+                actions.write(PlayerAction::SetFailed {
+                    at,
+                    failed: !sim.failed,
+                });
+            }
+            InspectorButton::Upgrade => {
+                actions.write(PlayerAction::Upgrade { at });
+            }
+            InspectorButton::Trace => {
+                if source.is_some() {
+                    trace.arm(node_e);
+                }
+            }
+            InspectorButton::Failover => {
+                if db.is_some_and(|db| db.role == DbRole::Primary) {
+                    actions.write(PlayerAction::Failover { at });
+                }
+            }
+            InspectorButton::Config(config) => {
+                actions.write(PlayerAction::Configure { at, config });
+            }
+            InspectorButton::DraftPrev => {
+                let n = FirewallRule::CATALOG.len();
+                editor.draft = (editor.draft + n - 1) % n;
+            }
+            InspectorButton::DraftNext => {
+                editor.draft = (editor.draft + 1) % FirewallRule::CATALOG.len();
+            }
+            InspectorButton::RulePrev
+            | InspectorButton::RuleNext
+            | InspectorButton::RuleUp
+            | InspectorButton::RuleDown
+            | InspectorButton::RuleDelete
+            | InspectorButton::RuleAdd => {
+                if let Some(firewall) = firewall
+                    && let Some(config) = edit_rules(&firewall.rules, &mut editor, *action)
+                {
+                    actions.write(PlayerAction::Configure { at, config });
+                }
+            }
+        }
+    }
+}
+
+/// move the cursor and turn rule edits into a config change. the cursor
+/// follows the edit as if it had already been applied.
+fn edit_rules(
+    rules: &[FirewallRule],
+    editor: &mut RuleEditor,
+    action: InspectorButton,
+) -> Option<NodeConfig> {
+    let cursor = editor.cursor.min(rules.len().saturating_sub(1));
+    match action {
+        InspectorButton::RulePrev => editor.cursor = cursor.saturating_sub(1),
+        InspectorButton::RuleNext => {
+            editor.cursor = (cursor + 1).min(rules.len().saturating_sub(1));
+        }
+        InspectorButton::RuleUp if cursor > 0 => {
+            editor.cursor = cursor - 1;
+            return Some(NodeConfig::RuleSwap(cursor, cursor - 1));
+        }
+        InspectorButton::RuleDown if cursor + 1 < rules.len() => {
+            editor.cursor = cursor + 1;
+            return Some(NodeConfig::RuleSwap(cursor, cursor + 1));
+        }
+        InspectorButton::RuleDelete if !rules.is_empty() => {
+            editor.cursor = cursor.min(rules.len().saturating_sub(2));
+            return Some(NodeConfig::RuleDelete(cursor));
+        }
+        InspectorButton::RuleAdd => {
+            // new rules go after the highlighted one
+            let at = if rules.is_empty() { 0 } else { cursor + 1 };
+            editor.cursor = at;
+            return Some(NodeConfig::RuleInsert(
+                at,
+                FirewallRule::CATALOG[editor.draft % FirewallRule::CATALOG.len()],
+            ));
+        }
+        _ => {}
+    }
+    None
+}
@@ -0,0 +1,170 @@
+use super::styles::*;
+
+use crate::camera::components::MainCam;
+use crate::game::actions::NodeRemoved;
+use crate::game::anim::{Anim, AnimKind};
+use crate::game::components::{NodeName, NodeTag};
+use crate::sim::components::{QueueNode, SimNode};
+
+use bevy::prelude::*;
+
+const LABEL_OFFSET_Y: f32 = 0.9;
+const FLOAT_SECS: f32 = 1.2;
+const FLOAT_SPEED: f32 = 0.6;
+const REFUND_COLOR: Color = Color::srgb(0.45, 0.95, 0.55);
+
+/// screen-space text that follows a node on the board.
+#[derive(Component)]
+pub struct NodeLabel {
+    pub node: Entity,
+}
+
+pub fn spawn_node_labels(mut commands: Commands, added: Query<Entity, Added<NodeTag>>) {
+    for node in &added {
+        commands.spawn((
+            Text::new(""),
+            text_style(12.0).0,
+            text_style(12.0).1,
+            Node {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            Visibility::Hidden,
+            NodeLabel { node },
+        ));
+    }
+}
+
+/// screen-space text that rises from a point on the board and fades out. it
+/// follows an anchor entity and goes when the anchor does.
+#[derive(Component)]
+pub struct FloatingText {
+    pub anchor: Entity,
+}
+
+pub fn spawn_refund_text(mut commands: Commands, mut reader: MessageReader<NodeRemoved>) {
+    for removed in reader.read() {
+        if removed.refund == 0 {
+            continue;
+        }
+        let anchor = commands
+            .spawn((
+                Transform::from_translation(
+                    removed.transform.translation + Vec3::Y * LABEL_OFFSET_Y,
+                ),
+                Anim::new(
+                    AnimKind::Drift {
+                        velocity: Vec3::Y * FLOAT_SPEED,
+                    },
+                    FLOAT_SECS,
+                )
+                .despawning(),
+            ))
+            .id();
+        commands.spawn((
+            Text::new(format!("+${}", removed.refund)),
+            text_style(16.0).0,
+            TextColor(REFUND_COLOR),
+            Node {
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            Visibility::Hidden,
+            FloatingText { anchor },
+        ));
+    }
+}
+
+pub fn update_floating_text(
+    mut commands: Commands,
+    camera: Option<Single<(&Camera, &GlobalTransform), With<MainCam>>>,
+    anchors: Query<(&GlobalTransform, &Anim)>,
+    mut texts: Query<(
+        Entity,
+        &FloatingText,
+        &mut TextColor,
+        &mut Node,
+        &mut Visibility,
+    )>,
+) {
+    let Some(camera) = camera else {
+        return;
+    };
+    let (camera, cam_tf) = *camera;
+
+    for (text_e, text, mut color, mut node, mut vis) in &mut texts {
+        let Ok((anchor_tf, anim)) = anchors.get(text.anchor) else {
+            commands.entity(text_e).despawn();
+            continue;
+        };
+        let Ok(pos) = camera.world_to_viewport(cam_tf, anchor_tf.translation()) else {
+            *vis = Visibility::Hidden;
+            continue;
+        };
+        *vis = Visibility::Visible;
+        node.left = Val::Px(pos.x);
+        node.top = Val::Px(pos.y);
+        color.0.set_alpha(1.0 - anim.progress());
+    }
+}
+
+fn label_text(sim: &SimNode, queue: Option<&QueueNode>, name: Option<&NodeName>) -> String {
+    let status = if sim.failed {
+        "DOWN".into()
+    } else {
+        match queue {
+            Some(queue) => format!("{}/{}", queue.depth(), queue.max_depth),
+            None => String::new(),
+        }
+    };
+    match name {
+        Some(name) if status.is_empty() => name.0.clone(),
+        Some(name) => format!("{}\n{status}", name.0),
+        None => status,
+    }
+}
+
+pub fn update_node_labels(
+    mut commands: Commands,
+    camera: Option<Single<(&Camera, &GlobalTransform), With<MainCam>>>,
+    nodes: Query<
+        (
+            &GlobalTransform,
+            &SimNode,
+            Option<&QueueNode>,
+            Option<&NodeName>,
+        ),
+        With<NodeTag>,
+    >,
+    mut labels: Query<(Entity, &NodeLabel, &mut Text, &mut Node, &mut Visibility)>,
+) {
+    let Some(camera) = camera else {
+        return;
+    };
+    let (camera, cam_tf) = *camera;
+
+    for (label_e, label, mut text, mut node, mut vis) in &mut labels {
+        let Ok((node_tf, sim, queue, name)) = nodes.get(label.node) else {
+            commands.entity(label_e).despawn();
+            continue;
+        };
+
+        let content = label_text(sim, queue, name);
+        let anchor = node_tf.translation() + Vec3::Y * LABEL_OFFSET_Y;
+        let Ok(pos) = camera.world_to_viewport(cam_tf, anchor) else {
+            *vis = Visibility::Hidden;
+            continue;
+        };
+        if content.is_empty() {
+            *vis = Visibility::Hidden;
+            continue;
+        }
+
+        *vis = Visibility::Visible;
+        node.left = Val::Px(pos.x);
+        node.top = Val::Px(pos.y);
+        if text.0 != content {
+            text.0 = content;
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use super::styles::*;
+
+use crate::game::components::NodeTag;
+use crate::sim::lint::LintReport;
+
+use bevy::ecs::prelude::ChildSpawnerCommands;
+use bevy::prelude::*;
+
+const LINT_COLOR: Color = Color::srgb(0.95, 0.35, 0.25);
+const LINT_RING_Y: f32 = 0.5;
+const LINT_RING_RADIUS: f32 = 0.4;
+const MAX_LINT_ROWS: usize = 12;
+
+#[derive(Component)]
+pub struct LintPanel;
+
+#[derive(Component)]
+pub struct LintSummary;
+
+#[derive(Component)]
+pub struct LintRows;
+
+/// a listed issue, clicking it selects the first node involved.
+#[derive(Component)]
+pub struct LintRow(pub usize);
+
+pub fn spawn_lint_panel(parent: &mut ChildSpawnerCommands) {
+    parent
+        .spawn((
+            Node {
+                width: Val::Px(300.0),
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(20.0),
+                right: Val::Px(12.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            BackgroundColor(PANEL_BG),
+            LintPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(""),
+                text_style(13.0).0,
+                text_style(13.0).1,
+                LintSummary,
+            ));
+            panel.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(2.0),
+                    ..default()
+                },
+                LintRows,
+            ));
+        });
+}
+
+pub fn update_lint_panel(
+    mut commands: Commands,
+    report: Res<LintReport>,
+    summary: Option<Single<&mut Text, With<LintSummary>>>,
+    rows: Option<Single<Entity, With<LintRows>>>,
+) {
+    let (Some(mut summary), Some(rows)) = (summary, rows) else {
+        return;
+    };
+    // the HUD can spawn after the report last changed
+    if !report.is_changed() && !summary.0.is_empty() {
+        return;
+    }
+
+    summary.0 = match report.issues.len() {
+        0 => "Architecture: no issues".to_string(),
+        n => format!("Architecture: {n} issue(s)"),
+    };
+
+    commands.entity(*rows).despawn_children();
+    commands.entity(*rows).with_children(|list| {
+        for (i, issue) in report.issues.iter().enumerate().take(MAX_LINT_ROWS) {
+            list.spawn((
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(4.0), Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(BTN_IDLE),
+                LintRow(i),
+            ))
+            .with_children(|row| {
+                row.spawn((
+                    Text::new(issue.message.clone()),
+                    text_style(11.0).0,
+                    TextColor(LINT_COLOR),
+                ));
+            });
+        }
+        if report.issues.len() > MAX_LINT_ROWS {
+            list.spawn((
+                Text::new(format!("... {} more", report.issues.len() - MAX_LINT_ROWS)),
+                text_style(11.0).0,
+                text_style(11.0).1,
+            ));
+        }
+    });
+}
+
+pub fn lint_row_buttons(
+    report: Res<LintReport>,
+    mut tags: Query<(Entity, &mut NodeTag)>,
+    mut q: Query<(&Interaction, &LintRow, &mut BackgroundColor), Changed<Interaction>>,
+) {
+    for (interaction, row, mut bg) in &mut q {
+        *bg = match *interaction {
+            Interaction::Hovered => BTN_HOVER.into(),
+            Interaction::Pressed => BTN_ACTIVE.into(),
+            Interaction::None => BTN_IDLE.into(),
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(first) = report.issues.get(row.0).and_then(|i| i.nodes.first()) else {
+            continue;
+        };
+        for (node_e, mut tag) in &mut tags {
+            tag.selected = node_e == *first;
+        }
+    }
+}
+
+/// ring every node that has an open issue.
+pub fn draw_lint_markers(
+    mut gizmos: Gizmos,
+    report: Res<LintReport>,
+    nodes: Query<(Entity, &Transform), With<NodeTag>>,
+) {
+    for (node_e, tf) in &nodes {
+        if !report.flags(node_e) {
+            continue;
+        }
+        let center = Vec3::new(tf.translation.x, LINT_RING_Y, tf.translation.z);
+        gizmos.circle(
+            Isometry3d::new(center, Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+            LINT_RING_RADIUS,
+            LINT_COLOR,
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use super::styles::*;
+
+use crate::game::components::{BoardPos, NodeTag};
+use crate::game::resources::{Economy, Game};
+use crate::game::state::GameState;
+use crate::game::terrain::Terrain;
+use crate::sim::components::QueueNode;
+use crate::sim::export::LastExport;
+use crate::sim::recorder::MetricsLog;
+use crate::sim::resources::{SimClock, SimStats};
+
+use bevy::ecs::prelude::ChildSpawnerCommands;
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct MetricsPanel;
+
+#[derive(Component)]
+pub struct MetricsText;
+
+/// the zone under the cursor, below the run metrics.
+#[derive(Component)]
+pub struct ZoneText;
+
+pub fn spawn_metrics_panel(parent: &mut ChildSpawnerCommands) {
+    parent
+        .spawn((
+            Node {
+                width: Val::Px(220.0),
+                position_type: PositionType::Absolute,
+                top: Val::Px(64.0),
+                left: Val::Px(12.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            BackgroundColor(PANEL_BG),
+            MetricsPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(""),
+                text_style(13.0).0,
+                text_style(13.0).1,
+                MetricsText,
+            ));
+            panel.spawn((
+                Text::new(""),
+                text_style(12.0).0,
+                text_style(12.0).1,
+                Node {
+                    margin: UiRect::top(Val::Px(6.0)),
+                    ..default()
+                },
+                ZoneText,
+            ));
+        });
+}
+
+pub fn update_metrics_panel(
+    clock: Res<SimClock>,
+    stats: Res<SimStats>,
+    economy: Res<Economy>,
+    log: Res<MetricsLog>,
+    last_export: Res<LastExport>,
+    state: Res<State<GameState>>,
+    queues: Query<(Entity, &NodeTag, &QueueNode)>,
+    text: Option<Single<&mut Text, With<MetricsText>>>,
+) {
+    let Some(mut text) = text else {
+        return;
+    };
+
+    let mut body = format!(
+        "Budget ${}\nTick {}\nGenerated {}\nCompleted {}\nDropped {}\nThrottled {}\nAvg latency {:.1} ticks\nBlocked attacks {}\nBreaches {}\nStorage spend ${}\nData loss {} ({} objects)",
+        economy.budget,
+        clock.tick,
+        stats.generated,
+        stats.completed,
+        stats.dropped,
+        stats.throttled,
+        stats.avg_latency_ticks(),
+        stats.blocked_attacks,
+        stats.breaches,
+        stats.storage_spend,
+        stats.data_loss_events,
+        stats.lost_objects,
+    );
+
+    body.push_str(if log.sla_breached() {
+        "\nSLA BREACHED"
+    } else {
+        "\nSLA ok"
+    });
+    match &last_export.0 {
+        Some(Ok(dir)) => body.push_str(&format!("\nExported to {}", dir.display())),
+        Some(Err(err)) => body.push_str(&format!("\nExport failed: {err}")),
+        None => {}
+    }
+    if *state.get() == GameState::GameOver {
+        body.insert_str(0, "GAME OVER: out of budget\n");
+    }
+
+    if !queues.is_empty() {
+        body.push_str("\n\nQueues");
+        for (queue_e, tag, queue) in &queues {
+            body.push_str(&format!(
+                "\n{} #{}  {}/{}{}",
+                tag.node_type.name(),
+                queue_e.index(),
+                queue.depth(),
+                queue.max_depth,
+                if queue.full { "  FULL" } else { "" },
+            ));
+        }
+    }
+
+    text.0 = body;
+}
+
+pub fn update_zone_readout(
+    game: Res<Game>,
+    terrain: Res<Terrain>,
+    placed: Query<(&NodeTag, &BoardPos)>,
+    text: Option<Single<&mut Text, With<ZoneText>>>,
+) {
+    let Some(mut text) = text else {
+        return;
+    };
+    let mut body = match game.hovered_tile {
+        Some(pos) if terrain.is_blocked(pos) => "Blocked terrain".to_string(),
+        Some(pos) => {
+            let zone = terrain.zone_at(pos);
+            match terrain.zone(zone) {
+                Some(info) => {
+                    let power = terrain.power_used(zone, placed.iter());
+                    let limit = info
+                        .power_limit
+                        .map_or("unlimited".to_string(), |limit| format!("{limit} kW"));
+                    format!(
+                        "Zone {} ({})\nCost x{:.2}  Power {} kW / {}",
+                        info.name, info.region, info.cost_multiplier, power, limit
+                    )
+                }
+                None => "No zone".to_string(),
+            }
+        }
+        None => String::new(),
+    };
+    if let Some(seed) = terrain.seed {
+        body.insert_str(0, &format!("Board code {seed}\n"));
+    }
+    if text.0 != body {
+        text.0 = body;
+    }
+}
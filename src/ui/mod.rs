@@ -1,7 +1,17 @@
+pub mod analysis;
+pub mod blueprints;
+pub mod context_menu;
 pub mod plugin;
 pub mod styles;
 pub mod setup_menu;
 pub mod hud;
+pub mod inspector;
+pub mod labels;
+pub mod lint;
+pub mod metrics;
+pub mod replay;
 pub mod systems;
+pub mod text_entry;
+pub mod trace;
 
 pub use plugin::UIPlugin;
\ No newline at end of file
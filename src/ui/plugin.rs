@@ -1,17 +1,37 @@
+use bevy::input::InputSystems;
 use bevy::prelude::*;
 
 use crate::game::state::GameState;
 
-use super::{hud, setup_menu, systems};
+use super::{
+    analysis, blueprints, context_menu, hud, inspector, labels, lint, metrics, replay, setup_menu,
+    systems, text_entry, trace,
+};
 
 pub struct UIPlugin;
 
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
         // Setup menu only in Setup state
-        app.add_systems(OnEnter(GameState::Setup), setup_menu::spawn_setup_menu)
+        app.init_resource::<inspector::RuleEditor>()
+            .init_resource::<context_menu::RenameEntry>()
+            .init_resource::<blueprints::BlueprintNameEntry>()
+            .add_systems(
+                PreUpdate,
+                text_entry::consume_typed_keys.after(InputSystems),
+            )
+            .add_systems(OnEnter(GameState::Setup), setup_menu::spawn_setup_menu)
             .add_systems(OnExit(GameState::Setup), setup_menu::despawn_setup_menu)
-            .add_systems(Update, systems::setup_menu_buttons.run_if(in_state(GameState::Setup)));
+            .add_systems(
+                Update,
+                (
+                    systems::setup_menu_buttons,
+                    setup_menu::update_setup_labels,
+                    setup_menu::update_spec_status,
+                    setup_menu::seed_entry,
+                )
+                    .run_if(in_state(GameState::Setup)),
+            );
 
         // HUD for all states except Setup (Paused/Playing/Fast/GameOver)
         app.add_systems(
@@ -25,7 +45,47 @@ impl Plugin for UIPlugin {
                 systems::hotbar_buttons,
                 systems::node_palette_buttons,
                 systems::sync_hud_visibility,
+                inspector::rebuild_inspector_controls,
+                inspector::update_inspector,
+                inspector::inspector_buttons,
+                metrics::update_metrics_panel,
+                metrics::update_zone_readout,
+                labels::spawn_node_labels,
+                labels::update_node_labels,
+                trace::update_trace_panel,
+                trace::trace_panel_buttons,
+                trace::highlight_trace_path,
+                replay::update_replay_bar,
+                replay::replay_buttons,
+            )
+                .run_if(not(in_state(GameState::Setup))),
+        )
+        .add_systems(
+            Update,
+            (
+                trace::draw_trace_path,
+                lint::update_lint_panel,
+                lint::lint_row_buttons,
+                lint::draw_lint_markers,
+                analysis::update_analysis_panel,
+                analysis::draw_bottleneck_markers,
+                labels::spawn_refund_text,
+                labels::update_floating_text,
+                blueprints::update_blueprint_panel,
+                blueprints::blueprint_panel_buttons,
+                blueprints::blueprint_name_entry,
+            )
+                .run_if(not(in_state(GameState::Setup))),
+        )
+        .add_systems(
+            Update,
+            (
+                context_menu::close_context_menu,
+                context_menu::open_context_menu,
+                context_menu::context_menu_buttons,
+                context_menu::rename_entry,
             )
+                .chain()
                 .run_if(not(in_state(GameState::Setup))),
         );
     }
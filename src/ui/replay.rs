@@ -0,0 +1,153 @@
+use super::styles::*;
+
+use crate::game::constants::{REPLAY_STEP_LARGE, REPLAY_STEP_SMALL};
+use crate::game::replay::{ActionLog, Replay};
+use crate::game::state::GameState;
+use crate::sim::resources::SimClock;
+
+use bevy::ecs::prelude::ChildSpawnerCommands;
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct ReplayBar;
+
+#[derive(Component)]
+pub struct ReplayText;
+
+#[derive(Component, Clone, Copy)]
+pub enum ReplayButton {
+    Start,
+    BackLarge,
+    BackSmall,
+    ForwardSmall,
+    ForwardLarge,
+    Live,
+}
+
+pub fn spawn_replay_bar(parent: &mut ChildSpawnerCommands) {
+    parent
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(60.0),
+                left: Val::Percent(50.0),
+                margin: UiRect::left(Val::Px(-230.0)),
+                width: Val::Px(460.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(PANEL_BG),
+            Visibility::Hidden,
+            ReplayBar,
+        ))
+        .with_children(|bar| {
+            bar.spawn((
+                Text::new(""),
+                text_style(13.0).0,
+                text_style(13.0).1,
+                ReplayText,
+            ));
+            bar.spawn(Node {
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(6.0),
+                ..default()
+            })
+            .with_children(|row| {
+                for (label, action) in [
+                    ("|<", ReplayButton::Start),
+                    ("-100", ReplayButton::BackLarge),
+                    ("-10", ReplayButton::BackSmall),
+                    ("+10", ReplayButton::ForwardSmall),
+                    ("+100", ReplayButton::ForwardLarge),
+                    ("Live", ReplayButton::Live),
+                ] {
+                    row.spawn((
+                        Button,
+                        Node {
+                            width: Val::Px(64.0),
+                            height: Val::Px(26.0),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        BackgroundColor(BTN_IDLE),
+                        action,
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((Text::new(label), text_style(12.0).0, text_style(12.0).1));
+                    });
+                }
+            });
+        });
+}
+
+pub fn update_replay_bar(
+    replay: Res<Replay>,
+    log: Res<ActionLog>,
+    clock: Res<SimClock>,
+    bar: Option<Single<&mut Visibility, With<ReplayBar>>>,
+    text: Option<Single<&mut Text, With<ReplayText>>>,
+) {
+    let (Some(mut bar), Some(mut text)) = (bar, text) else {
+        return;
+    };
+    if !replay.active {
+        **bar = Visibility::Hidden;
+        return;
+    }
+    **bar = Visibility::Visible;
+
+    let status = match (replay.seek, replay.diverged_at) {
+        (Some(target), _) => format!("seeking to {target}"),
+        (None, Some(tick)) => format!("DIVERGED at tick {tick}"),
+        (None, None) => "matches recording".to_string(),
+    };
+    text.0 = format!(
+        "Replay  seed {:x}  tick {} / {}  {}",
+        log.seed, clock.tick, replay.live_tick, status,
+    );
+    if replay.ignored > 0 {
+        text.0.push_str(&format!(
+            "\nthe board can't change while replaying, ignored {} edits. go Live to build",
+            replay.ignored
+        ));
+    }
+}
+
+pub fn replay_buttons(
+    mut replay: ResMut<Replay>,
+    clock: Res<SimClock>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut q: Query<
+        (&Interaction, &ReplayButton, &mut BackgroundColor),
+        (Changed<Interaction>, With<Button>),
+    >,
+) {
+    for (interaction, action, mut bg) in &mut q {
+        *bg = match *interaction {
+            Interaction::Hovered => BTN_HOVER.into(),
+            Interaction::Pressed => BTN_ACTIVE.into(),
+            Interaction::None => BTN_IDLE.into(),
+        };
+
+        if *interaction != Interaction::Pressed || !replay.active {
+            continue;
+        }
+
+        let tick = replay.seek.unwrap_or(clock.tick);
+        match action {
+            ReplayButton::Start => replay.seek_to(0),
+            ReplayButton::BackLarge => replay.seek_to(tick.saturating_sub(REPLAY_STEP_LARGE)),
+            ReplayButton::BackSmall => replay.seek_to(tick.saturating_sub(REPLAY_STEP_SMALL)),
+            ReplayButton::ForwardSmall => replay.seek_to(tick + REPLAY_STEP_SMALL),
+            ReplayButton::ForwardLarge => replay.seek_to(tick + REPLAY_STEP_LARGE),
+            ReplayButton::Live => {
+                replay.go_live();
+                next_state.set(GameState::Paused);
+            }
+        }
+    }
+}
@@ -1,15 +1,30 @@
 use bevy::prelude::*;
 use super::styles::*;
 
+use crate::game::generate::{BoardGen, board_for};
+use crate::game::spawn::SpawnOrder;
+use crate::game::spec::{SpecImport, specs_dir};
+use crate::game::terrain::Terrain;
+
+const SPEC_ERROR_COLOR: Color = Color::srgb(0.95, 0.35, 0.25);
+
 #[derive(Component)]
 pub struct SetupMenuRoot;
 
+/// what the chosen architecture spec builds, or what is wrong with it.
+#[derive(Component)]
+pub struct SetupSpecStatus;
+
 #[derive(Component)]
 pub enum SetupButton {
-    BoardXMinus,
-    BoardXPlus,
-    BoardZMinus,
-    BoardZPlus,
+    Projection,
+    NewSeed,
+    Obstacles,
+    Irregular,
+    Legacy,
+    RandomZones,
+    SpawnOrder,
+    Spec,
     Start,
 }
 
@@ -22,7 +37,7 @@ pub fn spawn_setup_menu(mut commands: Commands) {
         .spawn((
             Node {
                 width: Val::Px(520.0),
-                height: Val::Px(280.0),
+                height: Val::Auto,
                 margin: UiRect::all(Val::Auto),
                 flex_direction: FlexDirection::Column,
                 row_gap: Val::Px(12.0),
@@ -35,39 +50,54 @@ pub fn spawn_setup_menu(mut commands: Commands) {
 
     let title = commands.spawn((Text::new("Setup Game"), text_style(28.0).0, text_style(28.0).1)).id();
 
-    let row_x = spawn_row(&mut commands, "Board X", SetupButton::BoardXMinus, SetupButton::BoardXPlus);
-    let row_z = spawn_row(&mut commands, "Board Z", SetupButton::BoardZMinus, SetupButton::BoardZPlus);
+    let choices = [
+        spawn_choice_row(&mut commands, "Map", SetupButton::Projection),
+        spawn_choice_row(&mut commands, "Board code (type to edit)", SetupButton::NewSeed),
+        spawn_choice_row(&mut commands, "Obstacles", SetupButton::Obstacles),
+        spawn_choice_row(&mut commands, "Irregular shape", SetupButton::Irregular),
+        spawn_choice_row(&mut commands, "Legacy infrastructure", SetupButton::Legacy),
+        spawn_choice_row(&mut commands, "Random zones", SetupButton::RandomZones),
+        spawn_choice_row(&mut commands, "Board intro (any key skips)", SetupButton::SpawnOrder),
+        spawn_choice_row(&mut commands, "Architecture spec", SetupButton::Spec),
+    ];
+    let spec_status = commands.spawn((Text::new(""), text_style(13.0).0, text_style(13.0).1, SetupSpecStatus)).id();
 
     let start_btn = spawn_button(&mut commands, "Start", SetupButton::Start);
 
     commands.entity(panel).add_child(title);
-    commands.entity(panel).add_child(row_x);
-    commands.entity(panel).add_child(row_z);
+    for row in choices {
+        commands.entity(panel).add_child(row);
+    }
+    commands.entity(panel).add_child(spec_status);
     commands.entity(panel).add_child(start_btn);
 
     commands.entity(root).add_child(panel);
 }
 
-fn spawn_row(commands: &mut Commands, label: &str, minus: SetupButton, plus: SetupButton) -> Entity {
+/// a label and one wide button whose text shows the current choice.
+fn spawn_choice_row(commands: &mut Commands, label: &str, action: SetupButton) -> Entity {
     let row = commands
-        .spawn((
-            Node {
-                width: Val::Percent(100.0),
-                height: Val::Px(52.0),
-                justify_content: JustifyContent::SpaceBetween,
-                align_items: AlignItems::Center,
-                ..default()
-            },
-        ))
+        .spawn((Node {
+            width: Val::Percent(100.0),
+            height: Val::Px(40.0),
+            justify_content: JustifyContent::SpaceBetween,
+            align_items: AlignItems::Center,
+            ..default()
+        },))
         .id();
 
-    let label_e = commands.spawn((Text::new(label), text_style(18.0).0, text_style(18.0).1)).id();
-    let minus_btn = spawn_button(commands, "-", minus);
-    let plus_btn = spawn_button(commands, "+", plus);
+    let label_e = commands.spawn((Text::new(label), text_style(16.0).0, text_style(16.0).1)).id();
+    let btn = spawn_button(commands, "", action);
+    commands.entity(btn).insert(Node {
+        width: Val::Px(220.0),
+        height: Val::Px(34.0),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    });
 
     commands.entity(row).add_child(label_e);
-    commands.entity(row).add_child(minus_btn);
-    commands.entity(row).add_child(plus_btn);
+    commands.entity(row).add_child(btn);
     row
 }
 
@@ -92,6 +122,103 @@ fn spawn_button(commands: &mut Commands, text: &str, action: SetupButton) -> Ent
     btn
 }
 
+/// choice buttons show the current setting as their text.
+pub fn update_setup_labels(
+    terrain: Res<Terrain>,
+    board_gen: Res<BoardGen>,
+    spawn_order: Res<SpawnOrder>,
+    spec_import: Res<SpecImport>,
+    buttons: Query<(&SetupButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    let on_off = |on: bool| if on { "On" } else { "Off" }.to_string();
+    for (action, children) in &buttons {
+        let label = match action {
+            SetupButton::Projection => terrain.projection.name().to_string(),
+            SetupButton::NewSeed => board_gen.code().to_string(),
+            SetupButton::Obstacles => on_off(board_gen.obstacles),
+            SetupButton::Irregular => on_off(board_gen.irregular),
+            SetupButton::Legacy => on_off(board_gen.legacy),
+            SetupButton::RandomZones => on_off(board_gen.random_zones),
+            SetupButton::SpawnOrder => spawn_order.name().to_string(),
+            SetupButton::Spec => spec_import.label(),
+            _ => continue,
+        };
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child)
+                && text.0 != label
+            {
+                text.0 = label.clone();
+            }
+        }
+    }
+}
+
+/// the chosen spec is tried on the board the current options generate, the
+/// way the run will build it, so it is clear before starting whether all of
+/// it gets built.
+pub fn update_spec_status(
+    spec_import: Res<SpecImport>,
+    board_gen: Res<BoardGen>,
+    terrain: Res<Terrain>,
+    status: Option<Single<(&mut Text, &mut TextColor, Ref<SetupSpecStatus>)>>,
+) {
+    let Some(mut status) = status else {
+        return;
+    };
+    let (text, color, marker) = &mut *status;
+    if !(spec_import.is_changed() || board_gen.is_changed() || terrain.is_changed() || marker.is_added()) {
+        return;
+    }
+
+    let board = board_for(&board_gen, &terrain);
+    let result = match (spec_import.actions(&board), &spec_import.spec) {
+        (Err(errors), _) => Err(errors.iter().map(|err| err.to_string()).collect::<Vec<_>>().join("\n")),
+        (Ok(_), None) => Ok(format!("Put .yaml or .toml specs in {}", specs_dir().display())),
+        (Ok(_), Some(spec)) => Ok(format!(
+            "{} nodes, {} links, ${}",
+            spec.blueprint.nodes.len(),
+            spec.blueprint.links.len(),
+            spec.blueprint.base_cost()
+        )),
+    };
+    let (content, tint) = match result {
+        Ok(content) => (content, Color::WHITE),
+        Err(content) => (content, SPEC_ERROR_COLOR),
+    };
+    text.0 = content;
+    color.0 = tint;
+}
+
+/// digits typed in the setup menu edit the board code, so a shared board can
+/// be entered, options and all.
+pub fn seed_entry(keys: Res<ButtonInput<KeyCode>>, mut board_gen: ResMut<BoardGen>) {
+    const DIGITS: [KeyCode; 10] = [
+        KeyCode::Digit0,
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    let mut code = board_gen.code();
+    for (digit, key) in DIGITS.iter().enumerate() {
+        if keys.just_pressed(*key) && code < u64::MAX / 100 {
+            code = code * 10 + digit as u64;
+        }
+    }
+    if keys.just_pressed(KeyCode::Backspace) {
+        code /= 10;
+    }
+    if code != board_gen.code() {
+        board_gen.set_code(code);
+    }
+}
+
 pub fn despawn_setup_menu(mut commands: Commands, q: Query<Entity, With<SetupMenuRoot>>) {
     for e in &q {
         commands.entity(e).despawn();
@@ -1,14 +1,27 @@
 use bevy::prelude::*;
 use crate::game::resources::Game;
+use crate::game::spawn::SpawnOrder;
 use crate::game::state::GameState;
+use crate::game::generate::{BoardGen, board_for};
+use crate::game::layout::AutoLayout;
+use crate::game::spec::SpecImport;
+use crate::game::terrain::Terrain;
 use crate::game::types::{ToolType, NodeType};
+use crate::game::replay::{Replay, SaveBoard};
+use crate::sim::messages::ExportMetrics;
+use crate::sim::resources::SimClock;
 
+use super::analysis::AnalysisPanel;
+use super::blueprints::BlueprintPanel;
 use super::hud::*;
 use super::setup_menu::SetupButton;
 use super::styles::*;
 
 pub fn setup_menu_buttons(
-    mut game: ResMut<Game>,
+    mut terrain: ResMut<Terrain>,
+    mut board_gen: ResMut<BoardGen>,
+    mut spawn_order: ResMut<SpawnOrder>,
+    mut spec_import: ResMut<SpecImport>,
     mut next_state: ResMut<NextState<GameState>>,
     mut q: Query<(&Interaction, &SetupButton, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
 ) {
@@ -24,17 +37,33 @@ pub fn setup_menu_buttons(
         }
 
         match action {
-            SetupButton::BoardXMinus => game.board_size_x = game.board_size_x.saturating_sub(1).max(1),
-            SetupButton::BoardXPlus => game.board_size_x += 1,
-            SetupButton::BoardZMinus => game.board_size_z = game.board_size_z.saturating_sub(1).max(1),
-            SetupButton::BoardZPlus => game.board_size_z += 1,
-            SetupButton::Start => next_state.set(GameState::Paused),
+            SetupButton::Projection => terrain.projection = terrain.projection.next(),
+            SetupButton::NewSeed => board_gen.roll_seed(),
+            SetupButton::Obstacles => board_gen.obstacles = !board_gen.obstacles,
+            SetupButton::Irregular => board_gen.irregular = !board_gen.irregular,
+            SetupButton::Legacy => board_gen.legacy = !board_gen.legacy,
+            SetupButton::RandomZones => board_gen.random_zones = !board_gen.random_zones,
+            SetupButton::SpawnOrder => *spawn_order = spawn_order.next(),
+            SetupButton::Spec => spec_import.cycle(),
+            // a spec that won't build keeps the menu up, its status says why
+            SetupButton::Start => {
+                if spec_import.actions(&board_for(&board_gen, &terrain)).is_ok() {
+                    next_state.set(GameState::Paused);
+                }
+            }
         }
     }
 }
 
 pub fn top_bar_buttons(
     mut next_state: ResMut<NextState<GameState>>,
+    mut export: MessageWriter<ExportMetrics>,
+    mut save: MessageWriter<SaveBoard>,
+    mut layout: MessageWriter<AutoLayout>,
+    mut replay: ResMut<Replay>,
+    clock: Res<SimClock>,
+    mut analysis_panel: Option<Single<&mut Visibility, (With<AnalysisPanel>, Without<BlueprintPanel>)>>,
+    mut blueprint_panel: Option<Single<&mut Visibility, With<BlueprintPanel>>>,
     mut q: Query<(&Interaction, &TopBarButton, &mut BackgroundColor), (Changed<Interaction>, With<Button>)>,
 ) {
     for (interaction, action, mut bg) in &mut q {
@@ -53,8 +82,30 @@ pub fn top_bar_buttons(
             TopBarButton::Play => next_state.set(GameState::Playing),
             TopBarButton::Fast => next_state.set(GameState::Fast),
             TopBarButton::Reset => next_state.set(GameState::Setup),
+            TopBarButton::Replay => {
+                if !replay.active {
+                    replay.start(clock.tick);
+                    next_state.set(GameState::Paused);
+                }
+            }
+            TopBarButton::Analysis => {
+                if let Some(panel) = analysis_panel.as_deref_mut() {
+                    panel.toggle_visible_hidden();
+                }
+            }
+            TopBarButton::Blueprints => {
+                if let Some(panel) = blueprint_panel.as_deref_mut() {
+                    panel.toggle_visible_hidden();
+                }
+            }
+            TopBarButton::Layout => {
+                layout.write(AutoLayout);
+            }
+            TopBarButton::Export => {
+                export.write(ExportMetrics);
+            }
             TopBarButton::Save => {
-                // TODO: emit Save event
+                save.write(SaveBoard);
             }
             TopBarButton::Quit => {
                 // TODO: emit AppExit or go to Setup/GameOver
@@ -106,11 +157,11 @@ pub fn node_palette_buttons(
 
 pub fn sync_hud_visibility(
     game: Res<Game>,
-    nodebar: Option<Single<&Visibility, With<NodePaletteBar>>>,
+    nodebar: Option<Single<&mut Visibility, With<NodePaletteBar>>>,
 ) {
     let show_nodes = matches!(game.tool_selection, ToolType::Add(_));
 
     if let Some(mut vis) = nodebar {
-        *vis = if show_nodes { &Visibility::Visible } else { &Visibility::Hidden };
+        **vis = if show_nodes { Visibility::Visible } else { Visibility::Hidden };
     }
 }
@@ -0,0 +1,57 @@
+use super::blueprints::BlueprintNameEntry;
+use super::context_menu::RenameEntry;
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+/// what finished a line of typing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EntryEnd {
+    /// Enter, keep the text.
+    Submit,
+    /// Escape, give up.
+    Cancel,
+}
+
+/// type key presses into `text`, up to `max_len` characters. stops at Enter
+/// or Escape and says which, keys after that are dropped.
+pub fn type_text<'a>(
+    text: &mut String,
+    max_len: usize,
+    keys: impl IntoIterator<Item = &'a KeyboardInput>,
+) -> Option<EntryEnd> {
+    for key in keys {
+        if !key.state.is_pressed() {
+            continue;
+        }
+        match &key.logical_key {
+            Key::Enter => return Some(EntryEnd::Submit),
+            Key::Escape => return Some(EntryEnd::Cancel),
+            Key::Backspace => {
+                text.pop();
+            }
+            Key::Space => text.push(' '),
+            Key::Character(chars) => {
+                for c in chars.chars().filter(|c| !c.is_control()) {
+                    if text.chars().count() < max_len {
+                        text.push(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// while a name is being typed the keys are text, not shortcuts, so nothing
+/// else gets to see them.
+pub fn consume_typed_keys(
+    rename: Res<RenameEntry>,
+    blueprint_name: Res<BlueprintNameEntry>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+) {
+    if rename.target.is_some() || blueprint_name.active {
+        keys.reset_all();
+    }
+}
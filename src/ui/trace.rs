@@ -0,0 +1,260 @@
+use super::styles::*;
+
+use crate::game::components::NodeTag;
+use crate::game::resources::RenderAssets;
+use crate::sim::resources::{SimClock, Trace};
+
+use bevy::ecs::prelude::ChildSpawnerCommands;
+use bevy::prelude::*;
+
+const WATERFALL_WIDTH: f32 = 200.0;
+const WATERFALL_MAX_TICK_PX: f32 = 12.0;
+const ROW_HEIGHT: f32 = 12.0;
+const QUEUED_COLOR: Color = Color::srgb(0.9, 0.65, 0.2);
+const SERVICE_COLOR: Color = Color::srgb(0.35, 0.75, 0.45);
+const TRACE_PATH_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+const TRACE_PATH_Y: f32 = 0.55;
+
+#[derive(Component)]
+pub struct TracePanel;
+
+#[derive(Component)]
+pub struct TraceSummary;
+
+/// one row per span, rebuilt whenever the trace changes.
+#[derive(Component)]
+pub struct TraceRows;
+
+#[derive(Component)]
+pub struct TraceClearButton;
+
+pub fn spawn_trace_panel(parent: &mut ChildSpawnerCommands) {
+    parent
+        .spawn((
+            Node {
+                width: Val::Px(340.0),
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(20.0),
+                left: Val::Px(12.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                ..default()
+            },
+            BackgroundColor(PANEL_BG),
+            Visibility::Hidden,
+            TracePanel,
+        ))
+        .with_children(|panel| {
+            panel
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    ..default()
+                })
+                .with_children(|header| {
+                    header.spawn((
+                        Text::new(""),
+                        text_style(13.0).0,
+                        text_style(13.0).1,
+                        TraceSummary,
+                    ));
+                    header
+                        .spawn((
+                            Button,
+                            Node {
+                                width: Val::Px(56.0),
+                                height: Val::Px(22.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            BackgroundColor(BTN_IDLE),
+                            TraceClearButton,
+                        ))
+                        .with_children(|btn| {
+                            btn.spawn((Text::new("Clear"), text_style(12.0).0, text_style(12.0).1));
+                        });
+                });
+            panel.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(3.0),
+                    ..default()
+                },
+                TraceRows,
+            ));
+        });
+}
+
+pub fn update_trace_panel(
+    mut commands: Commands,
+    trace: Res<Trace>,
+    clock: Res<SimClock>,
+    tags: Query<&NodeTag>,
+    panel: Option<Single<&mut Visibility, With<TracePanel>>>,
+    summary: Option<Single<&mut Text, With<TraceSummary>>>,
+    rows: Option<Single<Entity, With<TraceRows>>>,
+) {
+    let (Some(mut panel), Some(mut summary), Some(rows)) = (panel, summary, rows) else {
+        return;
+    };
+
+    if trace.armed.is_none() && trace.outcome.is_none() {
+        **panel = Visibility::Hidden;
+        return;
+    }
+    **panel = Visibility::Visible;
+
+    if !trace.is_changed() {
+        return;
+    }
+
+    let Some(outcome) = trace.outcome else {
+        summary.0 = "Trace armed, waiting for a request".to_string();
+        commands.entity(*rows).despawn_children();
+        return;
+    };
+
+    let end = trace
+        .spans
+        .last()
+        .and_then(|span| span.left)
+        .unwrap_or(clock.tick);
+    let total = end.saturating_sub(trace.started_at) + 1;
+    summary.0 = format!(
+        "Trace {}  {} hops  {} ticks",
+        outcome.name(),
+        trace.spans.len().saturating_sub(1),
+        total,
+    );
+
+    let tick_px = (WATERFALL_WIDTH / total as f32).min(WATERFALL_MAX_TICK_PX);
+    commands.entity(*rows).despawn_children();
+    commands.entity(*rows).with_children(|list| {
+        for span in &trace.spans {
+            let name = tags
+                .get(span.node)
+                .map_or("(deleted)", |tag| tag.node_type.name());
+            let queued = span.queued_ticks(clock.tick);
+            let offset = span.arrived - trace.started_at;
+
+            list.spawn(Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(6.0),
+                ..default()
+            })
+            .with_children(|row| {
+                row.spawn((
+                    Node {
+                        width: Val::Px(96.0),
+                        ..default()
+                    },
+                    Text::new(format!("{} #{} {}t", name, span.node.index(), queued)),
+                    text_style(11.0).0,
+                    text_style(11.0).1,
+                ));
+                row.spawn(Node {
+                    width: Val::Px(WATERFALL_WIDTH),
+                    height: Val::Px(ROW_HEIGHT),
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                })
+                .with_children(|bar| {
+                    bar.spawn(Node {
+                        width: Val::Px(offset as f32 * tick_px),
+                        ..default()
+                    });
+                    bar.spawn((
+                        Node {
+                            width: Val::Px(queued as f32 * tick_px),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(QUEUED_COLOR),
+                    ));
+                    if span.left.is_some() {
+                        bar.spawn((
+                            Node {
+                                width: Val::Px(tick_px.max(2.0)),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            BackgroundColor(SERVICE_COLOR),
+                        ));
+                    }
+                });
+            });
+        }
+    });
+}
+
+pub fn trace_panel_buttons(
+    mut trace: ResMut<Trace>,
+    mut q: Query<
+        (&Interaction, &mut BackgroundColor),
+        (Changed<Interaction>, With<TraceClearButton>),
+    >,
+) {
+    for (interaction, mut bg) in &mut q {
+        *bg = match *interaction {
+            Interaction::Hovered => BTN_HOVER.into(),
+            Interaction::Pressed => BTN_ACTIVE.into(),
+            Interaction::None => BTN_IDLE.into(),
+        };
+        if *interaction == Interaction::Pressed {
+            trace.clear();
+        }
+    }
+}
+
+/// swap nodes on the traced path to their vfx material and restore the rest.
+pub fn highlight_trace_path(
+    trace: Res<Trace>,
+    render_assets: Option<Res<RenderAssets>>,
+    mut highlighted: Local<Vec<Entity>>,
+    mut nodes: Query<(&NodeTag, &mut MeshMaterial3d<StandardMaterial>)>,
+) {
+    let Some(render_assets) = render_assets else {
+        return;
+    };
+
+    let path: Vec<Entity> = trace.path().collect();
+    for node_e in highlighted.drain(..) {
+        if path.contains(&node_e) {
+            continue;
+        }
+        if let Ok((tag, mut mat)) = nodes.get_mut(node_e) {
+            let (_mesh, base, _vfx) = render_assets.get_node_assets(tag.node_type);
+            mat.0 = base;
+        }
+    }
+
+    for node_e in &path {
+        let Ok((tag, mut mat)) = nodes.get_mut(*node_e) else {
+            continue;
+        };
+        let (_mesh, _mat, vfx) = render_assets.get_node_assets(tag.node_type);
+        if mat.0 != vfx {
+            mat.0 = vfx;
+        }
+    }
+    *highlighted = path;
+}
+
+pub fn draw_trace_path(
+    mut gizmos: Gizmos,
+    trace: Res<Trace>,
+    positions: Query<&Transform, With<NodeTag>>,
+) {
+    let points: Vec<Vec3> = trace
+        .path()
+        .filter_map(|node_e| positions.get(node_e).ok())
+        .map(|tf| Vec3::new(tf.translation.x, TRACE_PATH_Y, tf.translation.z))
+        .collect();
+    for pair in points.windows(2) {
+        gizmos.arrow(pair[0], pair[1], TRACE_PATH_COLOR);
+    }
+}